@@ -1,6 +1,8 @@
+use crate::card_diff::ExistingCard;
+use crate::pipeline::AnalysisPipeline;
 use crate::raw_card_analyzer::{AnalysisError, RawCardAnalyzer, to_half};
+use crate::raw_card_fields::{COLOR_UNKNOWN, RawCardFields};
 use chrono::{DateTime, Utc};
-use color::convert_cost;
 use feature::feature::{BurstHashSetToBits, HashSetToBits};
 use feature::{BurstFeature, CardFeature, create_burst_detect_patterns, create_detect_patterns};
 use models::card::CreateCard;
@@ -15,6 +17,8 @@ use std::sync::Arc;
 pub struct CreateCardWithKlass {
     pub create_card: CreateCard,
     pub detected_klasses: Vec<(String, Option<String>, Option<String>)>,
+    /// 未知の項目名などサイトのレイアウト変更が疑われる点
+    pub warnings: Vec<String>,
 }
 
 /// RawCardDb with product_id included
@@ -68,350 +72,162 @@ impl SimpleRawCardAnalyzer {
         Self
     }
 
-    /// HTMLからカード種別を簡易検出する
+    /// カード種別を検出する
     /// データベースのwix_cardtypeテーブルのIDに対応
-    fn detect_card_type_from_html(&self, html: &str) -> i32 {
-        // カード種別セクションを探してそこから種別を抽出
-        let card_type_text = if let Some(start) = html.find("<dt>カード種類</dt>") {
-            let after_dt = &html[start..];
-            if let Some(dd_start) = after_dt.find("<dd>") {
-                if let Some(dd_end) = after_dt.find("</dd>") {
-                    &after_dt[dd_start + 4..dd_end]
-                } else {
-                    ""
-                }
-            } else {
-                ""
-            }
-        } else {
-            // フォールバック: 旧形式のHTML構造を試す
-            if let Some(start) = html.find("<dt>種類</dt>") {
-                let after_dt = &html[start..];
-                if let Some(dd_start) = after_dt.find("<dd>") {
-                    if let Some(dd_end) = after_dt.find("</dd>") {
-                        &after_dt[dd_start + 4..dd_end]
+    pub(crate) fn detect_card_type(&self, fields: &RawCardFields, html: &str) -> i32 {
+        match &fields.card_type {
+            Some(card_type_text) => card_type_id_from_text(card_type_text),
+            None => {
+                // カード種別の項目が見つからない場合は全体のHTMLから検索（フォールバック）
+                if html.contains("ルリグ") && !html.contains("ルリグトラッシュ") {
+                    if html.contains("アシスト") {
+                        3 // アシストルリグ (DB ID: 3)
                     } else {
-                        ""
-                    }
-                } else {
-                    ""
-                }
-            } else {
-                ""
-            }
-        };
-
-        // println!("DEBUG: Extracted card type text: '{}'", card_type_text);
-
-        // 抽出したテキストから種別を判定
-        if card_type_text.contains("ルリグ") {
-            if card_type_text.contains("アシスト") {
-                3 // アシストルリグ (DB ID: 3)
-            } else {
-                1 // ルリグ (DB ID: 1)
-            }
-        } else if card_type_text.contains("アーツ") {
-            if card_type_text.contains("クラフト") {
-                9 // クラフトアーツ (DB ID: 9)
-            } else {
-                2 // アーツ (DB ID: 2)
-            }
-        } else if card_type_text.contains("キー") {
-            8 // キー (DB ID: 8)
-        } else if card_type_text.contains("シグニ") {
-            if card_type_text.contains("クラフト") {
-                10 // クラフトシグニ (DB ID: 10)
-            } else {
-                5 // シグニ (DB ID: 5)
-            }
-        } else if card_type_text.contains("スペル") {
-            if card_type_text.contains("クラフト") {
-                11 // クラフトスペル (DB ID: 11)
-            } else {
-                6 // スペル (DB ID: 6)
-            }
-        } else if card_type_text.contains("レゾナ") {
-            if card_type_text.contains("クラフト") {
-                14 // クラフトレゾナ (DB ID: 14)
-            } else {
-                7 // レゾナ (DB ID: 7)
-            }
-        } else if card_type_text.contains("ピース") {
-            if card_type_text.contains("リレー") {
-                12 // リレーピース (DB ID: 12)
-            } else if card_type_text.contains("クラフト") {
-                13 // クラフトピース (DB ID: 13)
-            } else {
-                4 // ピース (DB ID: 4)
-            }
-        } else if card_type_text.contains("トークン") {
-            15 // トークン (DB ID: 15)
-        } else if card_type_text.contains("コイン") {
-            16 // コイン (DB ID: 16)
-        } else {
-            // カード種別セクションが見つからない場合は全体のHTMLから検索（フォールバック）
-            // println!("DEBUG: Card type section not found, falling back to full HTML search");
-            if html.contains("ルリグ") && !html.contains("ルリグトラッシュ") {
-                if html.contains("アシスト") {
-                    3 // アシストルリグ (DB ID: 3)
-                } else {
-                    1 // ルリグ (DB ID: 1)
-                }
-            } else if html.contains("アーツ") {
-                if html.contains("クラフト") {
-                    9 // クラフトアーツ (DB ID: 9)
-                } else {
-                    2 // アーツ (DB ID: 2)
-                }
-            } else {
-                0 // Unknown
-            }
-        }
-    }
-
-    /// HTMLから色を検出する
-    fn detect_color_from_html(&self, html: &str) -> i32 {
-        let mut color = 0;
-
-        // <dt>色</dt><dd>○○</dd> パターンで色を検出
-        if let Some(color_start) = html.find("<dt>色</dt>") {
-            let after_dt = &html[color_start + 11..]; // "<dt>色</dt>"の後
-            if let Some(dd_start) = after_dt.find("<dd>") {
-                if let Some(dd_end) = after_dt.find("</dd>") {
-                    let color_text = &after_dt[dd_start + 4..dd_end];
-
-                    // 各色をチェック（shared/color/lib.rsの定義に合わせる）
-                    if color_text.contains("白") {
-                        color |= 1 << 1; // White
-                    }
-                    if color_text.contains("青") {
-                        color |= 1 << 2; // Blue
-                    }
-                    if color_text.contains("赤") {
-                        color |= 1 << 3; // Red
-                    }
-                    if color_text.contains("黒") {
-                        color |= 1 << 4; // Black
+                        1 // ルリグ (DB ID: 1)
                     }
-                    if color_text.contains("緑") {
-                        color |= 1 << 5; // Green
-                    }
-                    if color_text.contains("無") {
-                        color = 1 << 6; // Colorless
+                } else if html.contains("アーツ") {
+                    if html.contains("クラフト") {
+                        9 // クラフトアーツ (DB ID: 9)
+                    } else {
+                        2 // アーツ (DB ID: 2)
                     }
+                } else {
+                    0 // Unknown
                 }
             }
         }
-
-        // 何も検出されない場合はデフォルトで不明
-        if color == 0 {
-            color = 1 << 7; // Unknown
-        }
-
-        color
     }
 
-    /// HTMLから共通のdd要素を抽出するヘルパーメソッド
-    fn extract_dd_elements(&self, html: &str) -> Vec<String> {
-        use regex::Regex;
-
-        // dt-dd ペアを正しく抽出するための正規表現（複数行対応）
-        let dt_dd_regex = Regex::new(r"(?s)<dt>([^<]+)</dt>\s*<dd[^>]*>(.*?)</dd>").unwrap();
-        let mut dd_elements = Vec::new();
-
-        // まず最初のdt要素（カード種類または種類）を見つける
-        let start_patterns = ["<dt>カード種類</dt>", "<dt>種類</dt>"];
-        let mut search_start = 0;
-
-        for pattern in &start_patterns {
-            if let Some(index) = html.find(pattern) {
-                search_start = index;
-                break;
-            }
-        }
-
-        // search_start以降でdt-ddペアを抽出
-        let search_html = &html[search_start..];
-
-        for caps in dt_dd_regex.captures_iter(search_html) {
-            if let (Some(_dt_content), Some(dd_content)) = (caps.get(1), caps.get(2)) {
-                let dd_text = dd_content.as_str().trim();
-                dd_elements.push(dd_text.to_string());
-            }
-        }
-        dd_elements
+    /// 色を検出する（読み取れなければ不明）
+    pub(crate) fn detect_color(&self, fields: &RawCardFields) -> i32 {
+        fields
+            .color
+            .filter(|color| *color != 0)
+            .unwrap_or(COLOR_UNKNOWN)
     }
 
-    /// HTMLからレベル情報を検出する（dd[3]）
-    pub fn detect_level_from_html(&self, html: &str) -> Option<String> {
-        let dd_elements = self.extract_dd_elements(html);
-
-        if dd_elements.len() > 3 {
-            let level_text = dd_elements[3].trim();
-            if !level_text.is_empty() && level_text != "-" {
-                Some(level_text.to_string())
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+    /// レベルを検出する
+    pub fn detect_level(&self, fields: &RawCardFields) -> Option<i32> {
+        fields.level
     }
 
-    /// HTMLからリミット情報を検出する（dd[6]、ルリグ、アシストルリグのみ）
-    pub fn detect_limit_from_html(&self, html: &str) -> (Option<String>, HashSet<CardFeature>) {
-        let dd_elements = self.extract_dd_elements(html);
-
+    /// リミットを検出する（ルリグ、アシストルリグのみ）
+    pub fn detect_limit(&self, fields: &RawCardFields) -> (Option<i32>, HashSet<CardFeature>) {
         let mut feature_set = HashSet::new();
 
-        if dd_elements.is_empty() {
+        // ルリグカードかどうかをチェック
+        let card_type = fields.card_type.as_deref().unwrap_or_default();
+        if !card_type.contains("ルリグ") {
             return (None, feature_set);
         }
 
-        // ルリグカードかどうかをチェック
-        let card_type = &dd_elements[0];
-        let is_lrig = card_type.contains("ルリグ") || card_type.contains("アシストルリグ");
-
-        if is_lrig && dd_elements.len() > 6 {
-            let limit_text = dd_elements[6].trim();
-            if !limit_text.is_empty() && limit_text != "-" {
-                if card_type.contains("アシストルリグ") && limit_text != "0" {
-                    feature_set.insert(CardFeature::EnhanceLimit);
-                }
-
-                (Some(limit_text.to_string()), feature_set)
-            } else {
-                (None, feature_set)
-            }
-        } else {
-            (None, feature_set)
+        if card_type.contains("アシストルリグ") && fields.limit.is_some_and(|limit| limit != 0)
+        {
+            feature_set.insert(CardFeature::EnhanceLimit);
         }
-    }
-
-    /// HTMLからパワー情報を検出する（dd[7]、シグニ/クラフトのみ）
-    pub fn detect_power_from_html(&self, html: &str) -> Option<String> {
-        let dd_elements = self.extract_dd_elements(html);
 
-        if dd_elements.is_empty() {
-            return None;
-        }
+        (fields.limit, feature_set)
+    }
 
+    /// パワーを検出する（シグニ/クラフト/レゾナのみ）
+    pub fn detect_power(&self, fields: &RawCardFields) -> Option<String> {
         // シグニ、クラフト、レゾナカードかどうかをチェック
-        let card_type = &dd_elements[0];
+        let card_type = fields.card_type.as_deref().unwrap_or_default();
         let has_power = card_type.contains("シグニ")
             || card_type.contains("クラフト")
             || card_type.contains("レゾナ");
 
-        if has_power && dd_elements.len() > 7 {
-            let power_text = dd_elements[7].trim();
-            if !power_text.is_empty() && power_text != "-" {
-                Some(power_text.to_string())
-            } else {
-                None
-            }
+        if has_power {
+            fields.power.map(|power| power.to_string())
         } else {
             None
         }
     }
 
-    /// HTMLから使用タイミング情報を検出する（dd[9]、アーツ/ピース/アシストルリグ）
-    pub fn detect_timing_from_html(&self, html: &str) -> Option<String> {
-        let dd_elements = self.extract_dd_elements(html);
-
-        if dd_elements.is_empty() {
-            return None;
-        }
-
+    /// 使用タイミングを検出する（アーツ/ピース/アシストルリグ）
+    pub fn detect_timing(&self, fields: &RawCardFields) -> Option<String> {
         // 使用タイミングを持つカードタイプをチェック
-        let card_type = &dd_elements[0];
-        let has_timing = card_type.contains("アーツ") 
-                      || card_type.contains("ピース") 
-                      || card_type.contains("アシストルリグ");
-
-        if has_timing && dd_elements.len() > 9 {
-            let timing_text = dd_elements[9].trim();
-            
-            // HTMLタグを除去してクリーンなテキストにする
-            let clean_timing_text = timing_text
-                .replace("<br>", "")
-                .replace("<br/>", "")
-                .replace("<br />", "")
-                .replace('\n', "")
-                .replace('\r', "")
-                .trim()
-                .to_string();
-            
-            if !clean_timing_text.is_empty() && clean_timing_text != "-" {
-                Some(clean_timing_text)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    }
-
-    /// HTMLからリミット消費情報を検出する（dd[8]、将来対応）
-    pub fn detect_limit_ex_from_html(&self, html: &str) -> Option<String> {
-        let dd_elements = self.extract_dd_elements(html);
+        let card_type = fields.card_type.as_deref().unwrap_or_default();
+        let has_timing = card_type.contains("アーツ")
+            || card_type.contains("ピース")
+            || card_type.contains("アシストルリグ");
 
-        if dd_elements.len() > 8 {
-            let limit_ex_text = dd_elements[8].trim();
-            if !limit_ex_text.is_empty() && limit_ex_text != "-" {
-                Some(limit_ex_text.to_string())
-            } else {
-                None
-            }
+        if has_timing {
+            fields.timing.clone()
         } else {
             None
         }
     }
 
-    /// HTMLからストーリー情報を検出する（dd[11]のdissonaアイコンチェック）
-    pub fn detect_story_from_html(&self, html: &str) -> (Option<String>, HashSet<CardFeature>) {
-        let dd_elements = self.extract_dd_elements(html);
+    /// リミット消費を検出する
+    pub fn detect_limit_ex(&self, fields: &RawCardFields) -> Option<i32> {
+        fields.limit_ex
+    }
 
+    /// ストーリーを検出する（dissonaアイコンチェック）
+    pub fn detect_story(&self, fields: &RawCardFields) -> (Option<String>, HashSet<CardFeature>) {
         let mut s: HashSet<CardFeature> = HashSet::new();
+        if fields.story.as_deref() == Some("dissona") {
+            s.insert(CardFeature::Dissona);
+        }
+        (fields.story.clone(), s)
+    }
 
-        if dd_elements.len() > 11 {
-            let story_html = &dd_elements[11];
+    /// フォーマットを検出する
+    /// 対応値: 7（オールスター）, 3（キーセレクション）, 1（ディーヴァセレクション）
+    pub fn detect_format(&self, fields: &RawCardFields) -> i32 {
+        // アイコンがなければオールスター（111）
+        fields.format.unwrap_or(7)
+    }
 
-            // dd[11]内にdissonaアイコンがあるかチェック
-            if story_html.contains("icon_txt_dissona.png") {
-                s.insert(CardFeature::Dissona);
-                (Some("dissona".to_string()), s)
-            } else {
-                (None, s)
-            }
+    /// コストを検出する（ルリグはグロウコスト、それ以外はコスト）
+    ///
+    /// 「《白》×１《青》×２」→「w1u2」形式の変換は項目の読み取り時に済んでいる
+    pub fn detect_cost(&self, fields: &RawCardFields) -> Option<String> {
+        let is_lrig = fields
+            .card_type
+            .as_deref()
+            .is_some_and(|card_type| card_type.contains("ルリグ"));
+        if is_lrig {
+            fields.grow_cost.clone()
         } else {
-            (None, s)
+            fields.cost.clone()
         }
     }
 
-    /// HTMLからフォーマット情報を検出する（dd[10]）
-    /// 包含関係: オールスター（空文字）> キーセレクション > ディーヴァセレクション
-    /// 対応値: 7（オールスター）, 3（キーセレクション）, 1（ディーヴァセレクション）
-    pub fn detect_format_from_html(&self, html: &str) -> i32 {
-        let dd_elements = self.extract_dd_elements(html);
+    // 以下はHTMLを直接受け取る版（1枚を単発で調べる用）。
+    // パイプラインはカードごとに1回だけパースした `RawCardFields` を使う
 
-        if dd_elements.len() > 10 {
-            let format_html = &dd_elements[10];
+    /// HTMLからレベル情報を検出する
+    pub fn detect_level_from_html(&self, html: &str) -> Option<String> {
+        self.detect_level(&RawCardFields::from_html(html))
+            .map(|level| level.to_string())
+    }
 
-            // フォーマットアイコンをチェック
-            let has_key_icon = format_html.contains("icon_txt_format_key.png");
-            let has_diva_icon = format_html.contains("icon_txt_format_diva.png");
+    /// HTMLからリミット情報を検出する（ルリグ、アシストルリグのみ）
+    pub fn detect_limit_from_html(&self, html: &str) -> (Option<String>, HashSet<CardFeature>) {
+        let (limit, features) = self.detect_limit(&RawCardFields::from_html(html));
+        (limit.map(|limit| limit.to_string()), features)
+    }
 
-            // 包含関係に基づいて値を決定
-            if has_diva_icon {
-                1 // ディーヴァセレクション（001）
-            } else if has_key_icon {
-                3 // キーセレクション（011）
-            } else {
-                7 // オールスター（111）- デフォルト（空文字の場合）
-            }
-        } else {
-            7 // デフォルトはオールスター
-        }
+    /// HTMLからパワー情報を検出する（シグニ/クラフト/レゾナのみ）
+    pub fn detect_power_from_html(&self, html: &str) -> Option<String> {
+        self.detect_power(&RawCardFields::from_html(html))
+    }
+
+    /// HTMLから使用タイミング情報を検出する（アーツ/ピース/アシストルリグ）
+    pub fn detect_timing_from_html(&self, html: &str) -> Option<String> {
+        self.detect_timing(&RawCardFields::from_html(html))
+    }
+
+    /// HTMLからストーリー情報を検出する（dissonaアイコンチェック）
+    pub fn detect_story_from_html(&self, html: &str) -> (Option<String>, HashSet<CardFeature>) {
+        self.detect_story(&RawCardFields::from_html(html))
+    }
+
+    /// HTMLからコスト情報を検出する（ルリグはグロウコスト、それ以外はコスト）
+    pub fn detect_cost_from_html(&self, html: &str) -> Option<String> {
+        self.detect_cost(&RawCardFields::from_html(html))
     }
 
     /// 公式サイトの表記ゆれ・誤字を修正する
@@ -447,74 +263,57 @@ impl SimpleRawCardAnalyzer {
         (normalized_cat1, normalized_cat2, normalized_cat3)
     }
 
-    /// HTMLからKlass情報を検出する（dd[1]のカードタイプから）
-    pub fn detect_klass_from_html(
+    /// Klass情報を検出する（カードタイプ欄から）
+    pub fn detect_klass(
         &self,
-        html: &str,
+        fields: &RawCardFields,
     ) -> Vec<(String, Option<String>, Option<String>)> {
-        let dd_elements = self.extract_dd_elements(html);
         let mut klasses = Vec::new();
 
-        // dd[1]にカードタイプがある（シグニの場合は種族情報を含む）
-        if dd_elements.len() > 1 {
-            let card_type_text = &dd_elements[1];
-
-            // <br>タグで分割してそれぞれ処理
-            let lines: Vec<&str> = card_type_text
-                .split("<br>")
-                .flat_map(|line| line.split("<br/>"))
-                .flat_map(|line| line.split("<br />"))
-                .collect();
-
-            for line in lines {
-                let clean_text = line.trim();
-                if clean_text.is_empty() || clean_text == "-" {
-                    continue;
-                }
-
-                // パターン1: "奏羅：宇宙" のような形式（半角コロン）
-                if let Some(colon_pos) = clean_text.find(':') {
-                    let cat1 = clean_text[..colon_pos].trim();
-                    let rest = clean_text[colon_pos + 1..].trim();
-
-                    // パターン1a: "空獣／地獣" のような複数cat2を持つ場合
-                    if let Some(slash_pos) = rest.find('/') {
-                        let cat2 = rest[..slash_pos].trim();
-                        let cat3 = rest[slash_pos + 1..].trim();
-                        klasses.push((
-                            cat1.to_string(),
-                            Some(cat2.to_string()),
-                            Some(cat3.to_string()),
-                        ));
-                    } else {
-                        // パターン1b: "奏羅：宇宙" のような単一cat2の場合
-                        klasses.push((cat1.to_string(), Some(rest.to_string()), None));
-                    }
-                }
-                // パターン2: 全角コロン "奏羅：宇宙" のような形式
-                else if let Some(colon_pos) = clean_text.find('：') {
-                    let cat1 = clean_text[..colon_pos].trim();
-                    let rest = clean_text[colon_pos + '：'.len_utf8()..].trim();
-
-                    // パターン2a: "空獣／地獣" のような複数cat2を持つ場合
-                    if let Some(slash_pos) = rest.find('/') {
-                        let cat2 = rest[..slash_pos].trim();
-                        let cat3 = rest[slash_pos + 1..].trim();
-                        klasses.push((
-                            cat1.to_string(),
-                            Some(cat2.to_string()),
-                            Some(cat3.to_string()),
-                        ));
-                    } else {
-                        // パターン2b: "奏羅：宇宙" のような単一cat2の場合
-                        klasses.push((cat1.to_string(), Some(rest.to_string()), None));
-                    }
+        // カードタイプ欄（シグニの場合は種族情報を含む）の各行を処理
+        for clean_text in fields.klass.iter().map(|line| line.as_str()) {
+            // パターン1: "奏羅：宇宙" のような形式（半角コロン）
+            if let Some(colon_pos) = clean_text.find(':') {
+                let cat1 = clean_text[..colon_pos].trim();
+                let rest = clean_text[colon_pos + 1..].trim();
+
+                // パターン1a: "空獣／地獣" のような複数cat2を持つ場合
+                if let Some(slash_pos) = rest.find('/') {
+                    let cat2 = rest[..slash_pos].trim();
+                    let cat3 = rest[slash_pos + 1..].trim();
+                    klasses.push((
+                        cat1.to_string(),
+                        Some(cat2.to_string()),
+                        Some(cat3.to_string()),
+                    ));
+                } else {
+                    // パターン1b: "奏羅：宇宙" のような単一cat2の場合
+                    klasses.push((cat1.to_string(), Some(rest.to_string()), None));
                 }
-                // パターン3: 単純な種族名のみ（解放派、闘争派、防衛派、奏元、精元など）
-                else {
-                    klasses.push((clean_text.to_string(), None, None));
+            }
+            // パターン2: 全角コロン "奏羅：宇宙" のような形式
+            else if let Some(colon_pos) = clean_text.find('：') {
+                let cat1 = clean_text[..colon_pos].trim();
+                let rest = clean_text[colon_pos + '：'.len_utf8()..].trim();
+
+                // パターン2a: "空獣／地獣" のような複数cat2を持つ場合
+                if let Some(slash_pos) = rest.find('/') {
+                    let cat2 = rest[..slash_pos].trim();
+                    let cat3 = rest[slash_pos + 1..].trim();
+                    klasses.push((
+                        cat1.to_string(),
+                        Some(cat2.to_string()),
+                        Some(cat3.to_string()),
+                    ));
+                } else {
+                    // パターン2b: "奏羅：宇宙" のような単一cat2の場合
+                    klasses.push((cat1.to_string(), Some(rest.to_string()), None));
                 }
             }
+            // パターン3: 単純な種族名のみ（解放派、闘争派、防衛派、奏元、精元など）
+            else {
+                klasses.push((clean_text.to_string(), None, None));
+            }
         }
 
         // 正規化処理を適用
@@ -542,57 +341,8 @@ impl SimpleRawCardAnalyzer {
         s
     }
 
-    /// HTMLからコスト情報を検出する（ルリグはグロウコスト、それ以外はコスト）
-    pub fn detect_cost_from_html(&self, html: &str) -> Option<String> {
-        let dd_elements = self.extract_dd_elements(html);
-
-        // dd要素が十分にない場合は早期リターン
-        if dd_elements.is_empty() {
-            return None;
-        }
-
-        // カードタイプを確認（dd[0]がカード種類）
-        let card_type = &dd_elements[0];
-        let is_lrig = card_type.contains("ルリグ");
-
-        // ルリグの場合はグロウコスト（dd[4]）、それ以外はコスト（dd[5]）
-        let cost_index = if is_lrig { 4 } else { 5 };
-
-        if dd_elements.len() > cost_index {
-            let cost_html = &dd_elements[cost_index];
-
-            // HTMLタグを除去（flatten_breakと同等の処理）
-            let cost_text = cost_html
-                .replace('\n', "")
-                .replace("<br>", "")
-                .replace("<br/>", "")
-                .replace("<br />", "");
-
-            if !cost_text.trim().is_empty() && cost_text.trim() != "-" {
-                // convert_cost関数を使って「《白》×１《青》×２」→「w1u2」形式に変換
-                match convert_cost(&cost_text) {
-                    Ok(converted) => Some(converted),
-                    Err(_) => {
-                        // 変換に失敗した場合は元のテキストを返す
-                        println!(
-                            "DEBUG: Failed to convert {} '{}' for {} card",
-                            if is_lrig { "grow cost" } else { "cost" },
-                            cost_text,
-                            if is_lrig { "Lrig" } else { "non-Lrig" }
-                        );
-                        Some(cost_text)
-                    }
-                }
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    }
-
     /// スキルテキストから特徴を検出し、置換後のテキストも返す
-    fn detect_features_and_replace_text(&self, skill_text: &str) -> (i64, i64, String) {
+    pub(crate) fn detect_features_and_replace_text(&self, skill_text: &str) -> (i64, i64, String) {
         let (replace_patterns, detect_patterns) = create_detect_patterns();
         let mut detected_features = HashSet::new();
 
@@ -628,208 +378,153 @@ impl SimpleRawCardAnalyzer {
         (bits1, bits2, processed_skill_text)
     }
 
-    /// ライフバーストテキストからバーストフィーチャーを検出（正規表現パターンシステム使用）
-    fn detect_burst_features(&self, life_burst_text: &str) -> (i64, String) {
-        let (replace_patterns, detect_patterns) = create_burst_detect_patterns();
-        let mut detected_burst_features: HashSet<BurstFeature> = HashSet::new();
-
-        // 半角に変換
-        let mut processed_text = to_half(life_burst_text);
-
-        // 置換パターンを適用してバーストフィーチャーを検出
-        for pattern in &replace_patterns {
-            if pattern.pattern_r.is_match(&processed_text) {
-                processed_text = pattern
-                    .pattern_r
-                    .replace_all(&processed_text, pattern.replace_to)
-                    .to_string();
-                for feature in pattern.features_detected {
-                    detected_burst_features.insert(feature.clone());
-                }
-            }
-        }
-
-        // 検出パターンでバーストフィーチャーを検出 - 並列処理
-        let additional_features: HashSet<BurstFeature> = detect_patterns
-            .par_iter()
-            .filter(|pattern| pattern.pattern_r.is_match(&processed_text))
-            .flat_map(|pattern| pattern.features_detected.par_iter().cloned())
-            .collect();
-
-        detected_burst_features.extend(additional_features);
-
-        // HashSetからビットに変換
-        (detected_burst_features.to_burst_bits(), processed_text)
-    }
-
+    /// 標準の解析パイプラインでRawCardDbを解析する
     pub async fn analyze_with_product_id(
         &self,
         raw_card: &RawCardDb,
         product_id: Option<i64>,
     ) -> Result<CreateCardWithKlass, AnalysisError> {
-        // HTMLからカード種別を検出
-        let card_type = self.detect_card_type_from_html(&raw_card.raw_html);
-        // println!("DEBUG: Card {} - Detected card_type: {}", raw_card.card_number, card_type);
-
-        // HTMLから色を検出
-        let color = self.detect_color_from_html(&raw_card.raw_html);
-        // println!("DEBUG: Card {} - Detected color: {}", raw_card.card_number, color);
-
-        // HTMLからコストを検出
-        let cost = self.detect_cost_from_html(&raw_card.raw_html);
-
-        // HTMLから追加フィールドを検出
-        let level_str = self.detect_level_from_html(&raw_card.raw_html);
-        let (limit_str, detected_feature_set) = self.detect_limit_from_html(&raw_card.raw_html);
-        let power = self.detect_power_from_html(&raw_card.raw_html);
-        let timing_str = self.detect_timing_from_html(&raw_card.raw_html);
-        let limit_ex_str = self.detect_limit_ex_from_html(&raw_card.raw_html);
-        let (story, story_as_skill) = self.detect_story_from_html(&raw_card.raw_html);
-        let detected_klasses = self.detect_klass_from_html(&raw_card.raw_html);
-        let format = self.detect_format_from_html(&raw_card.raw_html);
-
-        // 数値フィールドの型変換（String → i32）
-        let level: Option<i32> = level_str.and_then(|s| s.parse().ok());
-        let limit: Option<i32> = limit_str.and_then(|s| s.parse().ok());
-        let limit_ex: Option<i32> = limit_ex_str.and_then(|s| s.parse().ok());
-
-        // 使用タイミング文字列を数値にマッピング（ビットフラグ形式）
-        let timing: Option<i32> = timing_str.and_then(|s| {
-            match s.as_str() {
-                "アタックフェイズ" => Some(1),
-                "アタックフェイズスペルカットイン" => Some(2),
-                "メインフェイズ" => Some(4),
-                "メインフェイズアタックフェイズ" => Some(8),
-                "メインフェイズアタックフェイズスペルカットイン" => Some(16),
-                "メインフェイズスペルカットイン" => Some(32),
-                _ => None,
-            }
-        });
-
-        // スキルテキストとライフバーストテキストから特徴を検出し、置換後のテキストを取得
-        let (mut feature_bits1, mut feature_bits2, replaced_skill_text) =
-            self.detect_features_and_replace_text(&raw_card.skill_text);
+        AnalysisPipeline::standard().run(raw_card, product_id)
+    }
+}
 
-        // ライフバーストテキストからバーストフィーチャーを検出
-        let (burst_bits, replaced_burst_text) =
-            self.detect_burst_features(&raw_card.life_burst_text);
+/// 使用タイミング文字列を数値にマッピング（ビットフラグ形式）
+pub(crate) fn timing_to_bits(timing: &str) -> Option<i32> {
+    match timing {
+        "アタックフェイズ" => Some(1),
+        "アタックフェイズスペルカットイン" => Some(2),
+        "メインフェイズ" => Some(4),
+        "メインフェイズアタックフェイズ" => Some(8),
+        "メインフェイズアタックフェイズスペルカットイン" => Some(16),
+        "メインフェイズスペルカットイン" => Some(32),
+        _ => None,
+    }
+}
 
-        // NOTE: ライフバーストテキストからはBurstFeatureのみを検出し、
-        // 通常のCardFeatureは検出しない（防御系＞ダウンなどの誤検出を防ぐため）
+/// 「名前<読み方>」形式のカード名を名前と読み方に分離する
+///
+/// DBのカラム長に合わせ、名前は256バイト、読み方は128バイトで切り詰める
+pub(crate) fn split_name_and_pronunciation(raw_name: &str) -> (String, String) {
+    let full_name = to_half(raw_name);
+
+    // <読み方>形式を検索して分離
+    let (name, pronunciation) = match (full_name.find('<'), full_name.find('>')) {
+        // 読み方を抽出（<>を除く）し、nameから<読み方>部分を削除（前後の空白も削除）
+        (Some(start), Some(end)) => (
+            full_name[..start].trim_end().to_string(),
+            full_name[start + 1..end].to_string(),
+        ),
+        // <読み方>形式ではない場合はフォールバック
+        _ => (full_name.clone(), full_name.clone()),
+    };
+
+    (
+        truncate_bytes(&name, 256),
+        truncate_bytes(&pronunciation, 128),
+    )
+}
 
-        {
-            // テキスト以外からのFeature検出
-            let bits = detected_feature_set.to_bits();
-            feature_bits1 |= bits.0;
-            feature_bits2 |= bits.1;
+/// カード種類の表記をwix_cardtypeのIDに変換する
+fn card_type_id_from_text(card_type_text: &str) -> i32 {
+    if card_type_text.contains("ルリグ") {
+        if card_type_text.contains("アシスト") {
+            3 // アシストルリグ (DB ID: 3)
+        } else {
+            1 // ルリグ (DB ID: 1)
         }
-
-        {
-            let bits = story_as_skill.to_bits();
-            feature_bits1 |= bits.0;
-            feature_bits2 |= bits.1;
+    } else if card_type_text.contains("アーツ") {
+        if card_type_text.contains("クラフト") {
+            9 // クラフトアーツ (DB ID: 9)
+        } else {
+            2 // アーツ (DB ID: 2)
         }
-
-        let full_name = to_half(&raw_card.name);
-        let mut name: String;
-        let mut pronunciation: String;
-
-        // <読み方>形式を検索して分離
-        if let Some(start) = full_name.find('<') {
-            if let Some(end) = full_name.find('>') {
-                // 読み方を抽出（<>を除く）
-                pronunciation = full_name[start + 1..end].to_string();
-                // nameから<読み方>部分を削除（前後の空白も削除）
-                name = full_name[..start].trim_end().to_string();
-            } else {
-                // 閉じ括弧がない場合はフォールバック
-                name = full_name.clone();
-                pronunciation = full_name.clone();
-            }
+    } else if card_type_text.contains("キー") {
+        8 // キー (DB ID: 8)
+    } else if card_type_text.contains("シグニ") {
+        if card_type_text.contains("クラフト") {
+            10 // クラフトシグニ (DB ID: 10)
         } else {
-            // <読み方>形式ではない場合はフォールバック
-            name = full_name.clone();
-            pronunciation = full_name.clone();
+            5 // シグニ (DB ID: 5)
         }
-
-        // nameが256バイトを超える場合は切り詰める
-        if name.len() > 256 {
-            name = name
-                .chars()
-                .scan(0, |acc, c| {
-                    let char_len = c.len_utf8();
-                    if *acc + char_len <= 256 {
-                        *acc += char_len;
-                        Some(c)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+    } else if card_type_text.contains("スペル") {
+        if card_type_text.contains("クラフト") {
+            11 // クラフトスペル (DB ID: 11)
+        } else {
+            6 // スペル (DB ID: 6)
         }
-
-        {
-            let skills = Self::detect_story_from_name(&name);
-            let bits = skills.to_bits();
-            feature_bits1 |= bits.0;
-            feature_bits2 |= bits.1;
+    } else if card_type_text.contains("レゾナ") {
+        if card_type_text.contains("クラフト") {
+            14 // クラフトレゾナ (DB ID: 14)
+        } else {
+            7 // レゾナ (DB ID: 7)
         }
-        // pronunciation が128文字を超える場合は切り詰める（バイト長ベース）
-        if pronunciation.len() > 128 {
-            // eprintln!("DEBUG: Truncating pronunciation from {} to 128 bytes", pronunciation.len());
-            // UTF-8文字の境界を考慮して切り詰める
-            pronunciation = pronunciation
-                .chars()
-                .collect::<String>()
-                .chars()
-                .scan(0, |acc, c| {
-                    let char_len = c.len_utf8();
-                    if *acc + char_len <= 128 {
-                        *acc += char_len;
-                        Some(c)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            // eprintln!("DEBUG: After truncation: {} bytes", pronunciation.len());
+    } else if card_type_text.contains("ピース") {
+        if card_type_text.contains("リレー") {
+            12 // リレーピース (DB ID: 12)
+        } else if card_type_text.contains("クラフト") {
+            13 // クラフトピース (DB ID: 13)
+        } else {
+            4 // ピース (DB ID: 4)
         }
+    } else if card_type_text.contains("トークン") {
+        15 // トークン (DB ID: 15)
+    } else if card_type_text.contains("コイン") {
+        16 // コイン (DB ID: 16)
+    } else {
+        0 // Unknown
+    }
+}
 
-        let create_card = CreateCard {
-            name,
-            code: to_half(&raw_card.card_number),
-            pronunciation,
-            color,
-            cost,
-            level,
-            limit,
-            limit_ex,
-            product: product_id.unwrap_or(0) as i32,
-            card_type,
-            power,
-            has_burst: if raw_card.life_burst_text.is_empty() {
-                2
+/// UTF-8文字の境界を考慮して指定バイト数以内に切り詰める
+fn truncate_bytes(text: &str, max_bytes: usize) -> String {
+    text.chars()
+        .scan(0, |acc, c| {
+            let char_len = c.len_utf8();
+            if *acc + char_len <= max_bytes {
+                *acc += char_len;
+                Some(c)
             } else {
-                1
-            },
-            skill_text: Some(replaced_skill_text),
-            burst_text: Some(replaced_burst_text),
-            format,
-            story,
-            rarity: None,
-            timing,
-            url: Some(raw_card.source_url.clone()),
-            feature_bits1,
-            feature_bits2,
-            burst_bits,
-            ex1: None,
-        };
-
-        Ok(CreateCardWithKlass {
-            create_card,
-            detected_klasses,
+                None
+            }
         })
+        .collect()
+}
+
+/// ライフバーストテキストからバーストフィーチャーを検出（正規表現パターンシステム使用）
+///
+/// analyzer・webappのどちらの取り込み経路でも同じburst_bitsになるよう、
+/// バースト検出はすべてこの関数を経由する
+pub fn detect_burst_features(life_burst_text: &str) -> (i64, String) {
+    let (replace_patterns, detect_patterns) = create_burst_detect_patterns();
+    let mut detected_burst_features: HashSet<BurstFeature> = HashSet::new();
+
+    // 半角に変換
+    let mut processed_text = to_half(life_burst_text);
+
+    // 置換パターンを適用してバーストフィーチャーを検出
+    for pattern in &replace_patterns {
+        if pattern.pattern_r.is_match(&processed_text) {
+            processed_text = pattern
+                .pattern_r
+                .replace_all(&processed_text, pattern.replace_to)
+                .to_string();
+            for feature in pattern.features_detected {
+                detected_burst_features.insert(feature.clone());
+            }
+        }
     }
+
+    // 検出パターンでバーストフィーチャーを検出 - 並列処理
+    let additional_features: HashSet<BurstFeature> = detect_patterns
+        .par_iter()
+        .filter(|pattern| pattern.pattern_r.is_match(&processed_text))
+        .flat_map(|pattern| pattern.features_detected.par_iter().cloned())
+        .collect();
+
+    detected_burst_features.extend(additional_features);
+
+    // HashSetからビットに変換
+    (detected_burst_features.to_burst_bits(), processed_text)
 }
 
 #[async_trait::async_trait]
@@ -838,6 +533,13 @@ impl RawCardAnalyzer for SimpleRawCardAnalyzer {
         let result = self.analyze_with_product_id(raw_card, None).await?;
         Ok(result.create_card)
     }
+
+    async fn analyze_with_klass(
+        &self,
+        raw_card: &RawCardDb,
+    ) -> Result<CreateCardWithKlass, AnalysisError> {
+        self.analyze_with_product_id(raw_card, None).await
+    }
 }
 
 /// カードをデータベースに保存する機能
@@ -1233,10 +935,12 @@ impl CardRepository {
 
         // 検出されたKlassを処理
         for (cat1, cat2, cat3) in create_card_with_klass.detected_klasses {
-            if let Some(klass_id) = self
+            // if letの条件式に置くとエラー型（Send でない）が次のawaitまで残り、
+            // spawnしたタスクから呼べなくなるため先に束縛する
+            let klass_id = self
                 .get_existing_klass(&cat1, cat2.as_deref(), cat3.as_deref())
-                .await?
-            {
+                .await?;
+            if let Some(klass_id) = klass_id {
                 self.assign_klass_to_card(card_id, klass_id).await?;
             }
             // 既存のKlassが見つからない場合はスキップ（エラーにしない）
@@ -1244,6 +948,58 @@ impl CardRepository {
 
         Ok(card_id)
     }
+
+    /// 既存のwix_card行をクラス付きで取得する（差分表示用）
+    pub async fn find_existing_card(
+        &self,
+        code: &str,
+    ) -> Result<Option<ExistingCard>, Box<dyn std::error::Error>> {
+        let existing: Option<ExistingCard> = sqlx::query_as(
+            r#"
+            SELECT id, name, code, pronunciation, color, cost, level, "limit", limit_ex,
+                   product, card_type, power, has_burst, skill_text, burst_text, "format",
+                   story, timing, feature_bits1, feature_bits2, burst_bits
+            FROM wix_card
+            WHERE code = $1
+            "#,
+        )
+        .bind(code)
+        .fetch_optional(self.pool.as_ref())
+        .await?;
+
+        let Some(mut existing) = existing else {
+            return Ok(None);
+        };
+
+        existing.klasses = sqlx::query_as(
+            r#"
+            SELECT k.cat1, k.cat2, k.cat3
+            FROM wix_card_klass ck
+            JOIN wix_klass k ON k.id = ck.klass_id
+            WHERE ck.card_id = $1
+            ORDER BY k.id
+            "#,
+        )
+        .bind(existing.id)
+        .fetch_all(self.pool.as_ref())
+        .await?;
+
+        Ok(Some(existing))
+    }
+
+    /// RawCardを解析済みにマーク
+    pub async fn mark_raw_card_analyzed(
+        &self,
+        raw_card_id: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        sqlx::query(
+            "UPDATE wix_rawcard SET is_analyzed = true, last_analyzed_at = NOW() WHERE id = $1",
+        )
+        .bind(raw_card_id)
+        .execute(self.pool.as_ref())
+        .await?;
+        Ok(())
+    }
 }
 
 /// RawCardを解析してDBに保存する
@@ -1266,6 +1022,9 @@ pub async fn analyze_and_save_card_with_product_id(
     let create_card_with_klass = analyzer
         .analyze_with_product_id(raw_card, product_id)
         .await?;
+    for warning in &create_card_with_klass.warnings {
+        eprintln!("  ⚠ {}: {}", raw_card.card_number, warning);
+    }
 
     // DBに保存（Klass情報も含む）
     let card_repo = CardRepository::new(Arc::new(pool.clone()));
@@ -1274,12 +1033,7 @@ pub async fn analyze_and_save_card_with_product_id(
         .await?;
 
     // RawCardを解析済みにマーク
-    sqlx::query(
-        "UPDATE wix_rawcard SET is_analyzed = true, last_analyzed_at = NOW() WHERE id = $1",
-    )
-    .bind(raw_card.id)
-    .execute(pool)
-    .await?;
+    card_repo.mark_raw_card_analyzed(raw_card.id).await?;
 
     Ok(card_id)
 }
@@ -0,0 +1,386 @@
+//! 解析結果と既存wix_cardの差分
+//!
+//! アナライザーの変更がDBにどう影響するかを書き込み前に確認するため、
+//! 新しく解析したCreateCardと既存のwix_card行をフィールド単位で比較する。
+//! 特徴ビットは追加・削除されたフィーチャ名に、クラスは追加・削除された表記に展開する。
+
+use crate::card_analyzer::CreateCardWithKlass;
+use feature::{BurstFeature, CardFeature};
+use models::klass::klass_label;
+
+/// 比較対象となる既存のwix_card行
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ExistingCard {
+    pub id: i64,
+    pub name: String,
+    pub code: String,
+    pub pronunciation: String,
+    pub color: i32,
+    pub cost: Option<String>,
+    pub level: Option<i32>,
+    pub limit: Option<i32>,
+    pub limit_ex: Option<i32>,
+    pub product: i32,
+    pub card_type: i32,
+    pub power: Option<String>,
+    pub has_burst: i32,
+    pub skill_text: Option<String>,
+    pub burst_text: Option<String>,
+    pub format: i32,
+    pub story: Option<String>,
+    pub timing: Option<i32>,
+    pub feature_bits1: i64,
+    pub feature_bits2: i64,
+    pub burst_bits: i64,
+    /// wix_card_klass経由で紐づくクラス（cat1, cat2, cat3）
+    #[sqlx(skip)]
+    pub klasses: Vec<(String, Option<String>, Option<String>)>,
+}
+
+/// 値が変わったフィールド
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+/// 1枚のカードの差分
+#[derive(Debug, Clone, Default)]
+pub struct CardDiff {
+    pub code: String,
+    /// wix_cardにまだ存在しないカード
+    pub is_new: bool,
+    pub changes: Vec<FieldChange>,
+    pub features_added: Vec<String>,
+    pub features_removed: Vec<String>,
+    pub burst_features_added: Vec<String>,
+    pub burst_features_removed: Vec<String>,
+    pub klasses_added: Vec<String>,
+    pub klasses_removed: Vec<String>,
+}
+
+/// `--apply-only-diff` で1枚のカードに対して行うこと
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyAction {
+    /// 解析結果を保存してから解析済みにする
+    Save,
+    /// 書き込んでも変わらないので解析済みにするだけ（しないと `--force` なしでも毎回対象になる）
+    MarkAnalyzed,
+}
+
+macro_rules! diff_fields {
+    ($changes:ident, $before:expr, $after:expr, [$($field:ident),* $(,)?]) => {
+        $(
+            if $before.$field != $after.$field {
+                $changes.push(FieldChange {
+                    field: stringify!($field),
+                    before: format!("{:?}", $before.$field),
+                    after: format!("{:?}", $after.$field),
+                });
+            }
+        )*
+    };
+}
+
+impl CardDiff {
+    pub fn between(existing: Option<&ExistingCard>, analyzed: &CreateCardWithKlass) -> Self {
+        let card = &analyzed.create_card;
+        let new_klasses: Vec<String> = analyzed
+            .detected_klasses
+            .iter()
+            .map(|(cat1, cat2, cat3)| klass_label(cat1, cat2.as_deref(), cat3.as_deref()))
+            .collect();
+
+        let Some(existing) = existing else {
+            return Self {
+                code: card.code.clone(),
+                is_new: true,
+                features_added: feature_names(card.feature_bits1, card.feature_bits2),
+                burst_features_added: burst_feature_names(card.burst_bits),
+                klasses_added: new_klasses,
+                ..Self::default()
+            };
+        };
+
+        let mut changes = Vec::new();
+        diff_fields!(
+            changes,
+            existing,
+            card,
+            [
+                name,
+                pronunciation,
+                color,
+                cost,
+                level,
+                limit,
+                limit_ex,
+                product,
+                card_type,
+                power,
+                has_burst,
+                skill_text,
+                burst_text,
+                format,
+                story,
+                timing,
+            ]
+        );
+
+        let before_features = feature_names(existing.feature_bits1, existing.feature_bits2);
+        let after_features = feature_names(card.feature_bits1, card.feature_bits2);
+        let before_bursts = burst_feature_names(existing.burst_bits);
+        let after_bursts = burst_feature_names(card.burst_bits);
+        let before_klasses: Vec<String> = existing
+            .klasses
+            .iter()
+            .map(|(cat1, cat2, cat3)| klass_label(cat1, cat2.as_deref(), cat3.as_deref()))
+            .collect();
+
+        Self {
+            code: card.code.clone(),
+            is_new: false,
+            changes,
+            features_added: missing_from(&after_features, &before_features),
+            features_removed: missing_from(&before_features, &after_features),
+            burst_features_added: missing_from(&after_bursts, &before_bursts),
+            burst_features_removed: missing_from(&before_bursts, &after_bursts),
+            klasses_added: missing_from(&new_klasses, &before_klasses),
+            klasses_removed: missing_from(&before_klasses, &new_klasses),
+        }
+    }
+
+    /// 既存行と完全に一致する（書き込んでも何も変わらない）
+    pub fn is_empty(&self) -> bool {
+        !self.is_new
+            && self.changes.is_empty()
+            && self.features_added.is_empty()
+            && self.features_removed.is_empty()
+            && self.burst_features_added.is_empty()
+            && self.burst_features_removed.is_empty()
+            && self.klasses_added.is_empty()
+            && self.klasses_removed.is_empty()
+    }
+
+    pub fn apply_action(&self) -> ApplyAction {
+        if self.is_empty() {
+            ApplyAction::MarkAnalyzed
+        } else {
+            ApplyAction::Save
+        }
+    }
+
+    /// `field, before, after` の行に展開する（TSV出力・表示用）
+    ///
+    /// フィーチャとクラスは追加を `after`、削除を `before` に置く
+    pub fn rows(&self) -> Vec<(String, String, String)> {
+        let mut rows: Vec<(String, String, String)> = self
+            .changes
+            .iter()
+            .map(|c| (c.field.to_string(), c.before.clone(), c.after.clone()))
+            .collect();
+
+        let sets = [
+            ("feature", &self.features_removed, &self.features_added),
+            (
+                "burst_feature",
+                &self.burst_features_removed,
+                &self.burst_features_added,
+            ),
+            ("klass", &self.klasses_removed, &self.klasses_added),
+        ];
+        for (field, removed, added) in sets {
+            for name in removed {
+                rows.push((field.to_string(), name.clone(), String::new()));
+            }
+            for name in added {
+                rows.push((field.to_string(), String::new(), name.clone()));
+            }
+        }
+
+        rows
+    }
+}
+
+/// 差分一覧をTSV形式で出力する
+pub fn diffs_to_tsv(diffs: &[CardDiff]) -> String {
+    let mut tsv = String::from("code\tstatus\tfield\tbefore\tafter\n");
+    for diff in diffs {
+        let status = if diff.is_new { "new" } else { "changed" };
+        for (field, before, after) in diff.rows() {
+            tsv.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                diff.code,
+                status,
+                field,
+                escape_tsv(&before),
+                escape_tsv(&after)
+            ));
+        }
+    }
+    tsv
+}
+
+fn escape_tsv(value: &str) -> String {
+    value.replace('\t', " ").replace('\n', "\\n")
+}
+
+fn missing_from(values: &[String], others: &[String]) -> Vec<String> {
+    values
+        .iter()
+        .filter(|value| !others.contains(value))
+        .cloned()
+        .collect()
+}
+
+/// 特徴ビットに含まれるフィーチャ名（定義順）
+fn feature_names(bits1: i64, bits2: i64) -> Vec<String> {
+    CardFeature::from_bits(bits1, bits2)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// バーストビットに含まれるバーストフィーチャ名（定義順）
+fn burst_feature_names(bits: i64) -> Vec<String> {
+    BurstFeature::from_bits(bits)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feature::feature::HashSetToBits;
+    use models::card::CreateCard;
+    use std::collections::HashSet;
+
+    fn analyzed(features: &[CardFeature], power: Option<&str>) -> CreateCardWithKlass {
+        let (feature_bits1, feature_bits2) = features
+            .iter()
+            .cloned()
+            .collect::<HashSet<CardFeature>>()
+            .to_bits();
+        CreateCardWithKlass {
+            create_card: CreateCard {
+                name: "テスト".to_string(),
+                code: "TEST-001".to_string(),
+                pronunciation: "てすと".to_string(),
+                color: 2,
+                cost: None,
+                level: Some(1),
+                limit: None,
+                limit_ex: None,
+                product: 1,
+                card_type: 5,
+                power: power.map(|p| p.to_string()),
+                has_burst: 2,
+                skill_text: Some(String::new()),
+                burst_text: Some(String::new()),
+                format: 7,
+                story: None,
+                rarity: None,
+                timing: None,
+                url: None,
+                feature_bits1,
+                feature_bits2,
+                burst_bits: 0,
+                ex1: None,
+            },
+            detected_klasses: vec![("奏羅".to_string(), Some("宇宙".to_string()), None)],
+            warnings: Vec::new(),
+        }
+    }
+
+    fn existing_from(analyzed: &CreateCardWithKlass) -> ExistingCard {
+        let card = &analyzed.create_card;
+        ExistingCard {
+            id: 1,
+            name: card.name.clone(),
+            code: card.code.clone(),
+            pronunciation: card.pronunciation.clone(),
+            color: card.color,
+            cost: card.cost.clone(),
+            level: card.level,
+            limit: card.limit,
+            limit_ex: card.limit_ex,
+            product: card.product,
+            card_type: card.card_type,
+            power: card.power.clone(),
+            has_burst: card.has_burst,
+            skill_text: card.skill_text.clone(),
+            burst_text: card.burst_text.clone(),
+            format: card.format,
+            story: card.story.clone(),
+            timing: card.timing,
+            feature_bits1: card.feature_bits1,
+            feature_bits2: card.feature_bits2,
+            burst_bits: card.burst_bits,
+            klasses: analyzed.detected_klasses.clone(),
+        }
+    }
+
+    #[test]
+    fn test_identical_card_has_empty_diff() {
+        let card = analyzed(&[CardFeature::Draw], Some("5000"));
+        let existing = existing_from(&card);
+
+        assert!(CardDiff::between(Some(&existing), &card).is_empty());
+    }
+
+    #[test]
+    fn test_unchanged_card_is_only_marked_analyzed() {
+        let card = analyzed(&[CardFeature::Draw], Some("5000"));
+        let existing = existing_from(&card);
+        assert_eq!(
+            CardDiff::between(Some(&existing), &card).apply_action(),
+            ApplyAction::MarkAnalyzed
+        );
+
+        let changed = analyzed(&[CardFeature::Draw], Some("8000"));
+        assert_eq!(
+            CardDiff::between(Some(&existing), &changed).apply_action(),
+            ApplyAction::Save
+        );
+        assert_eq!(
+            CardDiff::between(None, &card).apply_action(),
+            ApplyAction::Save
+        );
+    }
+
+    #[test]
+    fn test_feature_power_and_klass_changes_are_reported() {
+        let before = analyzed(&[CardFeature::Draw], Some("5000"));
+        let mut existing = existing_from(&before);
+        existing.klasses = vec![("奏羅".to_string(), Some("宝石".to_string()), None)];
+
+        let after = analyzed(&[CardFeature::Banish], Some("8000"));
+        let diff = CardDiff::between(Some(&existing), &after);
+
+        assert_eq!(
+            diff.changes,
+            vec![FieldChange {
+                field: "power",
+                before: "Some(\"5000\")".to_string(),
+                after: "Some(\"8000\")".to_string(),
+            }]
+        );
+        assert_eq!(diff.features_added, vec![CardFeature::Banish.to_string()]);
+        assert_eq!(diff.features_removed, vec![CardFeature::Draw.to_string()]);
+        assert_eq!(diff.klasses_added, vec!["奏羅:宇宙"]);
+        assert_eq!(diff.klasses_removed, vec!["奏羅:宝石"]);
+        assert_eq!(diffs_to_tsv(&[diff]).lines().count(), 6);
+    }
+
+    #[test]
+    fn test_missing_card_is_new() {
+        let card = analyzed(&[CardFeature::Draw], None);
+        let diff = CardDiff::between(None, &card);
+
+        assert!(diff.is_new);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.features_added, vec![CardFeature::Draw.to_string()]);
+    }
+}
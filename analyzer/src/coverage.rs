@@ -0,0 +1,312 @@
+//! パターンカバレッジ計測
+//!
+//! 置換パターン・検出パターン・`concerned!` のいずれにも消費されなかった
+//! スキルテキスト（残差）をカードごとに求め、頻出フレーズを集計する。
+//! 集計結果は次に書くべきルールの優先順位付けに使う。
+
+use crate::raw_card_analyzer::to_half;
+use feature::{
+    DetectPattern, PATTERNS_AMOUNT_D, PATTERNS_AMOUNT_R, ReplacePattern, create_concerned_patterns,
+    create_detect_patterns,
+};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// これより短い残差フレーズはランキングに載せない（助詞の取り残しなどのノイズ対策。残差の文字数には数える）
+const MIN_PHRASE_CHARS: usize = 3;
+
+/// フレーズごとに保持するカード番号の例の最大数
+const MAX_EXAMPLES: usize = 3;
+
+/// 1枚のカードに対するカバレッジ計測結果
+#[derive(Debug, Clone)]
+pub struct CardCoverage {
+    pub card_number: String,
+    /// 半角化したスキルテキストの文字数（空白を除く）
+    pub total_chars: usize,
+    /// どのパターンにも消費されなかった文字数（空白を除く）
+    pub residual_chars: usize,
+    /// 数値を正規化した残差フレーズ
+    pub residual_phrases: Vec<String>,
+}
+
+impl CardCoverage {
+    /// パターンで説明できた割合（0.0〜1.0）
+    pub fn ratio(&self) -> f64 {
+        if self.total_chars == 0 {
+            1.0
+        } else {
+            1.0 - self.residual_chars as f64 / self.total_chars as f64
+        }
+    }
+}
+
+/// スキルテキストの残差を求めるアナライザー
+///
+/// パターンのコンパイルは重いため、インスタンスを使い回すこと
+pub struct PatternCoverage {
+    replace_patterns: [ReplacePattern; PATTERNS_AMOUNT_R],
+    detect_patterns: [DetectPattern; PATTERNS_AMOUNT_D],
+    concerned_patterns: Vec<Regex>,
+    digits: Regex,
+    markers_only: Regex,
+}
+
+impl Default for PatternCoverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PatternCoverage {
+    pub fn new() -> Self {
+        let (replace_patterns, detect_patterns) = create_detect_patterns();
+        Self {
+            replace_patterns,
+            detect_patterns,
+            concerned_patterns: create_concerned_patterns(),
+            digits: Regex::new(r"\d+").unwrap(),
+            // 【出】や《ターン1回》のような能力区分・アイコンだけの断片は構造上の記号として扱う
+            markers_only: Regex::new(r"^(【[^】]*】|《[^》]*》|[\s:])+$").unwrap(),
+        }
+    }
+
+    /// スキルテキストの残差を計測する
+    pub fn measure(&self, card_number: &str, skill_text: &str) -> CardCoverage {
+        let half_text = to_half(skill_text);
+        let total_chars = count_visible_chars(&half_text);
+
+        // 本番の解析と同じ順序で置換パターンを適用し、置換後のマーカーは説明済みとして除去
+        let mut processed = half_text;
+        for pattern in &self.replace_patterns {
+            if pattern.pattern_r.is_match(&processed) {
+                processed = pattern
+                    .pattern_r
+                    .replace_all(&processed, pattern.replace_to)
+                    .to_string();
+            }
+        }
+        for pattern in &self.replace_patterns {
+            if !pattern.replace_to.is_empty() {
+                processed = processed.replace(pattern.replace_to, "\n");
+            }
+        }
+
+        // 検出パターンと考慮済みパターンがマッチした範囲を消費済みとしてマークする
+        let mut consumed = vec![false; processed.len()];
+        let matchers = self
+            .detect_patterns
+            .iter()
+            .map(|pattern| &pattern.pattern_r)
+            .chain(self.concerned_patterns.iter());
+        for regex in matchers {
+            for m in regex.find_iter(&processed) {
+                consumed[m.start()..m.end()].fill(true);
+            }
+        }
+
+        let mut residual_phrases = Vec::new();
+        let mut residual_chars = 0;
+        let mut current = String::new();
+        for (index, ch) in processed.char_indices() {
+            if consumed[index] || matches!(ch, '\n' | '\r' | '。') {
+                self.push_phrase(&mut current, &mut residual_phrases, &mut residual_chars);
+            } else {
+                current.push(ch);
+            }
+        }
+        self.push_phrase(&mut current, &mut residual_phrases, &mut residual_chars);
+
+        CardCoverage {
+            card_number: card_number.to_string(),
+            total_chars,
+            residual_chars,
+            residual_phrases,
+        }
+    }
+
+    fn push_phrase(&self, current: &mut String, phrases: &mut Vec<String>, chars: &mut usize) {
+        let phrase = current
+            .trim_matches(|c: char| c.is_whitespace() || matches!(c, '、' | ',' | '.' | ':'))
+            .to_string();
+        current.clear();
+
+        if phrase.is_empty() || self.markers_only.is_match(&phrase) {
+            return;
+        }
+
+        *chars += count_visible_chars(&phrase);
+        if phrase.chars().count() < MIN_PHRASE_CHARS {
+            return;
+        }
+        phrases.push(self.digits.replace_all(&phrase, "N").to_string());
+    }
+}
+
+fn count_visible_chars(text: &str) -> usize {
+    text.chars().filter(|c| !c.is_whitespace()).count()
+}
+
+/// 残差フレーズの集計値
+#[derive(Debug, Clone)]
+pub struct PhraseStat {
+    pub phrase: String,
+    /// 出現回数（同一カード内の重複も数える）
+    pub count: usize,
+    /// 出現したカードの数
+    pub cards: usize,
+    /// 出現したカード番号の例
+    pub examples: Vec<String>,
+}
+
+/// 全カードのカバレッジ集計
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    pub cards: usize,
+    pub fully_covered_cards: usize,
+    pub total_chars: usize,
+    pub residual_chars: usize,
+    phrases: HashMap<String, PhraseStat>,
+}
+
+impl CoverageReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// カード1枚分の計測結果を加算する
+    pub fn add(&mut self, card: &CardCoverage) {
+        self.cards += 1;
+        self.total_chars += card.total_chars;
+        self.residual_chars += card.residual_chars;
+        if card.residual_phrases.is_empty() {
+            self.fully_covered_cards += 1;
+        }
+
+        let mut seen_in_card: Vec<&str> = Vec::new();
+        for phrase in &card.residual_phrases {
+            let stat = self
+                .phrases
+                .entry(phrase.clone())
+                .or_insert_with(|| PhraseStat {
+                    phrase: phrase.clone(),
+                    count: 0,
+                    cards: 0,
+                    examples: Vec::new(),
+                });
+            stat.count += 1;
+
+            if !seen_in_card.contains(&phrase.as_str()) {
+                seen_in_card.push(phrase);
+                stat.cards += 1;
+                if stat.examples.len() < MAX_EXAMPLES {
+                    stat.examples.push(card.card_number.clone());
+                }
+            }
+        }
+    }
+
+    /// 全体でパターンが説明できた割合（0.0〜1.0）
+    pub fn coverage_ratio(&self) -> f64 {
+        if self.total_chars == 0 {
+            1.0
+        } else {
+            1.0 - self.residual_chars as f64 / self.total_chars as f64
+        }
+    }
+
+    /// 出現カード数の多い順（同数なら出現回数、フレーズ順）に並べた残差フレーズ
+    pub fn ranked_phrases(&self) -> Vec<&PhraseStat> {
+        let mut ranked: Vec<&PhraseStat> = self.phrases.values().collect();
+        ranked.sort_by(|a, b| {
+            b.cards
+                .cmp(&a.cards)
+                .then(b.count.cmp(&a.count))
+                .then(a.phrase.cmp(&b.phrase))
+        });
+        ranked
+    }
+
+    /// ランキングをTSV形式で出力する
+    pub fn to_tsv(&self) -> String {
+        let mut tsv = String::from("rank\tcards\tcount\tphrase\texamples\n");
+        for (rank, stat) in self.ranked_phrases().iter().enumerate() {
+            tsv.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                rank + 1,
+                stat.cards,
+                stat.count,
+                stat.phrase.replace('\t', " "),
+                stat.examples.join(",")
+            ));
+        }
+        tsv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detected_text_has_no_ranked_residual() {
+        let coverage = PatternCoverage::new();
+        let result = coverage.measure("TEST-001", "【出】:カードを1枚引く。");
+
+        assert!(
+            result.residual_phrases.is_empty(),
+            "draw text should be consumed by a detect pattern: {:?}",
+            result.residual_phrases
+        );
+        // 検出パターンの外に残る送り仮名（「引く」の「く」）は残差に数える
+        assert_eq!(result.residual_chars, 1);
+    }
+
+    #[test]
+    fn test_concerned_text_is_consumed() {
+        let coverage = PatternCoverage::new();
+        let result = coverage.measure("TEST-002", "(表向きで置く)");
+
+        assert!(result.residual_phrases.is_empty());
+        assert_eq!(result.residual_chars, 0);
+    }
+
+    #[test]
+    fn test_unknown_text_is_reported_with_normalized_digits() {
+        let coverage = PatternCoverage::new();
+        let result = coverage.measure("TEST-003", "ふしぎなおまじないを3回となえる。");
+
+        assert_eq!(
+            result.residual_phrases,
+            vec!["ふしぎなおまじないをN回となえる"]
+        );
+        assert!(result.ratio() < 1.0);
+    }
+
+    #[test]
+    fn test_short_residual_is_counted_but_not_ranked() {
+        let coverage = PatternCoverage::new();
+        let result = coverage.measure("TEST-004", "(表向きで置く)。ぬ。");
+
+        assert!(result.residual_phrases.is_empty());
+        assert_eq!(result.residual_chars, 1);
+        assert!(result.ratio() < 1.0);
+    }
+
+    #[test]
+    fn test_report_ranks_by_card_count() {
+        let coverage = PatternCoverage::new();
+        let mut report = CoverageReport::new();
+        report.add(&coverage.measure("A-001", "ふしぎなおまじないを1回となえる。"));
+        report.add(&coverage.measure("A-002", "ふしぎなおまじないを2回となえる。"));
+        report.add(&coverage.measure("A-003", "めずらしいおどりをおどる。"));
+
+        let ranked = report.ranked_phrases();
+        assert_eq!(ranked[0].phrase, "ふしぎなおまじないをN回となえる");
+        assert_eq!(ranked[0].cards, 2);
+        assert_eq!(ranked[0].examples, vec!["A-001", "A-002"]);
+        assert_eq!(ranked[1].cards, 1);
+        assert_eq!(report.cards, 3);
+        assert_eq!(report.to_tsv().lines().count(), 3);
+    }
+}
@@ -1075,7 +1075,11 @@ pub mod migration {
 }
 
 pub mod card_analyzer;
+pub mod card_diff;
+pub mod coverage;
+pub mod pipeline;
 pub mod raw_card_analyzer;
+pub mod raw_card_fields;
 pub mod test_field_extraction;
 
 /// 本番のフィーチャー検出パターンを使用するルール
@@ -1,11 +1,15 @@
 use analyzer::card_analyzer::{
-    RawCardWithProduct, SimpleRawCardAnalyzer, analyze_and_save_card_with_product_id,
-    analyze_raw_cards_with_product_batch,
+    CardRepository, RawCardWithProduct, SimpleRawCardAnalyzer,
+    analyze_and_save_card_with_product_id, analyze_raw_cards_with_product_batch,
 };
-use clap::Parser;
-use dotenvy::from_filename;
+use analyzer::card_diff::{ApplyAction, CardDiff, diffs_to_tsv};
+use analyzer::coverage::{CoverageReport, PatternCoverage};
+use clap::{Parser, Subcommand};
+use config::Config;
+use rayon::prelude::*;
 use sqlx::PgPool;
-use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 /// WIXOSS カード解析ツール
 ///
@@ -23,6 +27,15 @@ use std::env;
 ///   
 ///   # 強制再解析
 ///   cargo run -p analyzer -- --product "WXDi-P01" --force
+///
+///   # 再解析した結果と既存のwix_cardの差分だけを確認（DBへの書き込みなし）
+///   cargo run -p analyzer -- --product "WXDi-P01" --force --dry-run --diff-output diff.tsv
+///
+///   # 差分のあるカードだけを保存
+///   cargo run -p analyzer -- --product "WXDi-P01" --force --apply-only-diff
+///
+///   # パターンで説明されていないスキルテキストを頻度順に表示
+///   cargo run -p analyzer -- coverage --top 100
 #[derive(Parser, Debug)]
 #[command(name = "analyzer")]
 #[command(about = "WIXOSS カード解析ツール")]
@@ -50,6 +63,35 @@ struct Args {
     /// 特定のプロダクト名またはプロダクトコードのカードのみを解析
     #[arg(long)]
     product: Option<String>,
+
+    /// 解析結果と既存のwix_cardの差分を表示するだけで保存しない
+    #[arg(long, conflicts_with = "apply_only_diff")]
+    dry_run: bool,
+
+    /// 既存のwix_cardと差分があるカードだけを保存する
+    #[arg(long)]
+    apply_only_diff: bool,
+
+    /// 差分をTSVで書き出すファイルパス（--dry-run / --apply-only-diff 時）
+    #[arg(long)]
+    diff_output: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// パターンカバレッジを計測し、未マッチのスキルテキストを頻度順に出力（DBへの書き込みなし）
+    Coverage {
+        /// 表示する未マッチフレーズの件数
+        #[arg(long, default_value = "50")]
+        top: usize,
+
+        /// ランキング全体をTSVで書き出すファイルパス
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
@@ -62,38 +104,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("プロダクトでフィルタリング: {}", product_filter);
     }
 
-    let workspace_env = format!(
-        "{}/.env",
-        env::var("CARGO_WORKSPACE_DIR").unwrap_or_default()
-    );
-    let env_paths = [
-        ".env",                 // カレントディレクトリ
-        "../.env",              // 一つ上のディレクトリ
-        "../../.env",           // 二つ上のディレクトリ（nested crateの場合）
-        workspace_env.as_str(), // CARGO_WORKSPACE_DIRが設定されている場合
-    ];
-
-    for path in &env_paths {
-        if std::path::Path::new(path).exists() {
-            from_filename(path).ok();
-            break;
-        }
-    }
-
-    let db_url = {
-        let host = env::var("DB_HOST").expect("DB_HOST not found in .env");
-        let port = env::var("DB_PORT").expect("DB_PORT not found in .env");
-        let user = env::var("DB_USER").expect("DB_USER not found in .env");
-        let password = env::var("DB_PASSWORD").expect("DB_PASSWORD not found in .env");
-        let db_name = env::var("DB_NAME").expect("DB_NAME not found in .env");
-        format!(
-            "postgres://{}:{}@{}:{}/{}",
-            user, password, host, port, db_name
-        )
-    };
+    let config = Config::load_or_exit();
 
     // Create database pool
-    let pool = PgPool::connect(&db_url).await?;
+    let pool = PgPool::connect(&config.database.url()).await?;
+
+    if let Some(Command::Coverage { top, output }) = &args.command {
+        return run_coverage(&pool, &args, *top, output.as_ref()).await;
+    }
 
     // Build query based on arguments
     let mut query = String::from(
@@ -103,24 +121,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         FROM wix_rawcard r",
     );
 
-    let mut conditions = Vec::new();
-
-    if let Some(product_filter) = &args.product {
-        query.push_str(" INNER JOIN wix_product p ON r.product_id = p.id");
-        // SQLインジェクション対策: シングルクォートをエスケープ
-        let safe_product_filter = product_filter.replace("'", "''");
-        // プロダクト名またはプロダクトコードでマッチ
-        conditions.push(format!(
-            "(p.name = '{}' OR p.product_code = '{}')", 
-            safe_product_filter, safe_product_filter
-        ));
-    }
-
-    if let Some(card_no) = &args.card_number {
-        // SQLインジェクション対策: シングルクォートをエスケープ
-        let safe_card_no = card_no.replace("'", "''");
-        conditions.push(format!("r.card_number = '{}'", safe_card_no));
-    }
+    let mut conditions = filter_conditions(&args, &mut query);
 
     if !args.force {
         conditions.push("r.is_analyzed = false".to_string());
@@ -169,6 +170,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("解析対象カード数: {}", raw_cards.len());
 
+    if args.dry_run || args.apply_only_diff {
+        return run_diff(&pool, &args, &raw_cards).await;
+    }
+
     // Process cards in batches
     let mut total_success = 0;
     let mut total_errors = 0;
@@ -308,3 +313,192 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// --product / --card-number からWHERE条件を組み立てる（必要ならJOINをクエリに追加）
+fn filter_conditions(args: &Args, query: &mut String) -> Vec<String> {
+    let mut conditions = Vec::new();
+
+    if let Some(product_filter) = &args.product {
+        query.push_str(" INNER JOIN wix_product p ON r.product_id = p.id");
+        // SQLインジェクション対策: シングルクォートをエスケープ
+        let safe_product_filter = product_filter.replace("'", "''");
+        // プロダクト名またはプロダクトコードでマッチ
+        conditions.push(format!(
+            "(p.name = '{}' OR p.product_code = '{}')", 
+            safe_product_filter, safe_product_filter
+        ));
+    }
+
+    if let Some(card_no) = &args.card_number {
+        // SQLインジェクション対策: シングルクォートをエスケープ
+        let safe_card_no = card_no.replace("'", "''");
+        conditions.push(format!("r.card_number = '{}'", safe_card_no));
+    }
+
+    conditions
+}
+
+/// 全RawCardのスキルテキストに対してパターンカバレッジを計測して表示する
+async fn run_coverage(
+    pool: &PgPool,
+    args: &Args,
+    top: usize,
+    output: Option<&PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut query = String::from("SELECT r.card_number, r.skill_text FROM wix_rawcard r");
+    let conditions = filter_conditions(args, &mut query);
+    if !conditions.is_empty() {
+        query.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    query.push_str(" ORDER BY r.card_number");
+
+    if args.verbose {
+        println!("Query: {}", query);
+    }
+
+    let rows: Vec<(String, String)> = sqlx::query_as(&query).fetch_all(pool).await?;
+    if rows.is_empty() {
+        println!("計測対象のカードが見つかりませんでした。");
+        return Ok(());
+    }
+
+    let coverage = PatternCoverage::new();
+    let results: Vec<_> = rows
+        .par_iter()
+        .map(|(card_number, skill_text)| coverage.measure(card_number, skill_text))
+        .collect();
+
+    let mut report = CoverageReport::new();
+    for result in &results {
+        report.add(result);
+        if args.verbose && !result.residual_phrases.is_empty() {
+            println!(
+                "  {} ({:.1}%): {}",
+                result.card_number,
+                result.ratio() * 100.0,
+                result.residual_phrases.join(" / ")
+            );
+        }
+    }
+
+    println!("\n=== パターンカバレッジ ===");
+    println!("カード数: {}", report.cards);
+    println!("完全にカバーされたカード: {}", report.fully_covered_cards);
+    println!(
+        "カバー率: {:.1}% (残差 {} / {} 文字)",
+        report.coverage_ratio() * 100.0,
+        report.residual_chars,
+        report.total_chars
+    );
+
+    let ranked = report.ranked_phrases();
+    println!("\n未マッチフレーズ上位 {} 件 (カード数 / 出現回数):", top.min(ranked.len()));
+    for (rank, stat) in ranked.iter().take(top).enumerate() {
+        println!(
+            "{:>4}. [{} / {}] {}  (例: {})",
+            rank + 1,
+            stat.cards,
+            stat.count,
+            stat.phrase,
+            stat.examples.join(", ")
+        );
+    }
+
+    if let Some(path) = output {
+        std::fs::write(path, report.to_tsv())?;
+        println!("\nランキングを書き出しました: {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// 解析結果を既存のwix_cardと比較し、差分を表示する（--apply-only-diff なら差分のあるカードだけ保存し、変更のないカードは解析済みにだけする）
+async fn run_diff(
+    pool: &PgPool,
+    args: &Args,
+    raw_cards: &[RawCardWithProduct],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let analyzer = SimpleRawCardAnalyzer::new();
+    let card_repo = CardRepository::new(Arc::new(pool.clone()));
+
+    let mut diffs = Vec::new();
+    let mut unchanged = 0;
+    let mut saved = 0;
+    let mut errors = 0;
+
+    for raw_card_with_product in raw_cards {
+        let raw_card = raw_card_with_product.to_raw_card_db();
+        let analyzed = match analyzer
+            .analyze_with_product_id(&raw_card, raw_card_with_product.product_id)
+            .await
+        {
+            Ok(analyzed) => analyzed,
+            Err(e) => {
+                println!("  ✗ {} : 解析失敗: {}", raw_card.card_number, e);
+                errors += 1;
+                continue;
+            }
+        };
+
+        let existing = card_repo
+            .find_existing_card(&analyzed.create_card.code)
+            .await?;
+        let diff = CardDiff::between(existing.as_ref(), &analyzed);
+        if diff.is_empty() {
+            unchanged += 1;
+            if args.verbose {
+                println!("  = {} - {}", raw_card.card_number, raw_card.name);
+            }
+        } else {
+            println!(
+                "  {} {} - {}",
+                if diff.is_new { "+" } else { "~" },
+                raw_card.card_number,
+                raw_card.name
+            );
+            for (field, before, after) in diff.rows() {
+                println!("      {}: {} → {}", field, before, after);
+            }
+        }
+
+        if args.apply_only_diff {
+            match diff.apply_action() {
+                ApplyAction::MarkAnalyzed => {
+                    card_repo.mark_raw_card_analyzed(raw_card.id).await?;
+                }
+                ApplyAction::Save => match card_repo.save_card_with_klass(analyzed).await {
+                    Ok(card_id) => {
+                        card_repo.mark_raw_card_analyzed(raw_card.id).await?;
+                        println!("    ✓ 保存完了 (ID: {})", card_id);
+                        saved += 1;
+                    }
+                    Err(e) => {
+                        println!("    ✗ 保存失敗: {}", e);
+                        errors += 1;
+                    }
+                },
+            }
+        }
+
+        if !diff.is_empty() {
+            diffs.push(diff);
+        }
+    }
+
+    let new_cards = diffs.iter().filter(|diff| diff.is_new).count();
+    println!("\n=== 差分 ===");
+    println!("新規: {}", new_cards);
+    println!("変更あり: {}", diffs.len() - new_cards);
+    println!("変更なし: {}", unchanged);
+    if args.apply_only_diff {
+        println!("保存: {}", saved);
+    }
+    println!("エラー: {}", errors);
+
+    if let Some(path) = &args.diff_output {
+        std::fs::write(path, diffs_to_tsv(&diffs))?;
+        println!("\n差分を書き出しました: {}", path.display());
+    }
+
+    Ok(())
+}
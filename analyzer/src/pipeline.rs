@@ -0,0 +1,555 @@
+//! 解析パイプライン
+//!
+//! RawCardDbからCreateCardを組み立てる処理を、フィールド抽出・クラス・特徴・バーストの
+//! ステージに分けて順に実行する。ステージは種類ごとに差し替えられるため、
+//! webapp側のカード種別ごとのHTMLパーサーもフィールド抽出ステージとして同じパイプラインに載せられる。
+//! `CrossCheck` は2つのアナライザーを同じカードに適用し、食い違ったフィールドを報告する。
+
+use crate::card_analyzer::{
+    CreateCardWithKlass, SimpleRawCardAnalyzer, detect_burst_features,
+    split_name_and_pronunciation, timing_to_bits,
+};
+use crate::raw_card_analyzer::{AnalysisError, RawCardAnalyzer, to_half};
+use crate::raw_card_fields::RawCardFields;
+use feature::feature::HashSetToBits;
+use models::card::CreateCard;
+use models::r#gen::django_models::RawCardDb;
+
+/// ステージの種類。パイプラインには種類ごとに1つのステージだけが登録される
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageKind {
+    /// 名前・種別・色・コストなどHTMLから読み取るフィールド
+    Fields,
+    /// クラス（種族）
+    Klass,
+    /// スキルテキストなどからのCardFeature
+    Features,
+    /// ライフバーストの有無とBurstFeature
+    Burst,
+}
+
+/// 解析パイプラインの1ステージ
+pub trait AnalysisStage: Send + Sync {
+    /// ステージ名（クロスチェックやログでの識別用）
+    fn name(&self) -> &'static str;
+
+    fn kind(&self) -> StageKind;
+
+    /// 解析途中のカードにこのステージの結果を書き込む
+    ///
+    /// `fields` はパイプラインがカードごとに1回だけHTMLを読み取った結果
+    fn apply(
+        &self,
+        raw_card: &RawCardDb,
+        fields: &RawCardFields,
+        card: &mut CreateCardWithKlass,
+    ) -> Result<(), AnalysisError>;
+}
+
+/// `<dl>` の項目名によるフィールド抽出ステージ
+///
+/// 未知の項目名は `warnings` に積む
+pub struct HtmlFieldStage;
+
+impl AnalysisStage for HtmlFieldStage {
+    fn name(&self) -> &'static str {
+        "html_fields"
+    }
+
+    fn kind(&self) -> StageKind {
+        StageKind::Fields
+    }
+
+    fn apply(
+        &self,
+        raw_card: &RawCardDb,
+        fields: &RawCardFields,
+        card: &mut CreateCardWithKlass,
+    ) -> Result<(), AnalysisError> {
+        let analyzer = SimpleRawCardAnalyzer::new();
+        card.warnings.extend(fields.warnings());
+        let create_card = &mut card.create_card;
+
+        let (name, pronunciation) = split_name_and_pronunciation(&raw_card.name);
+        create_card.name = name;
+        create_card.pronunciation = pronunciation;
+        create_card.card_type = analyzer.detect_card_type(fields, &raw_card.raw_html);
+        create_card.color = analyzer.detect_color(fields);
+        create_card.cost = analyzer.detect_cost(fields);
+        create_card.level = analyzer.detect_level(fields);
+        create_card.limit = analyzer.detect_limit(fields).0;
+        create_card.limit_ex = analyzer.detect_limit_ex(fields);
+        create_card.power = analyzer.detect_power(fields);
+        create_card.timing = analyzer
+            .detect_timing(fields)
+            .and_then(|s| timing_to_bits(&s));
+        create_card.story = analyzer.detect_story(fields).0;
+        create_card.format = analyzer.detect_format(fields);
+
+        Ok(())
+    }
+}
+
+/// カードタイプ欄からクラスを検出するステージ
+pub struct KlassStage;
+
+impl AnalysisStage for KlassStage {
+    fn name(&self) -> &'static str {
+        "klass"
+    }
+
+    fn kind(&self) -> StageKind {
+        StageKind::Klass
+    }
+
+    fn apply(
+        &self,
+        _raw_card: &RawCardDb,
+        fields: &RawCardFields,
+        card: &mut CreateCardWithKlass,
+    ) -> Result<(), AnalysisError> {
+        card.detected_klasses = SimpleRawCardAnalyzer::new().detect_klass(fields);
+        Ok(())
+    }
+}
+
+/// スキルテキスト・リミット・ストーリー・カード名からCardFeatureを検出するステージ
+///
+/// フィールド抽出ステージを差し替えても特徴の検出結果が変わらないよう、
+/// テキスト以外から得られる特徴もこのステージで検出する
+pub struct FeatureStage;
+
+impl AnalysisStage for FeatureStage {
+    fn name(&self) -> &'static str {
+        "features"
+    }
+
+    fn kind(&self) -> StageKind {
+        StageKind::Features
+    }
+
+    fn apply(
+        &self,
+        raw_card: &RawCardDb,
+        fields: &RawCardFields,
+        card: &mut CreateCardWithKlass,
+    ) -> Result<(), AnalysisError> {
+        let analyzer = SimpleRawCardAnalyzer::new();
+
+        let (bits1, bits2, replaced_skill_text) =
+            analyzer.detect_features_and_replace_text(&raw_card.skill_text);
+
+        // テキスト以外からのFeature検出
+        let mut features = analyzer.detect_limit(fields).1;
+        features.extend(analyzer.detect_story(fields).1);
+        let (name, _) = split_name_and_pronunciation(&raw_card.name);
+        features.extend(SimpleRawCardAnalyzer::detect_story_from_name(&name));
+        let (extra1, extra2) = features.to_bits();
+
+        let create_card = &mut card.create_card;
+        create_card.feature_bits1 |= bits1 | extra1;
+        create_card.feature_bits2 |= bits2 | extra2;
+        create_card.skill_text = Some(replaced_skill_text);
+
+        Ok(())
+    }
+}
+
+/// ライフバーストテキストからバーストの有無とBurstFeatureを検出するステージ
+pub struct BurstStage;
+
+impl AnalysisStage for BurstStage {
+    fn name(&self) -> &'static str {
+        "burst"
+    }
+
+    fn kind(&self) -> StageKind {
+        StageKind::Burst
+    }
+
+    fn apply(
+        &self,
+        raw_card: &RawCardDb,
+        _fields: &RawCardFields,
+        card: &mut CreateCardWithKlass,
+    ) -> Result<(), AnalysisError> {
+        // NOTE: ライフバーストテキストからはBurstFeatureのみを検出し、
+        // 通常のCardFeatureは検出しない（防御系＞ダウンなどの誤検出を防ぐため）
+        let (burst_bits, replaced_burst_text) = detect_burst_features(&raw_card.life_burst_text);
+
+        let create_card = &mut card.create_card;
+        create_card.has_burst = if raw_card.life_burst_text.is_empty() {
+            2
+        } else {
+            1
+        };
+        create_card.burst_bits = burst_bits;
+        create_card.burst_text = Some(replaced_burst_text);
+
+        Ok(())
+    }
+}
+
+/// ステージを順に適用してRawCardDbを解析するパイプライン
+pub struct AnalysisPipeline {
+    stages: Vec<Box<dyn AnalysisStage>>,
+}
+
+impl Default for AnalysisPipeline {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl AnalysisPipeline {
+    /// ステージを持たないパイプライン
+    pub fn empty() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    /// analyzer標準のステージ構成（フィールド → クラス → 特徴 → バースト）
+    pub fn standard() -> Self {
+        Self::empty()
+            .with_stage(HtmlFieldStage)
+            .with_stage(KlassStage)
+            .with_stage(FeatureStage)
+            .with_stage(BurstStage)
+    }
+
+    /// ステージを追加する。同じ種類のステージが登録済みの場合はその位置で差し替える
+    pub fn with_stage(mut self, stage: impl AnalysisStage + 'static) -> Self {
+        let stage: Box<dyn AnalysisStage> = Box::new(stage);
+        match self.stages.iter().position(|s| s.kind() == stage.kind()) {
+            Some(index) => self.stages[index] = stage,
+            None => self.stages.push(stage),
+        }
+        self
+    }
+
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    pub fn run(
+        &self,
+        raw_card: &RawCardDb,
+        product_id: Option<i64>,
+    ) -> Result<CreateCardWithKlass, AnalysisError> {
+        let mut card = CreateCardWithKlass {
+            create_card: blank_card(raw_card, product_id),
+            detected_klasses: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        let fields = RawCardFields::from_html(&raw_card.raw_html);
+        for stage in &self.stages {
+            stage.apply(raw_card, &fields, &mut card)?;
+        }
+
+        Ok(card)
+    }
+}
+
+/// ステージ適用前の初期値。RawCardDbから直接決まるものだけを埋める
+fn blank_card(raw_card: &RawCardDb, product_id: Option<i64>) -> CreateCard {
+    CreateCard {
+        name: String::new(),
+        code: to_half(&raw_card.card_number),
+        pronunciation: String::new(),
+        color: 0,
+        cost: None,
+        level: None,
+        limit: None,
+        limit_ex: None,
+        product: product_id.unwrap_or(0) as i32,
+        card_type: 0,
+        power: None,
+        has_burst: 0,
+        skill_text: None,
+        burst_text: None,
+        format: 0,
+        story: None,
+        rarity: None,
+        timing: None,
+        url: Some(raw_card.source_url.clone()),
+        feature_bits1: 0,
+        feature_bits2: 0,
+        burst_bits: 0,
+        ex1: None,
+    }
+}
+
+#[async_trait::async_trait]
+impl RawCardAnalyzer for AnalysisPipeline {
+    async fn analyze(&self, raw_card: &RawCardDb) -> Result<CreateCard, AnalysisError> {
+        Ok(self.run(raw_card, None)?.create_card)
+    }
+
+    async fn analyze_with_klass(
+        &self,
+        raw_card: &RawCardDb,
+    ) -> Result<CreateCardWithKlass, AnalysisError> {
+        self.run(raw_card, None)
+    }
+}
+
+/// 2つのアナライザーで値が食い違ったフィールド
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDisagreement {
+    pub field: &'static str,
+    pub left: String,
+    pub right: String,
+}
+
+/// 1枚のカードに対するクロスチェック結果
+#[derive(Debug, Clone)]
+pub struct CrossCheckReport {
+    pub card_number: String,
+    pub disagreements: Vec<FieldDisagreement>,
+}
+
+impl CrossCheckReport {
+    pub fn is_consistent(&self) -> bool {
+        self.disagreements.is_empty()
+    }
+}
+
+macro_rules! compare_fields {
+    ($out:ident, $left:expr, $right:expr, [$($field:ident),* $(,)?]) => {
+        $(
+            if $left.$field != $right.$field {
+                $out.push(FieldDisagreement {
+                    field: stringify!($field),
+                    left: format!("{:?}", $left.$field),
+                    right: format!("{:?}", $right.$field),
+                });
+            }
+        )*
+    };
+}
+
+/// 2つの解析結果を比較し、食い違ったフィールドを返す
+///
+/// skill_text・burst_textは置換後の表現が経路ごとに異なるため比較しない
+pub fn compare_cards(
+    left: &CreateCardWithKlass,
+    right: &CreateCardWithKlass,
+) -> Vec<FieldDisagreement> {
+    let mut disagreements = Vec::new();
+    let (l, r) = (&left.create_card, &right.create_card);
+    compare_fields!(
+        disagreements,
+        l,
+        r,
+        [
+            name,
+            pronunciation,
+            code,
+            card_type,
+            color,
+            cost,
+            level,
+            limit,
+            limit_ex,
+            power,
+            has_burst,
+            format,
+            story,
+            timing,
+            feature_bits1,
+            feature_bits2,
+            burst_bits,
+        ]
+    );
+
+    let mut left_klasses = left.detected_klasses.clone();
+    let mut right_klasses = right.detected_klasses.clone();
+    left_klasses.sort();
+    right_klasses.sort();
+    if left_klasses != right_klasses {
+        disagreements.push(FieldDisagreement {
+            field: "klass",
+            left: format!("{:?}", left_klasses),
+            right: format!("{:?}", right_klasses),
+        });
+    }
+
+    disagreements
+}
+
+/// 2つのアナライザーを同じカードに適用して結果を突き合わせる
+pub struct CrossCheck<L, R> {
+    left: L,
+    right: R,
+}
+
+impl<L, R> CrossCheck<L, R>
+where
+    L: RawCardAnalyzer + Sync,
+    R: RawCardAnalyzer + Sync,
+{
+    pub fn new(left: L, right: R) -> Self {
+        Self { left, right }
+    }
+
+    /// どちらかの解析が失敗した場合は `result` フィールドの食い違いとして報告する
+    pub async fn check(&self, raw_card: &RawCardDb) -> CrossCheckReport {
+        let left = self.left.analyze_with_klass(raw_card).await;
+        let right = self.right.analyze_with_klass(raw_card).await;
+
+        let disagreements = match (&left, &right) {
+            (Ok(left), Ok(right)) => compare_cards(left, right),
+            _ => vec![FieldDisagreement {
+                field: "result",
+                left: describe_result(&left),
+                right: describe_result(&right),
+            }],
+        };
+
+        CrossCheckReport {
+            card_number: raw_card.card_number.clone(),
+            disagreements,
+        }
+    }
+
+    pub async fn check_batch(&self, raw_cards: &[RawCardDb]) -> Vec<CrossCheckReport> {
+        let mut reports = Vec::new();
+        for raw_card in raw_cards {
+            reports.push(self.check(raw_card).await);
+        }
+        reports
+    }
+}
+
+fn describe_result(result: &Result<CreateCardWithKlass, AnalysisError>) -> String {
+    match result {
+        Ok(_) => "ok".to_string(),
+        Err(e) => format!("error: {}", e.message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    const SIGNI_HTML: &str = r#"
+        <dl>
+            <dt>カード種類</dt><dd>シグニ</dd>
+            <dt>カードタイプ</dt><dd>奏羅：宇宙</dd>
+            <dt>色</dt><dd>青</dd>
+            <dt>レベル</dt><dd>2</dd>
+            <dt>グロウコスト</dt><dd>-</dd>
+            <dt>コスト</dt><dd>-</dd>
+            <dt>リミット</dt><dd>-</dd>
+            <dt>パワー</dt><dd>5000</dd>
+            <dt>限定条件</dt><dd>-</dd>
+            <dt>ガード</dt><dd>-</dd>
+            <dt>フォーマット</dt><dd>-</dd>
+            <dt>ストーリー</dt><dd>-</dd>
+        </dl>
+    "#;
+
+    fn raw_card() -> RawCardDb {
+        RawCardDb {
+            id: 1,
+            card_number: "TEST-001".to_string(),
+            name: "テストシグニ<テストシグニ>".to_string(),
+            raw_html: SIGNI_HTML.to_string(),
+            skill_text: "【出】:カードを1枚引く。".to_string(),
+            life_burst_text: "カードを1枚引く。".to_string(),
+            source_url: "https://example.com/TEST-001".to_string(),
+            scraped_at: Utc::now(),
+            last_analyzed_at: None,
+            is_analyzed: false,
+            analysis_error: String::new(),
+        }
+    }
+
+    /// カード種別だけを固定値で上書きするフィールド抽出ステージ
+    struct FixedTypeStage(i32);
+
+    impl AnalysisStage for FixedTypeStage {
+        fn name(&self) -> &'static str {
+            "fixed_type"
+        }
+
+        fn kind(&self) -> StageKind {
+            StageKind::Fields
+        }
+
+        fn apply(
+            &self,
+            raw_card: &RawCardDb,
+            fields: &RawCardFields,
+            card: &mut CreateCardWithKlass,
+        ) -> Result<(), AnalysisError> {
+            HtmlFieldStage.apply(raw_card, fields, card)?;
+            card.create_card.card_type = self.0;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_standard_pipeline_fills_every_stage() {
+        let pipeline = AnalysisPipeline::standard();
+        assert_eq!(
+            pipeline.stage_names(),
+            vec!["html_fields", "klass", "features", "burst"]
+        );
+
+        let result = pipeline.run(&raw_card(), Some(42)).unwrap();
+        let card = &result.create_card;
+        assert_eq!(card.name, "テストシグニ");
+        assert_eq!(card.card_type, 5);
+        assert_eq!(card.level, Some(2));
+        assert_eq!(card.product, 42);
+        assert_eq!(card.has_burst, 1);
+        assert_ne!(card.feature_bits1 | card.feature_bits2, 0);
+        assert_ne!(card.burst_bits, 0);
+        assert_eq!(
+            result.detected_klasses,
+            vec![("奏羅".to_string(), Some("宇宙".to_string()), None)]
+        );
+    }
+
+    #[test]
+    fn test_with_stage_replaces_same_kind_in_place() {
+        let pipeline = AnalysisPipeline::standard().with_stage(FixedTypeStage(99));
+        assert_eq!(
+            pipeline.stage_names(),
+            vec!["fixed_type", "klass", "features", "burst"]
+        );
+        assert_eq!(
+            pipeline
+                .run(&raw_card(), None)
+                .unwrap()
+                .create_card
+                .card_type,
+            99
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cross_check_reports_only_disagreeing_fields() {
+        let raw_card = raw_card();
+
+        let same = CrossCheck::new(AnalysisPipeline::standard(), SimpleRawCardAnalyzer::new());
+        assert!(same.check(&raw_card).await.is_consistent());
+
+        let different = CrossCheck::new(
+            AnalysisPipeline::standard(),
+            AnalysisPipeline::standard().with_stage(FixedTypeStage(99)),
+        );
+        let report = different.check(&raw_card).await;
+        assert_eq!(report.card_number, "TEST-001");
+        assert_eq!(
+            report.disagreements,
+            vec![FieldDisagreement {
+                field: "card_type",
+                left: "5".to_string(),
+                right: "99".to_string(),
+            }]
+        );
+    }
+}
@@ -1,57 +1,11 @@
+use crate::card_analyzer::CreateCardWithKlass;
 use feature::feature::CardFeature;
 use models::card::CreateCard;
 use models::r#gen::django_models::RawCardDb;
 use std::collections::HashSet;
 
-/// Convert full-width characters to half-width equivalents
-/// Handles:
-/// - Full-width alphanumeric characters (Ａ-Ｚ, ａ-ｚ, ０-９)
-/// - Full-width space (　)
-/// - Common full-width symbols
-pub fn to_half(text: &str) -> String {
-    text.chars()
-        .map(|ch| match ch {
-            // Full-width uppercase letters (Ａ-Ｚ) -> (A-Z)
-            'Ａ'..='Ｚ' => {
-                let offset = ch as u32 - 'Ａ' as u32;
-                char::from_u32('A' as u32 + offset).unwrap()
-            }
-            // Full-width lowercase letters (ａ-ｚ) -> (a-z)
-            'ａ'..='ｚ' => {
-                let offset = ch as u32 - 'ａ' as u32;
-                char::from_u32('a' as u32 + offset).unwrap()
-            }
-            // Full-width digits (０-９) -> (0-9)
-            '０'..='９' => {
-                let offset = ch as u32 - '０' as u32;
-                char::from_u32('0' as u32 + offset).unwrap()
-            }
-            // Full-width space
-            '　' => ' ',
-            // Common full-width symbols
-            '－' => '-', // Full-width hyphen/minus
-            '＋' => '+', // Full-width plus
-            '．' => '.', // Full-width period
-            '，' => ',', // Full-width comma
-            '：' => ':', // Full-width colon
-            '；' => ';', // Full-width semicolon
-            '！' => '!', // Full-width exclamation
-            '？' => '?', // Full-width question mark
-            '（' => '(', // Full-width left parenthesis
-            '）' => ')', // Full-width right parenthesis
-            '［' => '[', // Full-width left bracket
-            '］' => ']', // Full-width right bracket
-            '＊' => '*', // Full-width asterisk
-            '＆' => '&', // Full-width ampersand
-            '＝' => '=', // Full-width equals
-            '／' => '/', // Full-width slash
-            '＜' => '<', // Full-width less than
-            '＞' => '>', // Full-width greater than
-            // Keep other characters as-is
-            _ => ch,
-        })
-        .collect()
-}
+/// 全角英数字・記号を半角にする（rule_editorと共有するためshared/featureで定義）
+pub use feature::text::to_half;
 
 /// Error type for card analysis
 #[derive(Debug, Clone)]
@@ -83,6 +37,20 @@ impl std::error::Error for AnalysisError {}
 pub trait RawCardAnalyzer {
     async fn analyze(&self, raw_card: &RawCardDb) -> Result<CreateCard, AnalysisError>;
 
+    /// Analyze a card together with its detected klasses
+    ///
+    /// Analyzers that do not detect klasses return an empty list
+    async fn analyze_with_klass(
+        &self,
+        raw_card: &RawCardDb,
+    ) -> Result<CreateCardWithKlass, AnalysisError> {
+        Ok(CreateCardWithKlass {
+            create_card: self.analyze(raw_card).await?,
+            detected_klasses: Vec::new(),
+            warnings: Vec::new(),
+        })
+    }
+
     /// Analyze multiple cards in batch
     async fn analyze_batch(
         &self,
@@ -118,81 +86,6 @@ pub fn features_to_bits(features: &HashSet<CardFeature>) -> (i64, i64) {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_to_half_uppercase() {
-        assert_eq!(
-            to_half("ＡＢＣＤＥＦＧＨＩＪＫＬＭＮＯＰＱＲＳＴＵＶＷＸＹＺ"),
-            "ABCDEFGHIJKLMNOPQRSTUVWXYZ"
-        );
-    }
-
-    #[test]
-    fn test_to_half_lowercase() {
-        assert_eq!(
-            to_half("ａｂｃｄｅｆｇｈｉｊｋｌｍｎｏｐｑｒｓｔｕｖｗｘｙｚ"),
-            "abcdefghijklmnopqrstuvwxyz"
-        );
-    }
-
-    #[test]
-    fn test_to_half_numbers() {
-        assert_eq!(to_half("０１２３４５６７８９"), "0123456789");
-    }
-
-    #[test]
-    fn test_to_half_space() {
-        assert_eq!(to_half("ハロー　ワールド"), "ハロー ワールド");
-    }
-
-    #[test]
-    fn test_to_half_mixed() {
-        assert_eq!(to_half("カードＷＸ２４－Ｐ１"), "カードWX24-P1");
-        assert_eq!(to_half("レベル３　パワー１２０００"), "レベル3 パワー12000");
-        assert_eq!(to_half("ＬＢ：カードを１枚引く"), "LB:カードを1枚引く");
-    }
-
-    #[test]
-    fn test_to_half_preserves_japanese() {
-        let input = "このシグニはアサシンを持つ";
-        assert_eq!(to_half(input), input);
-    }
-
-    #[test]
-    fn test_to_half_symbols() {
-        // Test that full-width symbols are converted
-        assert_eq!(
-            to_half("【チャーム】《ガードアイコン》：；・"),
-            "【チャーム】《ガードアイコン》:;・"
-        );
-        assert_eq!(to_half("（パワー＋１０００）"), "(パワー+1000)");
-        assert_eq!(to_half("！？＝＜＞"), "!?=<>");
-    }
-
-    #[test]
-    fn test_to_half_real_card_examples() {
-        // Real card name examples
-        assert_eq!(
-            to_half("コードアクセル　Ｈｙａｈｈａｈ"),
-            "コードアクセル Hyahhah"
-        );
-        assert_eq!(
-            to_half("羅植姫　ガーベラ／／Ｍｅｍｏｒｉａｌ"),
-            "羅植姫 ガーベラ//Memorial"
-        );
-
-        // Real skill text examples
-        assert_eq!(
-            to_half("【エナチャージ１】をする"),
-            "【エナチャージ1】をする"
-        );
-        assert_eq!(to_half("パワーを＋２０００する"), "パワーを+2000する");
-    }
-
-    #[test]
-    fn test_to_half_empty_string() {
-        assert_eq!(to_half(""), "");
-    }
-
     #[test]
     fn test_features_to_bits() {
         let mut features = HashSet::new();
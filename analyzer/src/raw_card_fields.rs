@@ -0,0 +1,320 @@
+//! カード詳細HTMLの項目抽出
+//!
+//! 公式サイトのカード詳細は `<dl>` の `<dt>項目名</dt><dd>値</dd>` で各項目を並べている。
+//! 項目の並び順はカード種別や掲載時期で異なるため、位置ではなく項目名で値を引き当てる。
+//! 知らない項目名は `unknown_labels` に残し、サイト側のレイアウト変更を警告として検知できるようにする。
+
+use color::convert_cost;
+use scraper::{ElementRef, Html, Selector};
+use std::sync::LazyLock;
+
+static DT_SELECTOR: LazyLock<Selector> =
+    LazyLock::new(|| Selector::parse("dt").expect("dtセレクタのパースに失敗"));
+
+/// FAQや制限情報の `<dl>` はカード項目ではないため除外する
+const NON_FIELD_CONTAINERS: [&str; 2] = ["cardFaq", "limitedInfo"];
+
+/// 色が読み取れなかったときの色ビット
+pub const COLOR_UNKNOWN: i32 = 1 << 7;
+
+/// `<dl>` から読み取ったカード項目
+///
+/// 空欄と `-` は `None`。型に合わない値は `None` にして `invalid_values` に残す
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawCardFields {
+    /// カード種類（旧表記: 種類）
+    pub card_type: Option<String>,
+    /// カードタイプ（旧表記: クラス）の各行
+    pub klass: Vec<String>,
+    /// 色ビット（shared/colorの定義）。色の項目はあるが色が読み取れなければ0
+    pub color: Option<i32>,
+    pub level: Option<i32>,
+    /// グロウコスト（`w1u2` 形式。変換できなければ元のテキスト）
+    pub grow_cost: Option<String>,
+    /// コスト（`w1u2` 形式。変換できなければ元のテキスト）
+    pub cost: Option<String>,
+    pub limit: Option<i32>,
+    pub power: Option<i32>,
+    /// 限定条件
+    pub condition: Option<String>,
+    pub team: Option<String>,
+    pub guard: Option<String>,
+    pub coin: Option<String>,
+    /// リミット消費
+    pub limit_ex: Option<i32>,
+    /// 使用タイミング（複数行は改行を除いて連結）
+    pub timing: Option<String>,
+    /// フォーマット（7: オールスター、3: キーセレクション、1: ディーヴァセレクション）
+    pub format: Option<i32>,
+    /// ストーリー（ディソナのアイコンのみ `dissona` として認識する）
+    pub story: Option<String>,
+    /// その他
+    pub other: Option<String>,
+    /// 対応する項目がなかった `<dt>` の項目名
+    pub unknown_labels: Vec<String>,
+    /// 型に合わなかった値（項目名, 値）
+    pub invalid_values: Vec<(String, String)>,
+}
+
+impl RawCardFields {
+    pub fn from_html(html: &str) -> Self {
+        Self::from_document(&Html::parse_document(html))
+    }
+
+    /// パース済みのHTMLから読み取る
+    pub fn from_document(document: &Html) -> Self {
+        let mut fields = Self::default();
+
+        for dt in document.select(&DT_SELECTOR) {
+            if is_in_non_field_container(&dt) {
+                continue;
+            }
+
+            let label = dt.text().collect::<String>().trim().to_string();
+            let value = next_dd(&dt)
+                .and_then(|dd| normalize_value(&dd.inner_html()))
+                .unwrap_or_default();
+            fields.set(label, &value);
+        }
+
+        fields
+    }
+
+    /// 項目名に対応するフィールドに値を型変換して入れる。知らない項目名は `unknown_labels` に積む
+    fn set(&mut self, label: String, value: &str) {
+        match label.as_str() {
+            "カード種類" | "種類" => self.card_type = text(value),
+            "カードタイプ" | "クラス" => {
+                self.klass = split_lines(value)
+                    .into_iter()
+                    .filter(|line| line != "-")
+                    .collect()
+            }
+            "色" => self.color = text(value).map(|color| color_bits(&color)),
+            "レベル" => self.level = self.number(&label, value),
+            "グロウコスト" => self.grow_cost = self.cost_code(&label, value),
+            "コスト" => self.cost = self.cost_code(&label, value),
+            "リミット" => self.limit = self.number(&label, value),
+            "パワー" => self.power = self.number(&label, value),
+            "限定条件" => self.condition = text(value),
+            "チーム" => self.team = text(value),
+            "ガード" => self.guard = text(value),
+            "コイン" => self.coin = text(value),
+            "リミット消費" => self.limit_ex = self.number(&label, value),
+            "使用タイミング" => self.timing = text(value),
+            "フォーマット" => self.format = format_value(value),
+            "ストーリー" => {
+                self.story = value
+                    .contains("icon_txt_dissona.png")
+                    .then(|| "dissona".to_string())
+            }
+            "その他" => self.other = text(value),
+            _ => self.unknown_labels.push(label),
+        }
+    }
+
+    fn number(&mut self, label: &str, value: &str) -> Option<i32> {
+        let value = text(value)?;
+        match value.parse() {
+            Ok(number) => Some(number),
+            Err(_) => {
+                self.invalid_values.push((label.to_string(), value));
+                None
+            }
+        }
+    }
+
+    fn cost_code(&mut self, label: &str, value: &str) -> Option<String> {
+        let value = text(value)?;
+        match convert_cost(&value) {
+            Ok(code) => Some(code),
+            Err(_) => {
+                self.invalid_values.push((label.to_string(), value.clone()));
+                Some(value)
+            }
+        }
+    }
+
+    /// レイアウト変更の疑いがある点を警告文にする
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self
+            .unknown_labels
+            .iter()
+            .map(|label| format!("未知の項目名: {}", label))
+            .collect();
+        warnings.extend(
+            self.invalid_values
+                .iter()
+                .map(|(label, value)| format!("値を読み取れません: {}={}", label, value)),
+        );
+        if self.card_type.is_none() {
+            warnings.push("カード種類が見つかりません".to_string());
+        }
+        warnings
+    }
+}
+
+fn is_in_non_field_container(element: &ElementRef) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| {
+            ancestor
+                .value()
+                .classes()
+                .any(|class| NON_FIELD_CONTAINERS.contains(&class))
+        })
+}
+
+/// `<dt>` の直後の要素が `<dd>` ならそれを返す
+fn next_dd<'a>(dt: &ElementRef<'a>) -> Option<ElementRef<'a>> {
+    dt.next_siblings()
+        .filter_map(ElementRef::wrap)
+        .next()
+        .filter(|element| element.value().name() == "dd")
+}
+
+fn normalize_value(inner_html: &str) -> Option<String> {
+    let value = inner_html.trim();
+    if value.is_empty() || value == "-" {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// `<br>` で区切られた行
+fn split_lines(html: &str) -> Vec<String> {
+    html.split("<br>")
+        .flat_map(|line| line.split("<br/>"))
+        .flat_map(|line| line.split("<br />"))
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// 改行と `<br>` を取り除いたテキスト。空なら `None`
+fn text(html: &str) -> Option<String> {
+    normalize_value(&split_lines(html).concat().replace(['\n', '\r'], ""))
+}
+
+/// 色の表記を色ビットにする（shared/color/lib.rsの定義に合わせる）
+fn color_bits(color_text: &str) -> i32 {
+    if color_text.contains("無") {
+        return 1 << 6; // Colorless
+    }
+    [('白', 1), ('青', 2), ('赤', 3), ('黒', 4), ('緑', 5)]
+        .iter()
+        .filter(|(color, _)| color_text.contains(*color))
+        .fold(0, |bits, (_, shift)| bits | 1 << shift)
+}
+
+/// フォーマットアイコンから値を決める
+///
+/// 包含関係: オールスター（アイコンなし）> キーセレクション > ディーヴァセレクション
+fn format_value(format_html: &str) -> Option<i32> {
+    if format_html.contains("icon_txt_format_diva.png") {
+        Some(1)
+    } else if format_html.contains("icon_txt_format_key.png") {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_are_found_by_label_regardless_of_order() {
+        let html = r#"
+            <div class="cardData">
+                <dl>
+                    <dt>カード種類</dt><dd>アーツ</dd>
+                    <dt>クラス</dt><dd>-</dd>
+                    <dt>色</dt><dd>緑</dd>
+                    <dt>リミット消費</dt><dd>-</dd>
+                    <dt>使用タイミング</dt><dd>メインフェイズ<br>アタックフェイズ</dd>
+                    <dt>その他</dt><dd>-</dd>
+                </dl>
+            </div>
+        "#;
+
+        let fields = RawCardFields::from_html(html);
+        assert_eq!(fields.card_type.as_deref(), Some("アーツ"));
+        assert!(fields.klass.is_empty());
+        assert_eq!(fields.color, Some(1 << 5));
+        assert_eq!(
+            fields.timing.as_deref(),
+            Some("メインフェイズアタックフェイズ")
+        );
+        assert!(fields.unknown_labels.is_empty());
+        assert!(fields.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_faq_definition_lists_are_ignored() {
+        let html = r#"
+            <div class="cardData">
+                <dl>
+                    <dt>カード種類</dt><dd>シグニ</dd>
+                    <dt>パワー</dt><dd>5000</dd>
+                </dl>
+                <div class="cardFaq">
+                    <dl>
+                        <dt>このシグニのパワーはいくつですか？</dt><dd>5000です。</dd>
+                    </dl>
+                </div>
+            </div>
+        "#;
+
+        let fields = RawCardFields::from_html(html);
+        assert_eq!(fields.power, Some(5000));
+        assert!(fields.unknown_labels.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_labels_are_reported() {
+        let html = r#"
+            <dl>
+                <dt>レベル</dt><dd>3</dd>
+                <dt>エクシード</dt><dd>2</dd>
+            </dl>
+        "#;
+
+        let fields = RawCardFields::from_html(html);
+        assert_eq!(fields.level, Some(3));
+        assert_eq!(fields.unknown_labels, vec!["エクシード"]);
+        assert_eq!(
+            fields.warnings(),
+            vec!["未知の項目名: エクシード", "カード種類が見つかりません"]
+        );
+    }
+
+    #[test]
+    fn test_values_are_typed() {
+        let html = r#"
+            <dl>
+                <dt>カード種類</dt><dd>シグニ</dd>
+                <dt>クラス</dt><dd>奏羅：宇宙<br>精武：アーム</dd>
+                <dt>色</dt><dd>白青</dd>
+                <dt>コスト</dt><dd>《白》×１<br>《無》×２</dd>
+                <dt>パワー</dt><dd>12000</dd>
+                <dt>リミット</dt><dd>不明</dd>
+                <dt>フォーマット</dt><dd><img src="/images/icon_txt_format_key.png"></dd>
+                <dt>ストーリー</dt><dd><img src="/images/icon_txt_dissona.png"></dd>
+            </dl>
+        "#;
+
+        let fields = RawCardFields::from_html(html);
+        assert_eq!(fields.klass, vec!["奏羅：宇宙", "精武：アーム"]);
+        assert_eq!(fields.color, Some(1 << 1 | 1 << 2));
+        assert_eq!(fields.cost.as_deref(), Some("w1l2"));
+        assert_eq!(fields.power, Some(12000));
+        assert_eq!(fields.limit, None);
+        assert_eq!(fields.format, Some(3));
+        assert_eq!(fields.story.as_deref(), Some("dissona"));
+        assert_eq!(fields.warnings(), vec!["値を読み取れません: リミット=不明"]);
+    }
+}
@@ -0,0 +1,351 @@
+//! フィーチャー修正の書き込みと変更履歴
+//!
+//! `wix_card_feature_override` を変更する処理はすべてここを通し、同じトランザクションで
+//! `wix_card_feature_override_history` に変更前後の値を1行追記する。履歴は追記のみで、更新・削除はしない。
+
+use chrono::{DateTime, Utc};
+use sqlx::types::Json;
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::{
+    CardFeatureOverride, CardFeatureOverrideHistory, HistoryAction, OverrideState,
+};
+
+/// 作成または更新し、履歴を残す
+///
+/// `timestamps` を渡すと作成・更新日時をその値にする（インポート用）。省略時は現在時刻
+pub async fn save(
+    conn: &mut PgConnection,
+    changed_by: &str,
+    pronunciation: &str,
+    new: &OverrideState,
+    timestamps: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Result<CardFeatureOverride, sqlx::Error> {
+    let old = lock_current(conn, pronunciation).await?;
+    let saved = upsert(conn, pronunciation, new, timestamps).await?;
+    let action = if old.is_some() {
+        HistoryAction::Update
+    } else {
+        HistoryAction::Create
+    };
+    let old = old.as_ref().map(OverrideState::from);
+    record(
+        conn,
+        pronunciation,
+        action,
+        old.as_ref(),
+        Some(new),
+        changed_by,
+        None,
+    )
+    .await?;
+    Ok(saved)
+}
+
+/// 削除し、履歴を残す。オーバーライドがなければ何もせず `None`
+pub async fn remove(
+    conn: &mut PgConnection,
+    changed_by: &str,
+    pronunciation: &str,
+) -> Result<Option<CardFeatureOverride>, sqlx::Error> {
+    let Some(old) = lock_current(conn, pronunciation).await? else {
+        return Ok(None);
+    };
+    delete(conn, pronunciation).await?;
+    record(
+        conn,
+        pronunciation,
+        HistoryAction::Delete,
+        Some(&OverrideState::from(&old)),
+        None,
+        changed_by,
+        None,
+    )
+    .await?;
+    Ok(Some(old))
+}
+
+/// 履歴の変更前の状態に戻し、差し戻しとして履歴を残す
+///
+/// 作成の履歴を差し戻すとオーバーライドは削除される
+pub async fn revert(
+    conn: &mut PgConnection,
+    changed_by: &str,
+    entry: &CardFeatureOverrideHistory,
+) -> Result<CardFeatureOverrideHistory, sqlx::Error> {
+    let pronunciation = entry.pronunciation.as_str();
+    let current = lock_current(conn, pronunciation).await?;
+    let target = entry.old_state();
+    match &target {
+        Some(state) => {
+            upsert(conn, pronunciation, state, None).await?;
+        }
+        None => delete(conn, pronunciation).await?,
+    }
+    let current = current.as_ref().map(OverrideState::from);
+    record(
+        conn,
+        pronunciation,
+        HistoryAction::Revert,
+        current.as_ref(),
+        target.as_ref(),
+        changed_by,
+        Some(entry.id),
+    )
+    .await
+}
+
+/// 読みごとの履歴（新しい順）
+pub async fn list(
+    pool: &PgPool,
+    pronunciation: &str,
+) -> Result<Vec<CardFeatureOverrideHistory>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT * FROM wix_card_feature_override_history \
+         WHERE pronunciation = $1 ORDER BY changed_at DESC, id DESC",
+    )
+    .bind(pronunciation)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find(
+    conn: &mut PgConnection,
+    id: i64,
+) -> Result<Option<CardFeatureOverrideHistory>, sqlx::Error> {
+    sqlx::query_as("SELECT * FROM wix_card_feature_override_history WHERE id = $1")
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+}
+
+/// 変更前の行を取得してロックする（同じ読みへの同時変更で履歴が食い違わないように）
+///
+/// 行がまだない作成時は `FOR UPDATE` では何もロックできないため、
+/// 読みごとのアドバイザリーロックをトランザクションの終わりまで取って同じ読みへの変更を直列にする
+async fn lock_current(
+    conn: &mut PgConnection,
+    pronunciation: &str,
+) -> Result<Option<CardFeatureOverride>, sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(pronunciation)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query_as("SELECT * FROM wix_card_feature_override WHERE pronunciation = $1 FOR UPDATE")
+        .bind(pronunciation)
+        .fetch_optional(conn)
+        .await
+}
+
+async fn upsert(
+    conn: &mut PgConnection,
+    pronunciation: &str,
+    state: &OverrideState,
+    timestamps: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Result<CardFeatureOverride, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO wix_card_feature_override
+        (pronunciation, mode, features, burst_features, removed_features, removed_burst_features,
+         note, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7,
+                COALESCE($8, CURRENT_TIMESTAMP), COALESCE($9, CURRENT_TIMESTAMP))
+        ON CONFLICT (pronunciation) DO UPDATE SET
+            mode = EXCLUDED.mode,
+            features = EXCLUDED.features,
+            burst_features = EXCLUDED.burst_features,
+            removed_features = EXCLUDED.removed_features,
+            removed_burst_features = EXCLUDED.removed_burst_features,
+            note = EXCLUDED.note,
+            updated_at = EXCLUDED.updated_at
+        RETURNING *
+        "#,
+    )
+    .bind(pronunciation)
+    .bind(state.feature_override.mode.as_str())
+    .bind(Json(&state.feature_override.features))
+    .bind(Json(&state.feature_override.burst_features))
+    .bind(Json(&state.feature_override.removed_features))
+    .bind(Json(&state.feature_override.removed_burst_features))
+    .bind(&state.note)
+    .bind(timestamps.map(|(created_at, _)| created_at))
+    .bind(timestamps.map(|(_, updated_at)| updated_at))
+    .fetch_one(conn)
+    .await
+}
+
+async fn delete(conn: &mut PgConnection, pronunciation: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM wix_card_feature_override WHERE pronunciation = $1")
+        .bind(pronunciation)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn record(
+    conn: &mut PgConnection,
+    pronunciation: &str,
+    action: HistoryAction,
+    old: Option<&OverrideState>,
+    new: Option<&OverrideState>,
+    changed_by: &str,
+    reverted_from: Option<i64>,
+) -> Result<CardFeatureOverrideHistory, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO wix_card_feature_override_history
+        (pronunciation, action,
+         old_mode, old_features, old_burst_features,
+         old_removed_features, old_removed_burst_features, old_note,
+         new_mode, new_features, new_burst_features,
+         new_removed_features, new_removed_burst_features, new_note,
+         changed_by, changed_at, reverted_from_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
+                $15, CURRENT_TIMESTAMP, $16)
+        RETURNING *
+        "#,
+    )
+    .bind(pronunciation)
+    .bind(action.as_str())
+    .bind(old.map(|s| s.feature_override.mode.as_str()))
+    .bind(old.map(|s| Json(&s.feature_override.features)))
+    .bind(old.map(|s| Json(&s.feature_override.burst_features)))
+    .bind(old.map(|s| Json(&s.feature_override.removed_features)))
+    .bind(old.map(|s| Json(&s.feature_override.removed_burst_features)))
+    .bind(old.and_then(|s| s.note.clone()))
+    .bind(new.map(|s| s.feature_override.mode.as_str()))
+    .bind(new.map(|s| Json(&s.feature_override.features)))
+    .bind(new.map(|s| Json(&s.feature_override.burst_features)))
+    .bind(new.map(|s| Json(&s.feature_override.removed_features)))
+    .bind(new.map(|s| Json(&s.feature_override.removed_burst_features)))
+    .bind(new.and_then(|s| s.note.clone()))
+    .bind(changed_by)
+    .bind(reverted_from)
+    .fetch_one(conn)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+    use sqlx::Executor;
+    use std::time::Duration;
+    use webapp::repositories::FeatureOverride;
+
+    const SCHEMA: &str = "fixed_data_server_history_test";
+
+    /// `TEST_DATABASE_URL` のDBに、テスト用スキーマへ2つのテーブルだけを作って接続する
+    async fn test_pool(url: &str) -> PgPool {
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .after_connect(|conn, _| {
+                Box::pin(async move {
+                    conn.execute(format!("SET search_path TO {}", SCHEMA).as_str())
+                        .await?;
+                    Ok(())
+                })
+            })
+            .connect(url)
+            .await
+            .unwrap();
+        pool.execute(
+            format!(
+                r#"
+                DROP SCHEMA IF EXISTS {schema} CASCADE;
+                CREATE SCHEMA {schema};
+                CREATE TABLE {schema}.wix_card_feature_override (
+                    id BIGSERIAL PRIMARY KEY,
+                    pronunciation VARCHAR(200) NOT NULL UNIQUE,
+                    mode VARCHAR(16) NOT NULL,
+                    features JSONB NOT NULL,
+                    burst_features JSONB NOT NULL,
+                    removed_features JSONB NOT NULL,
+                    removed_burst_features JSONB NOT NULL,
+                    note TEXT,
+                    created_at TIMESTAMPTZ NOT NULL,
+                    updated_at TIMESTAMPTZ NOT NULL
+                );
+                CREATE TABLE {schema}.wix_card_feature_override_history (
+                    id BIGSERIAL PRIMARY KEY,
+                    pronunciation VARCHAR(200) NOT NULL,
+                    action VARCHAR(16) NOT NULL,
+                    old_mode VARCHAR(16),
+                    old_features JSONB,
+                    old_burst_features JSONB,
+                    old_removed_features JSONB,
+                    old_removed_burst_features JSONB,
+                    old_note TEXT,
+                    new_mode VARCHAR(16),
+                    new_features JSONB,
+                    new_burst_features JSONB,
+                    new_removed_features JSONB,
+                    new_removed_burst_features JSONB,
+                    new_note TEXT,
+                    changed_by VARCHAR(100) NOT NULL,
+                    changed_at TIMESTAMPTZ NOT NULL,
+                    reverted_from_id BIGINT
+                );
+                "#,
+                schema = SCHEMA
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+        pool
+    }
+
+    fn state(feature: &str) -> OverrideState {
+        OverrideState {
+            feature_override: FeatureOverride {
+                features: vec![feature.to_string()],
+                ..Default::default()
+            },
+            note: None,
+        }
+    }
+
+    /// まだオーバーライドのない読みを同時に作成しても、後の方は更新として履歴に残る
+    ///
+    /// Postgresが必要なため `TEST_DATABASE_URL` が未設定なら何もしない
+    #[tokio::test]
+    async fn test_concurrent_creates_are_serialized() {
+        let Ok(url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL が未設定のためスキップします");
+            return;
+        };
+        let pool = test_pool(&url).await;
+
+        let mut first = pool.begin().await.unwrap();
+        save(&mut first, "a", "てすと", &state("Draw"), None)
+            .await
+            .unwrap();
+
+        let second_pool = pool.clone();
+        let second = tokio::spawn(async move {
+            let mut tx = second_pool.begin().await.unwrap();
+            save(&mut tx, "b", "てすと", &state("Salvage"), None)
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+        });
+        // 2つ目が1つ目のロック待ちになってからコミットする
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        first.commit().await.unwrap();
+        second.await.unwrap();
+
+        let actions: Vec<HistoryAction> = list(&pool, "てすと")
+            .await
+            .unwrap()
+            .into_iter()
+            .rev()
+            .map(|entry| entry.action)
+            .collect();
+        assert_eq!(actions, vec![HistoryAction::Create, HistoryAction::Update]);
+
+        pool.execute(format!("DROP SCHEMA {} CASCADE", SCHEMA).as_str())
+            .await
+            .unwrap();
+    }
+}
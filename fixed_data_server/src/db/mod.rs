@@ -1 +1,3 @@
-pub mod repository;
\ No newline at end of file
+pub mod history;
+pub mod repository;
+pub mod review;
@@ -0,0 +1,173 @@
+//! フィーチャー確認キュー（`wix_card_review_item`）
+//!
+//! 同じ読みと理由の組は1件にまとめる。積み直したときに詳細が変わっていれば、対応済みの項目も確認待ちに戻す。
+
+use sqlx::{PgConnection, PgPool};
+
+use crate::models::{CardReviewItem, ReviewCandidate, ReviewQuery, ReviewReason, ReviewStatus};
+
+/// 絞り込んで優先度の高い順に返す。状態の指定がなければ未対応（確認待ち・担当中）のみ
+pub async fn list(pool: &PgPool, query: &ReviewQuery) -> Result<Vec<CardReviewItem>, sqlx::Error> {
+    let statuses: Vec<&str> = match query.status {
+        Some(status) => vec![status.as_str()],
+        None => vec![
+            ReviewStatus::Pending.as_str(),
+            ReviewStatus::Claimed.as_str(),
+        ],
+    };
+    sqlx::query_as(
+        r#"
+        SELECT * FROM wix_card_review_item
+        WHERE status = ANY($1)
+          AND ($2::text IS NULL OR reason = $2)
+          AND ($3::text IS NULL OR pronunciation = $3)
+        ORDER BY priority DESC, id
+        "#,
+    )
+    .bind(&statuses)
+    .bind(query.reason.map(|reason| reason.as_str()))
+    .bind(&query.pronunciation)
+    .fetch_all(pool)
+    .await
+}
+
+/// 取得してロックする（同じ項目への同時操作で状態が食い違わないように）
+pub async fn lock(conn: &mut PgConnection, id: i64) -> Result<Option<CardReviewItem>, sqlx::Error> {
+    sqlx::query_as("SELECT * FROM wix_card_review_item WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+}
+
+/// 候補を積む。既にあれば詳細を更新し、詳細が変わった対応済みの項目は確認待ちに戻す
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    candidate: &ReviewCandidate,
+) -> Result<CardReviewItem, sqlx::Error> {
+    let existing: Option<CardReviewItem> = sqlx::query_as(
+        "SELECT * FROM wix_card_review_item WHERE pronunciation = $1 AND reason = $2 FOR UPDATE",
+    )
+    .bind(&candidate.pronunciation)
+    .bind(candidate.reason.as_str())
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match existing {
+        None => {
+            sqlx::query_as(
+                r#"
+                INSERT INTO wix_card_review_item
+                (pronunciation, reason, detail, priority, status, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, 'pending', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                ON CONFLICT (pronunciation, reason) DO UPDATE SET
+                    detail = EXCLUDED.detail,
+                    updated_at = EXCLUDED.updated_at
+                RETURNING *
+                "#,
+            )
+            .bind(&candidate.pronunciation)
+            .bind(candidate.reason.as_str())
+            .bind(&candidate.detail)
+            .bind(candidate.reason.priority())
+            .fetch_one(conn)
+            .await
+        }
+        Some(item) if item.detail == candidate.detail => Ok(item),
+        Some(item) => {
+            let reopen = item.status.is_resolved();
+            sqlx::query_as(
+                r#"
+                UPDATE wix_card_review_item SET
+                    detail = $2,
+                    priority = $3,
+                    status = CASE WHEN $4 THEN 'pending' ELSE status END,
+                    claimed_by = CASE WHEN $4 THEN NULL ELSE claimed_by END,
+                    claimed_at = CASE WHEN $4 THEN NULL ELSE claimed_at END,
+                    resolved_by = CASE WHEN $4 THEN NULL ELSE resolved_by END,
+                    resolved_at = CASE WHEN $4 THEN NULL ELSE resolved_at END,
+                    resolution_note = CASE WHEN $4 THEN NULL ELSE resolution_note END,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+                RETURNING *
+                "#,
+            )
+            .bind(item.id)
+            .bind(&candidate.detail)
+            .bind(candidate.reason.priority())
+            .bind(reopen)
+            .fetch_one(conn)
+            .await
+        }
+    }
+}
+
+/// `reasons` の未着手の項目のうち、`current` に含まれないもの（検出されなくなったもの）を削除する
+///
+/// 担当中の項目は確認作業を横から消さないよう残す
+pub async fn remove_stale(
+    conn: &mut PgConnection,
+    reasons: &[ReviewReason],
+    current: &[ReviewCandidate],
+) -> Result<u64, sqlx::Error> {
+    let reasons: Vec<&str> = reasons.iter().map(|reason| reason.as_str()).collect();
+    let pronunciations: Vec<&str> = current.iter().map(|c| c.pronunciation.as_str()).collect();
+    let current_reasons: Vec<&str> = current.iter().map(|c| c.reason.as_str()).collect();
+    let result = sqlx::query(
+        r#"
+        DELETE FROM wix_card_review_item
+        WHERE reason = ANY($1)
+          AND status = 'pending'
+          AND (pronunciation, reason) NOT IN (
+              SELECT * FROM UNNEST($2::text[], $3::text[])
+          )
+        "#,
+    )
+    .bind(&reasons)
+    .bind(&pronunciations)
+    .bind(&current_reasons)
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// 状態を変える。担当なら担当者、承認・却下なら対応者とメモを記録する
+pub async fn update_status(
+    conn: &mut PgConnection,
+    id: i64,
+    status: ReviewStatus,
+    caller: &str,
+    note: Option<&str>,
+) -> Result<CardReviewItem, sqlx::Error> {
+    if status.is_resolved() {
+        sqlx::query_as(
+            r#"
+            UPDATE wix_card_review_item SET
+                status = $2, resolved_by = $3, resolved_at = CURRENT_TIMESTAMP,
+                resolution_note = $4, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(caller)
+        .bind(note)
+        .fetch_one(conn)
+        .await
+    } else {
+        sqlx::query_as(
+            r#"
+            UPDATE wix_card_review_item SET
+                status = $2, claimed_by = $3, claimed_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(caller)
+        .fetch_one(conn)
+        .await
+    }
+}
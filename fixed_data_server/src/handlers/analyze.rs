@@ -1,70 +1,489 @@
+use analyzer::card_analyzer::{CardRepository, RawCardWithProduct, SimpleRawCardAnalyzer};
+use analyzer::card_diff::CardDiff;
 use axum::{
-    extract::{Path, State},
+    extract::{
+        rejection::{PathRejection, QueryRejection},
+        Path, Query, State,
+    },
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use utoipa::{IntoParams, ToSchema};
+use webapp::error::{ApiError, ApiErrorBody};
+
+use super::overrides::{convert_bits_to_features, convert_burst_bits_to_features};
+use super::review::detection_changed_candidate;
+use crate::db::review;
+
+/// 保持する完了済みジョブの上限（古いものから捨てる）
+const MAX_FINISHED_JOBS: usize = 100;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnalyzeQuery {
+    /// バックグラウンドで実行し、すぐにジョブを返す（結果は `/api/analyze/jobs/{id}` で取得）
+    #[serde(default)]
+    pub background: bool,
+    /// 解析と差分の計算だけ行い、wix_cardには書き込まない
+    #[serde(default)]
+    pub dry_run: bool,
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AnalyzeResponse {
     pub pronunciation: String,
     pub cards_analyzed: usize,
     pub success: bool,
     pub message: String,
+    /// 書き込みを行わなかった
+    pub dry_run: bool,
+    /// 収録ごとの解析結果
+    pub results: Vec<CardAnalysisResult>,
+}
+
+/// 1枚（1収録）の解析結果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardAnalysisResult {
+    pub code: String,
+    pub success: bool,
+    pub error: Option<String>,
+    /// 解析後のフィーチャ
+    pub features: Vec<String>,
+    /// 解析後のバーストフィーチャ
+    pub burst_features: Vec<String>,
+    /// 解析前のwix_cardとの差分
+    pub diff: Option<CardDiffResponse>,
+    /// サイトのレイアウト変更が疑われる点
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldChangeResponse {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// 解析前後の差分（[`CardDiff`] のJSON表現）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CardDiffResponse {
+    /// wix_cardにまだ存在しなかった
+    pub is_new: bool,
+    pub changes: Vec<FieldChangeResponse>,
+    pub features_added: Vec<String>,
+    pub features_removed: Vec<String>,
+    pub burst_features_added: Vec<String>,
+    pub burst_features_removed: Vec<String>,
+    pub klasses_added: Vec<String>,
+    pub klasses_removed: Vec<String>,
+}
+
+impl From<CardDiff> for CardDiffResponse {
+    fn from(diff: CardDiff) -> Self {
+        Self {
+            is_new: diff.is_new,
+            changes: diff
+                .changes
+                .into_iter()
+                .map(|change| FieldChangeResponse {
+                    field: change.field.to_string(),
+                    before: change.before,
+                    after: change.after,
+                })
+                .collect(),
+            features_added: diff.features_added,
+            features_removed: diff.features_removed,
+            burst_features_added: diff.burst_features_added,
+            burst_features_removed: diff.burst_features_removed,
+            klasses_added: diff.klasses_added,
+            klasses_removed: diff.klasses_removed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// バックグラウンドの解析ジョブ
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnalysisJob {
+    pub id: u64,
+    pub pronunciation: String,
+    pub dry_run: bool,
+    pub status: JobStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// 完了時の結果
+    pub result: Option<AnalyzeResponse>,
+    /// 失敗時のエラー
+    pub error: Option<String>,
 }
 
+/// 実行中・完了済みの解析ジョブ（プロセス内のみで保持し、再起動で消える）
+#[derive(Clone, Default)]
+pub struct AnalysisJobs {
+    next_id: Arc<AtomicU64>,
+    jobs: Arc<Mutex<BTreeMap<u64, AnalysisJob>>>,
+}
+
+impl AnalysisJobs {
+    fn start(&self, pronunciation: &str, dry_run: bool) -> AnalysisJob {
+        let job = AnalysisJob {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            pronunciation: pronunciation.to_string(),
+            dry_run,
+            status: JobStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            result: None,
+            error: None,
+        };
+
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.insert(job.id, job.clone());
+        let finished: Vec<u64> = jobs
+            .values()
+            .filter(|job| job.status != JobStatus::Running)
+            .map(|job| job.id)
+            .collect();
+        for id in finished.iter().take(finished.len().saturating_sub(MAX_FINISHED_JOBS)) {
+            jobs.remove(id);
+        }
+        job
+    }
+
+    fn finish(&self, id: u64, outcome: Result<AnalyzeResponse, ApiError>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(&id) else {
+            return;
+        };
+        job.finished_at = Some(Utc::now());
+        match outcome {
+            Ok(result) => {
+                job.status = JobStatus::Completed;
+                job.result = Some(result);
+            }
+            Err(e) => {
+                tracing::error!(job = id, error = %e, "解析ジョブが失敗しました");
+                job.status = JobStatus::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// `task` を別タスクで実行して結果を記録する（パニックも失敗として記録し、実行中のまま残さない）
+    fn spawn<F>(&self, id: u64, task: F)
+    where
+        F: Future<Output = Result<AnalyzeResponse, ApiError>> + Send + 'static,
+    {
+        let jobs = self.clone();
+        tokio::spawn(async move {
+            let outcome = match tokio::spawn(task).await {
+                Ok(outcome) => outcome,
+                Err(e) => Err(ApiError::Internal(format!(
+                    "解析ジョブが異常終了しました: {}",
+                    e
+                ))),
+            };
+            jobs.finish(id, outcome);
+        });
+    }
+
+    pub fn get(&self, id: u64) -> Option<AnalysisJob> {
+        self.jobs.lock().unwrap().get(&id).cloned()
+    }
+}
+
+/// 同じ読みのカードを生データから再解析する
+///
+/// アナライザーはプロセス内で実行する。`background=true` ならジョブを登録して202を返し、
+/// 結果はジョブの取得APIでポーリングする
+#[utoipa::path(
+    post,
+    path = "/api/analyze/{pronunciation}",
+    tag = "analyze",
+    params(("pronunciation" = String, Path, description = "カードの読み"), AnalyzeQuery),
+    responses(
+        (status = 200, description = "解析結果と解析前後の差分", body = AnalyzeResponse),
+        (status = 202, description = "バックグラウンドで開始したジョブ", body = AnalysisJob),
+        (status = 400, description = "クエリパラメータが不正", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn analyze_card(
     State(pool): State<PgPool>,
-    Path(pronunciation): Path<String>,
-) -> Result<Json<AnalyzeResponse>, StatusCode> {
-    // Get card numbers for this pronunciation
-    let card_numbers: Vec<String> = sqlx::query_scalar(
-        "SELECT code FROM wix_card WHERE pronunciation = $1"
+    State(jobs): State<AnalysisJobs>,
+    path: Result<Path<String>, PathRejection>,
+    query: Result<Query<AnalyzeQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Path(pronunciation) = path?;
+    let Query(query) = query?;
+    if !query.background {
+        let response = run_analysis(&pool, &pronunciation, query.dry_run).await?;
+        return Ok(Json(response).into_response());
+    }
+
+    let job = jobs.start(&pronunciation, query.dry_run);
+    jobs.spawn(job.id, async move {
+        run_analysis(&pool, &pronunciation, query.dry_run).await
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(job)).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analyze/jobs/{id}",
+    tag = "analyze",
+    params(("id" = u64, Path, description = "ジョブID")),
+    responses(
+        (status = 200, description = "ジョブの状態（完了していれば結果を含む）", body = AnalysisJob),
+        (status = 400, description = "IDが数値でない", body = ApiErrorBody),
+        (status = 404, description = "ジョブがない（再起動や古いジョブの破棄で消えた場合も含む）", body = ApiErrorBody)
     )
-    .bind(&pronunciation)
-    .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+)]
+pub async fn get_analysis_job(
+    State(jobs): State<AnalysisJobs>,
+    path: Result<Path<u64>, PathRejection>,
+) -> Result<Json<AnalysisJob>, ApiError> {
+    let Path(id) = path?;
+    jobs.get(id)
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("解析ジョブがありません: {}", id)))
+}
+
+async fn run_analysis(
+    pool: &PgPool,
+    pronunciation: &str,
+    dry_run: bool,
+) -> Result<AnalyzeResponse, ApiError> {
+    let card_numbers: Vec<String> =
+        sqlx::query_scalar("SELECT code FROM wix_card WHERE pronunciation = $1 ORDER BY code")
+            .bind(pronunciation)
+            .fetch_all(pool)
+            .await?;
 
     if card_numbers.is_empty() {
-        return Ok(Json(AnalyzeResponse {
-            pronunciation: pronunciation.clone(),
+        return Ok(AnalyzeResponse {
+            pronunciation: pronunciation.to_string(),
             cards_analyzed: 0,
             success: false,
             message: "No cards found with this pronunciation".to_string(),
-        }));
+            dry_run,
+            results: Vec::new(),
+        });
     }
 
-    // Run analyzer for each card number
-    let mut success_count = 0;
-    let mut errors = Vec::new();
+    let raw_cards: Vec<RawCardWithProduct> = sqlx::query_as(
+        r#"
+        SELECT id, card_number, name, raw_html, skill_text, life_burst_text,
+               source_url, scraped_at, last_analyzed_at, is_analyzed, analysis_error,
+               product_id
+        FROM wix_rawcard
+        WHERE card_number = ANY($1)
+        "#,
+    )
+    .bind(&card_numbers)
+    .fetch_all(pool)
+    .await?;
 
-    for card_number in &card_numbers {
-        let output = Command::new("cargo")
-            .args(&["run", "-p", "analyzer", "--", "--card-number", card_number])
-            .output()
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let analyzer = SimpleRawCardAnalyzer::new();
+    let card_repo = CardRepository::new(Arc::new(pool.clone()));
 
-        if output.status.success() {
-            success_count += 1;
-        } else {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            errors.push(format!("{}: {}", card_number, error_msg));
-        }
+    let mut results = Vec::with_capacity(card_numbers.len());
+    for code in &card_numbers {
+        let result = match raw_cards.iter().find(|raw| &raw.card_number == code) {
+            Some(raw_card) => analyze_one(&analyzer, &card_repo, raw_card, dry_run).await,
+            None => Err("生データ（wix_rawcard）がありません".to_string()),
+        };
+        results.push(result.unwrap_or_else(|error| CardAnalysisResult {
+            code: code.clone(),
+            success: false,
+            error: Some(error),
+            features: Vec::new(),
+            burst_features: Vec::new(),
+            diff: None,
+            warnings: Vec::new(),
+        }));
+    }
+
+    if !dry_run {
+        enqueue_detection_changes(pool, pronunciation, &results).await;
     }
 
-    let response = AnalyzeResponse {
-        pronunciation,
+    let success_count = results.iter().filter(|result| result.success).count();
+    let errors: Vec<String> = results
+        .iter()
+        .filter_map(|result| {
+            result
+                .error
+                .as_ref()
+                .map(|error| format!("{}: {}", result.code, error))
+        })
+        .collect();
+
+    Ok(AnalyzeResponse {
+        pronunciation: pronunciation.to_string(),
         cards_analyzed: success_count,
         success: success_count == card_numbers.len(),
         message: if errors.is_empty() {
             format!("Successfully analyzed {} cards", success_count)
         } else {
-            format!("Analyzed {} of {} cards. Errors: {}", success_count, card_numbers.len(), errors.join("; "))
+            format!(
+                "Analyzed {} of {} cards. Errors: {}",
+                success_count,
+                card_numbers.len(),
+                errors.join("; ")
+            )
         },
+        dry_run,
+        results,
+    })
+}
+
+/// フィーチャーが変わった収録を確認キューに積む
+///
+/// 解析結果は保存済みなので、積めなくても解析は失敗扱いにしない
+async fn enqueue_detection_changes(pool: &PgPool, pronunciation: &str, results: &[CardAnalysisResult]) {
+    let diffs: Vec<(String, CardDiffResponse)> = results
+        .iter()
+        .filter_map(|result| Some((result.code.clone(), result.diff.clone()?)))
+        .collect();
+    let Some(candidate) = detection_changed_candidate(pronunciation, &diffs) else {
+        return;
     };
 
-    Ok(Json(response))
-}
\ No newline at end of file
+    let enqueued = async {
+        let mut conn = pool.acquire().await?;
+        review::enqueue(&mut conn, &candidate).await
+    }
+    .await;
+    if let Err(e) = enqueued {
+        tracing::warn!(%pronunciation, error = %e, "検出結果の変化を確認キューに積めませんでした");
+    }
+}
+
+/// 1枚を解析して既存行との差分を取り、`dry_run` でなければ保存する
+async fn analyze_one(
+    analyzer: &SimpleRawCardAnalyzer,
+    card_repo: &CardRepository,
+    raw_card: &RawCardWithProduct,
+    dry_run: bool,
+) -> Result<CardAnalysisResult, String> {
+    let analyzed = analyzer
+        .analyze_with_product_id(&raw_card.to_raw_card_db(), raw_card.product_id)
+        .await
+        .map_err(|e| format!("解析失敗: {}", e))?;
+
+    let existing = card_repo
+        .find_existing_card(&analyzed.create_card.code)
+        .await
+        .map_err(|e| format!("既存カードの取得に失敗: {}", e))?;
+    let diff = CardDiff::between(existing.as_ref(), &analyzed);
+
+    let card = &analyzed.create_card;
+    let result = CardAnalysisResult {
+        code: raw_card.card_number.clone(),
+        success: true,
+        error: None,
+        features: convert_bits_to_features(card.feature_bits1 as u64, card.feature_bits2 as u64),
+        burst_features: convert_burst_bits_to_features(card.burst_bits as u64),
+        diff: Some(diff.into()),
+        warnings: analyzed.warnings.clone(),
+    };
+
+    if !dry_run {
+        card_repo
+            .save_card_with_klass(analyzed)
+            .await
+            .map_err(|e| format!("保存失敗: {}", e))?;
+        card_repo
+            .mark_raw_card_analyzed(raw_card.id)
+            .await
+            .map_err(|e| format!("解析済みの記録に失敗: {}", e))?;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response() -> AnalyzeResponse {
+        AnalyzeResponse {
+            pronunciation: "てすと".to_string(),
+            cards_analyzed: 0,
+            success: true,
+            message: String::new(),
+            dry_run: true,
+            results: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_job_lifecycle() {
+        let jobs = AnalysisJobs::default();
+        let job = jobs.start("てすと", true);
+        assert_eq!(jobs.get(job.id).unwrap().status, JobStatus::Running);
+
+        jobs.finish(job.id, Ok(response()));
+        let finished = jobs.get(job.id).unwrap();
+        assert_eq!(finished.status, JobStatus::Completed);
+        assert!(finished.result.is_some() && finished.finished_at.is_some());
+
+        let failed = jobs.start("てすと", false);
+        jobs.finish(failed.id, Err(ApiError::Internal("boom".to_string())));
+        assert_eq!(jobs.get(failed.id).unwrap().status, JobStatus::Failed);
+    }
+
+    fn explode() -> Result<AnalyzeResponse, ApiError> {
+        panic!("boom")
+    }
+
+    #[tokio::test]
+    async fn test_panicking_job_is_recorded_as_failed() {
+        let jobs = AnalysisJobs::default();
+        let job = jobs.start("てすと", true);
+        jobs.spawn(job.id, async { explode() });
+
+        for _ in 0..100 {
+            if jobs.get(job.id).unwrap().status != JobStatus::Running {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let failed = jobs.get(job.id).unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert!(failed.error.unwrap().contains("boom"));
+    }
+
+    #[test]
+    fn test_old_finished_jobs_are_discarded() {
+        let jobs = AnalysisJobs::default();
+        let first = jobs.start("てすと", true);
+        jobs.finish(first.id, Ok(response()));
+        for _ in 0..MAX_FINISHED_JOBS {
+            let job = jobs.start("てすと", true);
+            jobs.finish(job.id, Ok(response()));
+        }
+        let running = jobs.start("てすと", true);
+
+        assert!(jobs.get(first.id).is_none());
+        assert!(jobs.get(first.id + 1).is_some());
+        assert!(jobs.get(running.id).is_some());
+    }
+}
@@ -1,81 +1,541 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    Json,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        Query, State,
+    },
+    Extension, Json,
 };
-use chrono::Utc;
+use webapp::auth::Caller;
+use webapp::error::{ApiError, ApiErrorBody};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sqlx::PgPool;
-use crate::models::{CardFeatureOverride, ImportExportData};
+use std::collections::{HashMap, HashSet};
+use crate::db::history;
+use crate::handlers::overrides::convert_state_to_snapshot;
+use crate::models::{CardFeatureOverride, ImportExportData, OverrideSnapshot, OverrideState};
+use feature::feature::{BurstFeature, CardFeature, HashSetToBits};
+use utoipa::{IntoParams, ToSchema};
+use webapp::repositories::{FeatureOverride, OverrideMode};
 
+/// エクスポート形式の現在のバージョン
+///
+/// - 1.0: フィーチャーをビット値（`fixed_bits1` / `fixed_bits2` / `fixed_burst_bits`）で持つ
+/// - 2.0: フィーチャーを列挙子名（`features` / `burst_features`）で持つ
+/// - 2.1: 部分修正（`mode` / `removed_features` / `removed_burst_features`）を持つ
+pub const EXPORT_VERSION: &str = "2.1";
+
+/// 古い形式を1つ新しい形式に変換する段（変換元のバージョン、変換先のバージョン、変換処理）
+///
+/// 形式を変えたら `EXPORT_VERSION` を上げ、1つ前のバージョンからの段をここに追加する
+type Migration = (&'static str, &'static str, fn(&mut Value) -> Result<(), String>);
+
+const MIGRATIONS: &[Migration] = &[
+    ("1.0", "2.0", bits_to_feature_names),
+    ("2.0", "2.1", add_replace_mode),
+];
+
+/// 1.0 → 2.0: ビット値を現在の `define_features!` の定義で列挙子名に変換する
+///
+/// 現在の定義にないビットは列挙子名にできないため、黙って落とさずに変換を失敗させる
+fn bits_to_feature_names(data: &mut Value) -> Result<(), String> {
+    let (known_bits1, known_bits2) = CardFeature::create_vec()
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .to_bits();
+    let known_burst_bits = BurstFeature::create_vec()
+        .iter()
+        .fold(0, |bits, feature| bits | 1_i64 << feature.to_bit_shift());
+
+    let overrides = data
+        .get_mut("overrides")
+        .and_then(Value::as_array_mut)
+        .ok_or("overridesがありません")?;
+    let mut unknown_bits = Vec::new();
+    for o in overrides {
+        let o = o.as_object_mut().ok_or("overridesの要素がオブジェクトではありません")?;
+        let mut take_bits = |key: &str| {
+            o.remove(key)
+                .and_then(|v| v.as_i64())
+                .ok_or_else(|| format!("{} がありません", key))
+        };
+        let bits1 = take_bits("fixed_bits1")?;
+        let bits2 = take_bits("fixed_bits2")?;
+        let burst_bits = take_bits("fixed_burst_bits")?;
+
+        let unknown = [
+            ("fixed_bits1", bits1 & !known_bits1),
+            ("fixed_bits2", bits2 & !known_bits2),
+            ("fixed_burst_bits", burst_bits & !known_burst_bits),
+        ];
+        if unknown.iter().any(|(_, bits)| *bits != 0) {
+            let pronunciation = o.get("pronunciation").and_then(Value::as_str).unwrap_or("?");
+            let bits: Vec<String> = unknown
+                .iter()
+                .filter(|(_, bits)| *bits != 0)
+                .map(|(key, bits)| format!("{}={:#x}", key, bits))
+                .collect();
+            unknown_bits.push(format!("{}（{}）", pronunciation, bits.join(", ")));
+        }
+
+        let features: Vec<&str> = CardFeature::from_bits(bits1, bits2)
+            .iter()
+            .map(CardFeature::name)
+            .collect();
+        let burst_features: Vec<&str> = BurstFeature::from_bits(burst_bits)
+            .iter()
+            .map(BurstFeature::name)
+            .collect();
+        o.insert("features".to_string(), features.into());
+        o.insert("burst_features".to_string(), burst_features.into());
+    }
+    if !unknown_bits.is_empty() {
+        return Err(format!(
+            "現在の定義にないビットがあります: {}",
+            unknown_bits.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// 2.0 → 2.1: 部分修正の導入前の修正はすべて置き換え
+fn add_replace_mode(data: &mut Value) -> Result<(), String> {
+    let overrides = data
+        .get_mut("overrides")
+        .and_then(Value::as_array_mut)
+        .ok_or("overridesがありません")?;
+    for o in overrides {
+        let o = o.as_object_mut().ok_or("overridesの要素がオブジェクトではありません")?;
+        o.insert("mode".to_string(), OverrideMode::Replace.as_str().into());
+        o.insert("removed_features".to_string(), Value::Array(Vec::new()));
+        o.insert("removed_burst_features".to_string(), Value::Array(Vec::new()));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/export",
+    tag = "import_export",
+    responses(
+        (status = 200, description = "全オーバーライド", body = ImportExportData),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn export_all(
     State(pool): State<PgPool>,
-) -> Result<Json<ImportExportData>, StatusCode> {
+) -> Result<Json<ImportExportData>, ApiError> {
     let overrides = sqlx::query_as::<_, CardFeatureOverride>(
         "SELECT * FROM wix_card_feature_override ORDER BY pronunciation"
     )
     .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await?;
 
     let export_data = ImportExportData {
         overrides,
         exported_at: Utc::now(),
-        version: "1.0".to_string(),
+        version: EXPORT_VERSION.to_string(),
     };
 
     Ok(Json(export_data))
 }
 
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    /// 検証と差分の計算だけ行い、書き込まない
+    #[serde(default)]
+    pub dry_run: bool,
+    /// 競合（ファイルより後にこのサーバーで変更されたオーバーライド）もファイルの内容で上書きする
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// エクスポートしたファイルを取り込む
+///
+/// 古いバージョンの形式は現在の形式に変換してから取り込む。
+/// 全件を1トランザクションで書き込み、検証エラーや競合が1件でもあれば何も書き込まない
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    tag = "import_export",
+    params(ImportQuery),
+    request_body = ImportExportData,
+    responses(
+        (status = 200, description = "取り込み結果（ドライランなら取り込む予定の内容）", body = ImportReport),
+        (status = 400, description = "形式・内容が不正、または競合がある。detailsに取り込み結果が入る", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn import_data(
     State(pool): State<PgPool>,
-    Json(data): Json<ImportExportData>,
-) -> Result<Json<ImportResult>, StatusCode> {
-    let mut imported = 0;
-    let mut errors = Vec::new();
-
-    for override_data in data.overrides {
-        let result = sqlx::query(
-            r#"
-            INSERT INTO wix_card_feature_override 
-            (pronunciation, fixed_bits1, fixed_bits2, fixed_burst_bits, note, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            ON CONFLICT (pronunciation) DO UPDATE SET
-                fixed_bits1 = EXCLUDED.fixed_bits1,
-                fixed_bits2 = EXCLUDED.fixed_bits2,
-                fixed_burst_bits = EXCLUDED.fixed_burst_bits,
-                note = EXCLUDED.note,
-                updated_at = EXCLUDED.updated_at
-            "#
+    Extension(caller): Extension<Caller>,
+    query: Result<Query<ImportQuery>, QueryRejection>,
+    data: Result<Json<Value>, JsonRejection>,
+) -> Result<Json<ImportReport>, ApiError> {
+    let Query(query) = query?;
+    let Json(data) = data?;
+    let source_version = data
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let data = migrate(data).map_err(ApiError::validation)?;
+
+    let pronunciations: Vec<String> = data
+        .overrides
+        .iter()
+        .map(|o| o.pronunciation.clone())
+        .collect();
+
+    let mut tx = pool.begin().await?;
+    let known: HashSet<String> = sqlx::query_scalar(
+        "SELECT DISTINCT pronunciation FROM wix_card WHERE pronunciation = ANY($1)"
+    )
+    .bind(&pronunciations)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .collect();
+    let existing: HashMap<String, CardFeatureOverride> = sqlx::query_as::<_, CardFeatureOverride>(
+        "SELECT * FROM wix_card_feature_override WHERE pronunciation = ANY($1) FOR UPDATE"
+    )
+    .bind(&pronunciations)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|o| (o.pronunciation.clone(), o))
+    .collect();
+
+    let mut report = plan_import(&data.overrides, &existing, &known, source_version, query.dry_run);
+
+    if !report.errors.is_empty() || (!report.conflicts.is_empty() && !query.overwrite) {
+        if query.dry_run {
+            return Ok(Json(report));
+        }
+        let message = if report.errors.is_empty() {
+            format!(
+                "{}件の競合があります。overwrite=true で上書きできます",
+                report.conflicts.len()
+            )
+        } else {
+            format!("{}件の不正な行があります", report.errors.len())
+        };
+        return Err(ApiError::Validation {
+            message,
+            details: serde_json::to_value(&report).ok(),
+        });
+    }
+
+    if query.dry_run {
+        return Ok(Json(report));
+    }
+
+    // ここまで来た競合は上書きを指定されている
+    let to_save: HashSet<&str> = report
+        .created
+        .iter()
+        .chain(&report.updated)
+        .map(String::as_str)
+        .chain(report.conflicts.iter().map(|c| c.pronunciation.as_str()))
+        .collect();
+    for override_data in &data.overrides {
+        let pronunciation = override_data.pronunciation.as_str();
+        if !to_save.contains(pronunciation) {
+            continue;
+        }
+        history::save(
+            &mut tx,
+            &caller.name,
+            pronunciation,
+            &normalized_state(override_data),
+            Some((override_data.created_at, override_data.updated_at)),
         )
-        .bind(&override_data.pronunciation)
-        .bind(override_data.fixed_bits1)
-        .bind(override_data.fixed_bits2)
-        .bind(override_data.fixed_burst_bits)
-        .bind(&override_data.note)
-        .bind(override_data.created_at)
-        .bind(override_data.updated_at)
-        .execute(&pool)
-        .await;
-
-        match result {
-            Ok(_) => imported += 1,
-            Err(e) => errors.push(format!("{}: {}", override_data.pronunciation, e)),
+        .await?;
+    }
+    tx.commit().await?;
+
+    report.applied = true;
+    Ok(Json(report))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportReport {
+    /// 取り込んだファイルの形式のバージョン（変換前）
+    pub source_version: String,
+    pub dry_run: bool,
+    /// 書き込んだか
+    pub applied: bool,
+    /// 新しく作成する読み
+    pub created: Vec<String>,
+    /// 更新する読み
+    pub updated: Vec<String>,
+    /// 内容が同じで書き込まない読み
+    pub unchanged: Vec<String>,
+    /// このサーバーでファイルより後に変更されている読み
+    pub conflicts: Vec<ImportConflict>,
+    /// 取り込めない行
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportConflict {
+    pub pronunciation: String,
+    pub current: OverrideSnapshot,
+    pub current_updated_at: DateTime<Utc>,
+    pub imported: OverrideSnapshot,
+    pub imported_updated_at: DateTime<Utc>,
+}
+
+/// 古い形式を現在の形式に変換して読み込む
+fn migrate(mut data: Value) -> Result<ImportExportData, String> {
+    loop {
+        let version = data
+            .get("version")
+            .and_then(Value::as_str)
+            .ok_or("versionがありません")?
+            .to_string();
+        if version == EXPORT_VERSION {
+            break;
         }
+        let (_, to, step) = MIGRATIONS
+            .iter()
+            .find(|(from, _, _)| *from == version)
+            .ok_or_else(|| {
+                format!(
+                    "未対応のバージョンです: {}（このサーバーは {} まで対応）",
+                    version, EXPORT_VERSION
+                )
+            })?;
+        step(&mut data).map_err(|e| format!("バージョン {} からの変換に失敗しました: {}", version, e))?;
+        data["version"] = Value::String(to.to_string());
     }
 
-    let success = errors.is_empty();
-    let result = ImportResult {
-        imported,
-        errors: if errors.is_empty() { None } else { Some(errors) },
-        success,
+    serde_json::from_value(data).map_err(|e| format!("形式が不正です: {}", e))
+}
+
+/// 取り込む行を検証し、作成・更新・変更なし・競合に振り分ける
+fn plan_import(
+    overrides: &[CardFeatureOverride],
+    existing: &HashMap<String, CardFeatureOverride>,
+    known_pronunciations: &HashSet<String>,
+    source_version: String,
+    dry_run: bool,
+) -> ImportReport {
+    let mut report = ImportReport {
+        source_version,
+        dry_run,
+        applied: false,
+        created: Vec::new(),
+        updated: Vec::new(),
+        unchanged: Vec::new(),
+        conflicts: Vec::new(),
+        errors: Vec::new(),
     };
+    let mut seen = HashSet::new();
+
+    for imported in overrides {
+        let pronunciation = &imported.pronunciation;
+        if !seen.insert(pronunciation.as_str()) {
+            report.errors.push(format!("{}: ファイル内で重複しています", pronunciation));
+            continue;
+        }
+        if !known_pronunciations.contains(pronunciation) {
+            report.errors.push(format!("{}: この読みのカードがありません", pronunciation));
+            continue;
+        }
+        if let Some(message) = invalid_contents(imported) {
+            report.errors.push(format!("{}: {}", pronunciation, message));
+            continue;
+        }
+
+        let imported_state = normalized_state(imported);
+        match existing.get(pronunciation) {
+            None => report.created.push(pronunciation.clone()),
+            Some(current) if OverrideState::from(current) == imported_state => {
+                report.unchanged.push(pronunciation.clone())
+            }
+            Some(current) if current.updated_at > imported.updated_at => {
+                report.conflicts.push(ImportConflict {
+                    pronunciation: pronunciation.clone(),
+                    current: convert_state_to_snapshot(OverrideState::from(current)),
+                    current_updated_at: current.updated_at,
+                    imported: convert_state_to_snapshot(imported_state),
+                    imported_updated_at: imported.updated_at,
+                })
+            }
+            Some(_) => report.updated.push(pronunciation.clone()),
+        }
+    }
+
+    report
+}
+
+/// 取り込めない内容（定義にない列挙子名、置き換えでの削除指定）があれば、その内容
+fn invalid_contents(o: &CardFeatureOverride) -> Option<String> {
+    let feature_override = o.feature_override();
+    let mut messages = Vec::new();
+    let unknown = feature_override.unknown_names();
+    if !unknown.is_empty() {
+        messages.push(format!("未定義のフィーチャーがあります: {}", unknown.join(", ")));
+    }
+    if feature_override.mode == OverrideMode::Replace
+        && !(o.removed_features.is_empty() && o.removed_burst_features.is_empty())
+    {
+        messages.push("削除するフィーチャーは部分修正でのみ指定できます".to_string());
+    }
+    (!messages.is_empty()).then(|| messages.join(", "))
+}
+
+/// 列挙子名を定義順に並べ替え、重複を除いた内容
+fn normalized_state(o: &CardFeatureOverride) -> OverrideState {
+    OverrideState {
+        feature_override: FeatureOverride {
+            mode: o.mode,
+            features: normalized_names(&o.features),
+            burst_features: normalized_burst_names(&o.burst_features),
+            removed_features: normalized_names(&o.removed_features),
+            removed_burst_features: normalized_burst_names(&o.removed_burst_features),
+        },
+        note: o.note.clone(),
+    }
+}
+
+fn normalized_names(names: &[String]) -> Vec<String> {
+    CardFeature::create_vec()
+        .iter()
+        .map(CardFeature::name)
+        .filter(|name| names.iter().any(|n| n == name))
+        .map(str::to_string)
+        .collect()
+}
 
-    Ok(Json(result))
+fn normalized_burst_names(names: &[String]) -> Vec<String> {
+    BurstFeature::create_vec()
+        .iter()
+        .map(BurstFeature::name)
+        .filter(|name| names.iter().any(|n| n == name))
+        .map(str::to_string)
+        .collect()
 }
 
-#[derive(Debug, serde::Serialize)]
-pub struct ImportResult {
-    pub imported: usize,
-    pub errors: Option<Vec<String>>,
-    pub success: bool,
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    fn override_of(pronunciation: &str, feature: &str, updated_at: DateTime<Utc>) -> CardFeatureOverride {
+        CardFeatureOverride {
+            pronunciation: pronunciation.to_string(),
+            mode: OverrideMode::Replace,
+            features: vec![feature.to_string()],
+            burst_features: Vec::new(),
+            removed_features: Vec::new(),
+            removed_burst_features: Vec::new(),
+            created_at: updated_at,
+            updated_at,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_classifies_rows() {
+        let now = Utc::now();
+        let earlier = now - Duration::hours(1);
+        let draw = "Draw";
+        let charge = "Charge";
+
+        let existing: HashMap<String, CardFeatureOverride> = [
+            override_of("こうしん", draw, earlier),
+            override_of("おなじ", draw, earlier),
+            override_of("きょうごう", draw, now),
+        ]
+        .into_iter()
+        .map(|o| (o.pronunciation.clone(), o))
+        .collect();
+        let known: HashSet<String> = ["しんき", "こうしん", "おなじ", "きょうごう", "ふめい"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let imported = [
+            override_of("しんき", draw, now),
+            override_of("こうしん", charge, now),
+            override_of("おなじ", draw, now),
+            override_of("きょうごう", charge, earlier),
+            override_of("かーどなし", draw, now),
+            override_of("ふめい", "Unknown", now),
+            override_of("しんき", draw, now),
+        ];
+
+        let report = plan_import(&imported, &existing, &known, "1.0".to_string(), true);
+        assert_eq!(report.created, ["しんき"]);
+        assert_eq!(report.updated, ["こうしん"]);
+        assert_eq!(report.unchanged, ["おなじ"]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].pronunciation, "きょうごう");
+        assert_eq!(report.errors.len(), 3, "{:?}", report.errors);
+        assert!(!report.applied);
+    }
+
+    #[test]
+    fn test_migrate_rejects_unknown_versions() {
+        let current = json!({ "overrides": [], "exported_at": Utc::now(), "version": EXPORT_VERSION });
+        assert!(migrate(current).is_ok());
+
+        let future = json!({ "overrides": [], "exported_at": Utc::now(), "version": "99.0" });
+        assert!(migrate(future).unwrap_err().contains("未対応のバージョン"));
+
+        let missing = json!({ "overrides": [], "exported_at": Utc::now() });
+        assert!(migrate(missing).is_err());
+    }
+
+    /// 1.0のビット値は列挙子名に変換される
+    #[test]
+    fn test_migrate_bits_to_feature_names() {
+        let (shift1, _) = CardFeature::Draw.to_bit_shifts();
+        let exported = json!({
+            "overrides": [{
+                "pronunciation": "てすと",
+                "fixed_bits1": 1_i64 << shift1,
+                "fixed_bits2": 1,
+                "fixed_burst_bits": 1_i64 << BurstFeature::Search.to_bit_shift(),
+                "created_at": Utc::now(),
+                "updated_at": Utc::now(),
+                "note": null
+            }],
+            "exported_at": Utc::now(),
+            "version": "1.0"
+        });
+
+        let data = migrate(exported).unwrap();
+        assert_eq!(data.version, EXPORT_VERSION);
+        assert_eq!(data.overrides[0].mode, OverrideMode::Replace);
+        assert_eq!(data.overrides[0].features, ["Draw"]);
+        assert_eq!(data.overrides[0].burst_features, ["Search"]);
+        assert!(data.overrides[0].removed_features.is_empty());
+    }
+
+    /// 現在の定義にないビットは落とさずに変換を失敗させる
+    #[test]
+    fn test_migrate_rejects_unknown_bits() {
+        let (shift1, _) = CardFeature::Draw.to_bit_shifts();
+        let exported = json!({
+            "overrides": [{
+                "pronunciation": "てすと",
+                "fixed_bits1": 1_i64 << shift1 | 1_i64 << 62,
+                "fixed_bits2": 1,
+                "fixed_burst_bits": 0,
+                "created_at": Utc::now(),
+                "updated_at": Utc::now(),
+                "note": null
+            }],
+            "exported_at": Utc::now(),
+            "version": "1.0"
+        });
+
+        let message = migrate(exported).unwrap_err();
+        assert!(message.contains("てすと（fixed_bits1=0x4000000000000000）"), "{}", message);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod overrides;
 pub mod analyze;
-pub mod import_export;
\ No newline at end of file
+pub mod import_export;
+pub mod review;
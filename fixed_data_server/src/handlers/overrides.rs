@@ -1,22 +1,42 @@
 use axum::{
-    extract::{Path, State},
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        Path, Query, State,
+    },
     http::StatusCode,
-    Json,
+    Extension, Json,
+};
+use webapp::auth::Caller;
+use webapp::error::{ApiError, ApiErrorBody};
+use serde::Deserialize;
+use sqlx::{FromRow, PgPool, Row};
+use utoipa::IntoParams;
+use webapp::repositories::{FeatureOverride, OverrideMode};
+use crate::db::history;
+use crate::models::{
+    CardFeatureOverride, CardFeatureOverrideHistory, CreateOverrideRequest, OverrideResponse,
+    ConsistencyCheckResult, HistoryEntryResponse, OverrideSnapshot, OverrideState,
 };
-use sqlx::{PgPool, Row};
-use crate::models::{CardFeatureOverride, CreateOverrideRequest, OverrideResponse, ConsistencyCheckResult};
 use feature::feature::{CardFeature, BurstFeature};
 use std::collections::HashSet;
 
+#[utoipa::path(
+    get,
+    path = "/api/overrides",
+    tag = "overrides",
+    responses(
+        (status = 200, description = "全オーバーライド", body = Vec<OverrideResponse>),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn list_overrides(
     State(pool): State<PgPool>,
-) -> Result<Json<Vec<OverrideResponse>>, StatusCode> {
+) -> Result<Json<Vec<OverrideResponse>>, ApiError> {
     let overrides = sqlx::query_as::<_, CardFeatureOverride>(
         "SELECT * FROM wix_card_feature_override ORDER BY pronunciation"
     )
     .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await?;
 
     let responses: Vec<OverrideResponse> = overrides
         .into_iter()
@@ -26,213 +46,403 @@ pub async fn list_overrides(
     Ok(Json(responses))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/overrides/{pronunciation}",
+    tag = "overrides",
+    params(("pronunciation" = String, Path, description = "カードの読み")),
+    responses(
+        (status = 200, description = "オーバーライド", body = OverrideResponse),
+        (status = 404, description = "オーバーライドがない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn get_override(
     State(pool): State<PgPool>,
-    Path(pronunciation): Path<String>,
-) -> Result<Json<OverrideResponse>, StatusCode> {
+    path: Result<Path<String>, PathRejection>,
+) -> Result<Json<OverrideResponse>, ApiError> {
+    let Path(pronunciation) = path?;
     let override_data = sqlx::query_as::<_, CardFeatureOverride>(
         "SELECT * FROM wix_card_feature_override WHERE pronunciation = $1"
     )
     .bind(&pronunciation)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| StatusCode::NOT_FOUND)?;
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| ApiError::not_found(format!("オーバーライドがありません: {}", pronunciation)))?;
 
     Ok(Json(convert_to_response(override_data)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/overrides",
+    tag = "overrides",
+    request_body = CreateOverrideRequest,
+    responses(
+        (status = 200, description = "作成・更新したオーバーライド", body = OverrideResponse),
+        (status = 400, description = "リクエスト本文が不正、または定義にないフィーチャーがある", body = ApiErrorBody),
+        (status = 404, description = "この読みのカードがない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn create_or_update_override(
     State(pool): State<PgPool>,
-    Json(request): Json<CreateOverrideRequest>,
-) -> Result<Json<OverrideResponse>, StatusCode> {
-    // Convert feature names to bits
-    let (bits1, bits2) = convert_features_to_bits(&request.features);
-    let burst_bits = convert_burst_features_to_bits(&request.burst_features);
+    Extension(caller): Extension<Caller>,
+    request: Result<Json<CreateOverrideRequest>, JsonRejection>,
+) -> Result<Json<OverrideResponse>, ApiError> {
+    let Json(request) = request?;
+    // 表示名で受け取ったフィーチャーを列挙子名で保存する
+    let mut unknown = Vec::new();
+    let feature_override = FeatureOverride {
+        mode: request.mode,
+        features: convert_labels_to_names(&request.features, &mut unknown),
+        burst_features: convert_burst_labels_to_names(&request.burst_features, &mut unknown),
+        removed_features: convert_labels_to_names(&request.removed_features, &mut unknown),
+        removed_burst_features: convert_burst_labels_to_names(
+            &request.removed_burst_features,
+            &mut unknown,
+        ),
+    };
+    if !unknown.is_empty() {
+        return Err(ApiError::validation(format!(
+            "定義にないフィーチャーです: {}",
+            unknown.join(", ")
+        )));
+    }
+    if feature_override.mode == OverrideMode::Replace
+        && !(feature_override.removed_features.is_empty()
+            && feature_override.removed_burst_features.is_empty())
+    {
+        return Err(ApiError::validation(
+            "削除するフィーチャーは部分修正（mode: partial）でのみ指定できます",
+        ));
+    }
+    let state = OverrideState {
+        feature_override,
+        note: request.note,
+    };
 
-    let override_data = sqlx::query_as::<_, CardFeatureOverride>(
-        r#"
-        INSERT INTO wix_card_feature_override 
-        (pronunciation, fixed_bits1, fixed_bits2, fixed_burst_bits, note, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
-        ON CONFLICT (pronunciation) DO UPDATE SET
-            fixed_bits1 = EXCLUDED.fixed_bits1,
-            fixed_bits2 = EXCLUDED.fixed_bits2,
-            fixed_burst_bits = EXCLUDED.fixed_burst_bits,
-            note = EXCLUDED.note,
-            updated_at = CURRENT_TIMESTAMP
-        RETURNING *
-        "#
-    )
-    .bind(&request.pronunciation)
-    .bind(bits1 as i64)
-    .bind(bits2 as i64)
-    .bind(burst_bits as i64)
-    .bind(&request.note)
-    .fetch_one(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut tx = pool.begin().await?;
+    let card_exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM wix_card WHERE pronunciation = $1)")
+            .bind(&request.pronunciation)
+            .fetch_one(&mut *tx)
+            .await?;
+    if !card_exists {
+        return Err(ApiError::not_found(format!(
+            "この読みのカードがありません: {}",
+            request.pronunciation
+        )));
+    }
+    let override_data =
+        history::save(&mut tx, &caller.name, &request.pronunciation, &state, None).await?;
+    tx.commit().await?;
 
     Ok(Json(convert_to_response(override_data)))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/overrides/{pronunciation}",
+    tag = "overrides",
+    params(("pronunciation" = String, Path, description = "カードの読み")),
+    responses(
+        (status = 204, description = "削除した"),
+        (status = 404, description = "オーバーライドがない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn delete_override(
     State(pool): State<PgPool>,
-    Path(pronunciation): Path<String>,
-) -> Result<StatusCode, StatusCode> {
-    let result = sqlx::query(
-        "DELETE FROM wix_card_feature_override WHERE pronunciation = $1"
-    )
-    .bind(&pronunciation)
-    .execute(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Extension(caller): Extension<Caller>,
+    path: Result<Path<String>, PathRejection>,
+) -> Result<StatusCode, ApiError> {
+    let Path(pronunciation) = path?;
+    let mut tx = pool.begin().await?;
+    let deleted = history::remove(&mut tx, &caller.name, &pronunciation).await?;
+    tx.commit().await?;
 
-    if result.rows_affected() > 0 {
+    if deleted.is_some() {
         Ok(StatusCode::NO_CONTENT)
     } else {
-        Err(StatusCode::NOT_FOUND)
+        Err(ApiError::not_found(format!("オーバーライドがありません: {}", pronunciation)))
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/overrides/{pronunciation}/history",
+    tag = "overrides",
+    params(("pronunciation" = String, Path, description = "カードの読み")),
+    responses(
+        (status = 200, description = "変更履歴（新しい順）。削除済みのオーバーライドの履歴も返す", body = Vec<HistoryEntryResponse>),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
+pub async fn list_history(
+    State(pool): State<PgPool>,
+    path: Result<Path<String>, PathRejection>,
+) -> Result<Json<Vec<HistoryEntryResponse>>, ApiError> {
+    let Path(pronunciation) = path?;
+    let entries = history::list(&pool, &pronunciation).await?;
+    Ok(Json(entries.into_iter().map(convert_history_to_response).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/overrides/{pronunciation}/history/{id}/revert",
+    tag = "overrides",
+    params(
+        ("pronunciation" = String, Path, description = "カードの読み"),
+        ("id" = i64, Path, description = "差し戻す履歴のID")
+    ),
+    responses(
+        (status = 200, description = "履歴の変更前の状態に戻した。差し戻しの履歴を返す", body = HistoryEntryResponse),
+        (status = 400, description = "IDが数値でない", body = ApiErrorBody),
+        (status = 404, description = "この読みの履歴にない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
+pub async fn revert_override(
+    State(pool): State<PgPool>,
+    Extension(caller): Extension<Caller>,
+    path: Result<Path<(String, i64)>, PathRejection>,
+) -> Result<Json<HistoryEntryResponse>, ApiError> {
+    let Path((pronunciation, id)) = path?;
+    let mut tx = pool.begin().await?;
+    let entry = history::find(&mut tx, id)
+        .await?
+        .filter(|entry| entry.pronunciation == pronunciation)
+        .ok_or_else(|| ApiError::not_found(format!("{} の履歴がありません: {}", pronunciation, id)))?;
+    let reverted = history::revert(&mut tx, &caller.name, &entry).await?;
+    tx.commit().await?;
+
+    Ok(Json(convert_history_to_response(reverted)))
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConsistencyQuery {
+    /// 不要になった修正、または不要な追加・削除を含む部分修正だけを返す
+    #[serde(default)]
+    pub redundant_only: bool,
+}
+
+/// 修正とルールベースの解析結果を比べ、ルールが追いついて不要になった修正を見つける
+#[utoipa::path(
+    get,
+    path = "/api/check-consistency",
+    tag = "overrides",
+    params(ConsistencyQuery),
+    responses(
+        (status = 200, description = "オーバーライドとルール解析結果の比較", body = Vec<ConsistencyCheckResult>),
+        (status = 400, description = "クエリパラメータが不正", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
 pub async fn check_consistency(
     State(pool): State<PgPool>,
-) -> Result<Json<Vec<ConsistencyCheckResult>>, StatusCode> {
-    // Get all overrides with corresponding card data
-    let results = sqlx::query(
+    query: Result<Query<ConsistencyQuery>, QueryRejection>,
+) -> Result<Json<Vec<ConsistencyCheckResult>>, ApiError> {
+    let Query(query) = query?;
+    // 再録でルールベースの結果が異なることがあるため、結果ごとにカード番号をまとめて取る
+    // （読みごとに再録の多い結果から並べる）
+    let rows = sqlx::query(
         r#"
-        SELECT DISTINCT
-            o.pronunciation,
-            o.fixed_bits1,
-            o.fixed_bits2,
-            o.fixed_burst_bits,
+        SELECT
+            o.*,
             c.feature_bits1,
             c.feature_bits2,
-            c.burst_bits
+            c.burst_bits,
+            array_agg(c.code ORDER BY c.code) AS card_codes
         FROM wix_card_feature_override o
         INNER JOIN wix_card c ON c.pronunciation = o.pronunciation
-        ORDER BY o.pronunciation
+        GROUP BY o.id, c.feature_bits1, c.feature_bits2, c.burst_bits
+        ORDER BY o.pronunciation, COUNT(*) DESC, MIN(c.code)
         "#
     )
     .fetch_all(&pool)
-    .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await?;
 
-    let consistency_results: Vec<ConsistencyCheckResult> = results
-        .into_iter()
-        .map(|r| {
-            let pronunciation: String = r.get("pronunciation");
-            let fixed_bits1: i64 = r.get("fixed_bits1");
-            let fixed_bits2: i64 = r.get("fixed_bits2");
-            let fixed_burst_bits: i64 = r.get("fixed_burst_bits");
-            let feature_bits1: i64 = r.get("feature_bits1");
-            let feature_bits2: i64 = r.get("feature_bits2");
-            let burst_bits: i64 = r.get("burst_bits");
-
-            let is_consistent = 
-                fixed_bits1 == feature_bits1 &&
-                fixed_bits2 == feature_bits2 &&
-                fixed_burst_bits == burst_bits;
-
-            ConsistencyCheckResult {
-                pronunciation,
-                is_consistent,
-                rule_based_features: convert_bits_to_features(feature_bits1 as u64, feature_bits2 as u64),
-                override_features: convert_bits_to_features(fixed_bits1 as u64, fixed_bits2 as u64),
-                rule_based_burst_features: convert_burst_bits_to_features(burst_bits as u64),
-                override_burst_features: convert_burst_bits_to_features(fixed_burst_bits as u64),
-            }
-        })
-        .collect();
-
-    Ok(Json(consistency_results))
-}
-
-// Helper functions
-fn convert_features_to_bits(features: &[String]) -> (u64, u64) {
-    let mut bits1 = 0i64;
-    let mut bits2 = 0i64;
-
-    // Create all features and find matches by display name
-    let all_features = CardFeature::create_vec();
-    
-    for feature_name in features {
-        for feature in &all_features {
-            if feature.to_string() == *feature_name {
-                let (shift1, shift2) = feature.to_bit_shifts();
-                bits1 |= 1_i64 << shift1;
-                bits2 |= 1_i64 << shift2;
-                break;
+    let mut grouped: Vec<(CardFeatureOverride, Vec<RuleBasedResult>)> = Vec::new();
+    for r in rows {
+        let override_data = CardFeatureOverride::from_row(&r)?;
+        let result = RuleBasedResult {
+            feature_bits1: r.get("feature_bits1"),
+            feature_bits2: r.get("feature_bits2"),
+            burst_bits: r.get("burst_bits"),
+            card_codes: r.get("card_codes"),
+        };
+        match grouped.last_mut() {
+            Some((last, results)) if last.pronunciation == override_data.pronunciation => {
+                results.push(result)
             }
+            _ => grouped.push((override_data, vec![result])),
         }
     }
 
-    (bits1 as u64, bits2 as u64)
-}
-
-fn convert_burst_features_to_bits(features: &[String]) -> u64 {
-    let mut bits = 0i64;
+    let mut consistency_results = Vec::new();
+    for (override_data, results) in grouped {
+        let feature_override = override_data.feature_override();
+        // 不要・効果なしと言えるのは、すべての再録で当てはまる場合だけ
+        let is_consistent = results.iter().all(|r| {
+            feature_override.is_redundant(r.feature_bits1, r.feature_bits2, r.burst_bits)
+        });
+        let primary = &results[0];
+        let (mut redundant_features, mut redundant_burst_features) = feature_override
+            .redundant_deltas(primary.feature_bits1, primary.feature_bits2, primary.burst_bits);
+        for r in &results[1..] {
+            let (features, burst_features) =
+                feature_override.redundant_deltas(r.feature_bits1, r.feature_bits2, r.burst_bits);
+            redundant_features.retain(|f| features.contains(f));
+            redundant_burst_features.retain(|f| burst_features.contains(f));
+        }
 
-    // Create all burst features and find matches by display name
-    let all_features = BurstFeature::create_vec();
-    for feature_name in features {
-        for feature in &all_features {
-            if feature.to_string() == *feature_name {
-                let shift = feature.to_bit_shift();
-                bits |= 1_i64 << shift;
-                break;
-            }
+        if query.redundant_only
+            && !is_consistent
+            && redundant_features.is_empty()
+            && redundant_burst_features.is_empty()
+        {
+            continue;
         }
+
+        let (effective_bits1, effective_bits2, effective_burst_bits) =
+            feature_override.apply(primary.feature_bits1, primary.feature_bits2, primary.burst_bits);
+        let mut card_codes: Vec<String> =
+            results.iter().flat_map(|r| r.card_codes.iter().cloned()).collect();
+        card_codes.sort();
+        let differing_card_codes: Vec<String> =
+            results[1..].iter().flat_map(|r| r.card_codes.iter().cloned()).collect();
+
+        consistency_results.push(ConsistencyCheckResult {
+            pronunciation: override_data.pronunciation,
+            mode: override_data.mode,
+            is_consistent,
+            card_codes,
+            differing_card_codes,
+            rule_based_features: convert_bits_to_features(primary.feature_bits1 as u64, primary.feature_bits2 as u64),
+            override_features: convert_names_to_labels(&override_data.features),
+            rule_based_burst_features: convert_burst_bits_to_features(primary.burst_bits as u64),
+            override_burst_features: convert_burst_names_to_labels(&override_data.burst_features),
+            removed_features: convert_names_to_labels(&override_data.removed_features),
+            removed_burst_features: convert_burst_names_to_labels(&override_data.removed_burst_features),
+            effective_features: convert_bits_to_features(effective_bits1 as u64, effective_bits2 as u64),
+            effective_burst_features: convert_burst_bits_to_features(effective_burst_bits as u64),
+            redundant_features: convert_names_to_labels(&redundant_features),
+            redundant_burst_features: convert_burst_names_to_labels(&redundant_burst_features),
+        });
     }
 
-    bits as u64
+    Ok(Json(consistency_results))
 }
 
-fn convert_bits_to_features(bits1: u64, bits2: u64) -> Vec<String> {
-    let mut features = Vec::new();
-    let bits1 = bits1 as i64;
-    let bits2 = bits2 as i64;
+/// 同じ読みで同じルールベースの結果になったカード
+struct RuleBasedResult {
+    feature_bits1: i64,
+    feature_bits2: i64,
+    burst_bits: i64,
+    card_codes: Vec<String>,
+}
 
-    // Check each feature against the bits
-    let all_features = CardFeature::create_vec();
-    for feature in all_features {
-        let (shift1, shift2) = feature.to_bit_shifts();
-        let has_feature = if shift2 == 0 {
-            (bits1 & (1_i64 << shift1)) != 0
-        } else {
-            (bits2 & (1_i64 << shift2)) != 0
-        };
-        
-        if has_feature {
-            features.push(feature.to_string());
-        }
-    }
+// Helper functions
+
+/// 表示名のリストを列挙子名のリストにする（定義順）。未定義の表示名は `unknown` に積む
+fn convert_labels_to_names(labels: &[String], unknown: &mut Vec<String>) -> Vec<String> {
+    let features = CardFeature::create_vec();
+    unknown.extend(
+        labels
+            .iter()
+            .filter(|label| !features.iter().any(|feature| feature.to_string() == **label))
+            .cloned(),
+    );
+    features
+        .into_iter()
+        .filter(|feature| labels.contains(&feature.to_string()))
+        .map(|feature| feature.name().to_string())
+        .collect()
+}
 
+fn convert_burst_labels_to_names(labels: &[String], unknown: &mut Vec<String>) -> Vec<String> {
+    let features = BurstFeature::create_vec();
+    unknown.extend(
+        labels
+            .iter()
+            .filter(|label| !features.iter().any(|feature| feature.to_string() == **label))
+            .cloned(),
+    );
     features
+        .into_iter()
+        .filter(|feature| labels.contains(&feature.to_string()))
+        .map(|feature| feature.name().to_string())
+        .collect()
 }
 
-fn convert_burst_bits_to_features(bits: u64) -> Vec<String> {
-    let mut features = Vec::new();
-    let bits = bits as i64;
+/// 列挙子名のリストを表示名のリストにする（定義順。定義から削除された名前は無視する）
+pub(crate) fn convert_names_to_labels(names: &[String]) -> Vec<String> {
+    CardFeature::create_vec()
+        .into_iter()
+        .filter(|feature| names.iter().any(|name| name == feature.name()))
+        .map(|feature| feature.to_string())
+        .collect()
+}
 
-    // Check each burst feature against the bits
-    let all_features = BurstFeature::create_vec();
-    for feature in all_features {
-        let shift = feature.to_bit_shift();
-        if (bits & (1_i64 << shift)) != 0 {
-            features.push(feature.to_string());
-        }
-    }
+pub(crate) fn convert_burst_names_to_labels(names: &[String]) -> Vec<String> {
+    BurstFeature::create_vec()
+        .into_iter()
+        .filter(|feature| names.iter().any(|name| name == feature.name()))
+        .map(|feature| feature.to_string())
+        .collect()
+}
 
-    features
+pub(crate) fn convert_bits_to_features(bits1: u64, bits2: u64) -> Vec<String> {
+    CardFeature::from_bits(bits1 as i64, bits2 as i64)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+pub(crate) fn convert_burst_bits_to_features(bits: u64) -> Vec<String> {
+    BurstFeature::from_bits(bits as i64)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
 }
 
 fn convert_to_response(override_data: CardFeatureOverride) -> OverrideResponse {
     OverrideResponse {
         pronunciation: override_data.pronunciation,
-        features: convert_bits_to_features(override_data.fixed_bits1 as u64, override_data.fixed_bits2 as u64),
-        burst_features: convert_burst_bits_to_features(override_data.fixed_burst_bits as u64),
+        mode: override_data.mode,
+        features: convert_names_to_labels(&override_data.features),
+        burst_features: convert_burst_names_to_labels(&override_data.burst_features),
+        removed_features: convert_names_to_labels(&override_data.removed_features),
+        removed_burst_features: convert_burst_names_to_labels(&override_data.removed_burst_features),
         created_at: override_data.created_at,
         updated_at: override_data.updated_at,
         note: override_data.note,
     }
-}
\ No newline at end of file
+}
+
+pub(crate) fn convert_state_to_snapshot(state: OverrideState) -> OverrideSnapshot {
+    let feature_override = state.feature_override;
+    OverrideSnapshot {
+        mode: feature_override.mode,
+        features: convert_names_to_labels(&feature_override.features),
+        burst_features: convert_burst_names_to_labels(&feature_override.burst_features),
+        removed_features: convert_names_to_labels(&feature_override.removed_features),
+        removed_burst_features: convert_burst_names_to_labels(&feature_override.removed_burst_features),
+        note: state.note,
+    }
+}
+
+fn convert_history_to_response(entry: CardFeatureOverrideHistory) -> HistoryEntryResponse {
+    HistoryEntryResponse {
+        old: entry.old_state().map(convert_state_to_snapshot),
+        new: entry.new_state().map(convert_state_to_snapshot),
+        id: entry.id,
+        pronunciation: entry.pronunciation,
+        action: entry.action,
+        changed_by: entry.changed_by,
+        changed_at: entry.changed_at,
+        reverted_from: entry.reverted_from_id,
+    }
+}
@@ -0,0 +1,299 @@
+use analyzer::coverage::PatternCoverage;
+use axum::{
+    extract::{
+        rejection::{PathRejection, QueryRejection},
+        Path, Query, State,
+    },
+    Extension, Json,
+};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use webapp::auth::Caller;
+use webapp::error::{ApiError, ApiErrorBody};
+
+use crate::db::review;
+use crate::models::{
+    CardFeatureOverride, CardReviewItem, RefreshReviewResponse, ResolveReviewRequest, ReviewAction,
+    ReviewCandidate, ReviewQuery, ReviewReason, ReviewTarget,
+};
+
+use super::analyze::CardDiffResponse;
+
+#[utoipa::path(
+    get,
+    path = "/api/review",
+    tag = "review",
+    params(ReviewQuery),
+    responses(
+        (status = 200, description = "確認キュー（優先度の高い順）", body = Vec<CardReviewItem>),
+        (status = 400, description = "クエリパラメータが不正", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
+pub async fn list_review_items(
+    State(pool): State<PgPool>,
+    query: Result<Query<ReviewQuery>, QueryRejection>,
+) -> Result<Json<Vec<CardReviewItem>>, ApiError> {
+    let Query(query) = query?;
+    Ok(Json(review::list(&pool, &query).await?))
+}
+
+/// 一括更新で調べる1読み分の行（複数の収録があれば番号の若いもの）
+#[derive(Debug, FromRow)]
+struct ReviewCardRow {
+    pronunciation: String,
+    code: String,
+    card_type: i32,
+    has_burst: i32,
+    skill_text: String,
+    feature_bits1: i64,
+    feature_bits2: i64,
+    burst_bits: i64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/review/refresh",
+    tag = "review",
+    responses(
+        (status = 200, description = "フィーチャーなし・カード種類との矛盾・カバレッジ不足を検出し直した", body = RefreshReviewResponse),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
+pub async fn refresh_review_items(
+    State(pool): State<PgPool>,
+) -> Result<Json<RefreshReviewResponse>, ApiError> {
+    // 生成コードと同じくDiva(1)のカードのみ。カバレッジは解析と同じ生データのテキストで測る
+    let cards: Vec<ReviewCardRow> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT ON (c.pronunciation)
+            c.pronunciation, c.code, c.card_type, c.has_burst,
+            COALESCE(r.skill_text, c.skill_text, '') AS skill_text,
+            c.feature_bits1, c.feature_bits2, c.burst_bits
+        FROM wix_card c
+        LEFT JOIN wix_rawcard r ON r.card_number = c.code
+        WHERE c.format = 1
+        ORDER BY c.pronunciation, c.code
+        "#,
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let overrides: HashMap<String, CardFeatureOverride> =
+        sqlx::query_as::<_, CardFeatureOverride>("SELECT * FROM wix_card_feature_override")
+            .fetch_all(&pool)
+            .await?
+            .into_iter()
+            .map(|o| (o.pronunciation.clone(), o))
+            .collect();
+
+    let checked = cards.len();
+    // パターンのコンパイルと全カードの計測は重いのでブロッキングスレッドで行う
+    let candidates = tokio::task::spawn_blocking(move || detect_candidates(cards, &overrides))
+        .await
+        .map_err(|e| ApiError::Internal(format!("確認キューの検出に失敗しました: {}", e)))?;
+
+    let mut tx = pool.begin().await?;
+    for candidate in &candidates {
+        review::enqueue(&mut tx, candidate).await?;
+    }
+    let removed = review::remove_stale(&mut tx, &ReviewReason::REFRESHED, &candidates).await?;
+    tx.commit().await?;
+
+    Ok(Json(RefreshReviewResponse {
+        checked,
+        flagged: candidates.len(),
+        removed,
+    }))
+}
+
+/// オーバーライドを適用したフィーチャーで各カードを調べる
+fn detect_candidates(
+    cards: Vec<ReviewCardRow>,
+    overrides: &HashMap<String, CardFeatureOverride>,
+) -> Vec<ReviewCandidate> {
+    let coverage = PatternCoverage::new();
+    cards
+        .into_iter()
+        .flat_map(|card| {
+            let (feature_bits1, feature_bits2, burst_bits) =
+                match overrides.get(&card.pronunciation) {
+                    Some(o) => o.feature_override().apply(
+                        card.feature_bits1,
+                        card.feature_bits2,
+                        card.burst_bits,
+                    ),
+                    None => (card.feature_bits1, card.feature_bits2, card.burst_bits),
+                };
+            let ratio = coverage.measure(&card.code, &card.skill_text).ratio();
+            ReviewTarget {
+                pronunciation: card.pronunciation,
+                card_type: card.card_type,
+                has_burst: card.has_burst,
+                skill_text: card.skill_text,
+                feature_bits1,
+                feature_bits2,
+                burst_bits,
+            }
+            .candidates(Some(ratio))
+        })
+        .collect()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/review/{id}/claim",
+    tag = "review",
+    params(("id" = i64, Path, description = "確認キューの項目ID")),
+    responses(
+        (status = 200, description = "担当にした", body = CardReviewItem),
+        (status = 400, description = "他の人が担当中か対応済み、またはIDが数値でない", body = ApiErrorBody),
+        (status = 404, description = "項目がない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
+pub async fn claim_review_item(
+    State(pool): State<PgPool>,
+    Extension(caller): Extension<Caller>,
+    path: Result<Path<i64>, PathRejection>,
+) -> Result<Json<CardReviewItem>, ApiError> {
+    let Path(id) = path?;
+    transition(&pool, &caller, id, ReviewAction::Claim, None).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/review/{id}/approve",
+    tag = "review",
+    params(("id" = i64, Path, description = "確認キューの項目ID")),
+    request_body = ResolveReviewRequest,
+    responses(
+        (status = 200, description = "フィーチャーが正しいことを確認した", body = CardReviewItem),
+        (status = 400, description = "他の人が担当中か対応済み、またはIDが数値でない", body = ApiErrorBody),
+        (status = 404, description = "項目がない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
+pub async fn approve_review_item(
+    State(pool): State<PgPool>,
+    Extension(caller): Extension<Caller>,
+    path: Result<Path<i64>, PathRejection>,
+    request: Option<Json<ResolveReviewRequest>>,
+) -> Result<Json<CardReviewItem>, ApiError> {
+    let Path(id) = path?;
+    let note = request.and_then(|Json(request)| request.note);
+    transition(&pool, &caller, id, ReviewAction::Approve, note).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/review/{id}/reject",
+    tag = "review",
+    params(("id" = i64, Path, description = "確認キューの項目ID")),
+    request_body = ResolveReviewRequest,
+    responses(
+        (status = 200, description = "フィーチャーの誤りとして差し戻した（修正はオーバーライドで行う）", body = CardReviewItem),
+        (status = 400, description = "他の人が担当中か対応済み、またはIDが数値でない", body = ApiErrorBody),
+        (status = 404, description = "項目がない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody)
+    )
+)]
+pub async fn reject_review_item(
+    State(pool): State<PgPool>,
+    Extension(caller): Extension<Caller>,
+    path: Result<Path<i64>, PathRejection>,
+    request: Option<Json<ResolveReviewRequest>>,
+) -> Result<Json<CardReviewItem>, ApiError> {
+    let Path(id) = path?;
+    let note = request.and_then(|Json(request)| request.note);
+    transition(&pool, &caller, id, ReviewAction::Reject, note).await
+}
+
+async fn transition(
+    pool: &PgPool,
+    caller: &Caller,
+    id: i64,
+    action: ReviewAction,
+    note: Option<String>,
+) -> Result<Json<CardReviewItem>, ApiError> {
+    let mut tx = pool.begin().await?;
+    let item = review::lock(&mut tx, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("確認キューにありません: {}", id)))?;
+    let status = item
+        .next_status(action, &caller.name)
+        .map_err(ApiError::validation)?;
+    let updated = review::update_status(&mut tx, id, status, &caller.name, note.as_deref()).await?;
+    tx.commit().await?;
+
+    Ok(Json(updated))
+}
+
+/// 再解析でフィーチャーが変わった収録をまとめ、読みごとに1件の候補にする。変化がなければ `None`
+///
+/// wix_cardに初めて登録された収録は比較対象がないので含めない
+pub(crate) fn detection_changed_candidate(
+    pronunciation: &str,
+    diffs: &[(String, CardDiffResponse)],
+) -> Option<ReviewCandidate> {
+    let lines: Vec<String> = diffs
+        .iter()
+        .filter(|(_, diff)| !diff.is_new)
+        .filter_map(|(code, diff)| {
+            let mut parts = Vec::new();
+            let sets = [
+                ("追加", &diff.features_added),
+                ("削除", &diff.features_removed),
+                ("バースト追加", &diff.burst_features_added),
+                ("バースト削除", &diff.burst_features_removed),
+            ];
+            for (label, names) in sets {
+                if !names.is_empty() {
+                    parts.push(format!("{}: {}", label, names.join(", ")));
+                }
+            }
+            (!parts.is_empty()).then(|| format!("{} {}", code, parts.join(" / ")))
+        })
+        .collect();
+
+    (!lines.is_empty()).then(|| ReviewCandidate {
+        pronunciation: pronunciation.to_string(),
+        reason: ReviewReason::DetectionChanged,
+        detail: lines.join("\n"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(is_new: bool, added: &[&str], removed: &[&str]) -> CardDiffResponse {
+        CardDiffResponse {
+            is_new,
+            changes: Vec::new(),
+            features_added: added.iter().map(|s| s.to_string()).collect(),
+            features_removed: removed.iter().map(|s| s.to_string()).collect(),
+            burst_features_added: Vec::new(),
+            burst_features_removed: Vec::new(),
+            klasses_added: Vec::new(),
+            klasses_removed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_detection_changed_candidate() {
+        let diffs = vec![
+            (
+                "WX24-001".to_string(),
+                diff(false, &["ドロー"], &["バニッシュ"]),
+            ),
+            ("WX24-002".to_string(), diff(false, &[], &[])),
+            ("WX24-003".to_string(), diff(true, &["ドロー"], &[])),
+        ];
+        let candidate = detection_changed_candidate("てすと", &diffs).unwrap();
+        assert_eq!(candidate.reason, ReviewReason::DetectionChanged);
+        assert_eq!(candidate.detail, "WX24-001 追加: ドロー / 削除: バニッシュ");
+
+        assert_eq!(detection_changed_candidate("てすと", &diffs[1..]), None);
+    }
+}
@@ -1,105 +1,327 @@
 use anyhow::Result;
-use axum::{routing::{get, post, delete}, Router};
+use api_routes::ApiRoute;
+use axum::{
+    extract::{FromRef, State},
+    middleware::{from_fn, from_fn_with_state},
+    response::Response,
+    Json, Router,
+};
 use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
 use std::net::SocketAddr;
-use std::{env, fs};
+use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa::OpenApi;
+use config::Config;
+use webapp::auth::{require_api_key, ApiKeyAuth};
 
 mod handlers;
 mod models;
 mod db;
 
-use handlers::{overrides, analyze, import_export};
+use handlers::{overrides, analyze, import_export, review};
+use handlers::analyze::AnalysisJobs;
+
+/// ハンドラ共通の状態（各ハンドラは必要な部分だけを `State` で受け取る）
+#[derive(Clone)]
+struct AppState {
+    pool: PgPool,
+    analysis_jobs: AnalysisJobs,
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for AnalysisJobs {
+    fn from_ref(state: &AppState) -> Self {
+        state.analysis_jobs.clone()
+    }
+}
+
+/// API定義（`/api/openapi.json` で配信）
+#[derive(OpenApi)]
+#[openapi(paths(
+    overrides::list_overrides,
+    overrides::create_or_update_override,
+    overrides::get_override,
+    overrides::delete_override,
+    overrides::list_history,
+    overrides::revert_override,
+    analyze::analyze_card,
+    analyze::get_analysis_job,
+    import_export::export_all,
+    import_export::import_data,
+    overrides::check_consistency,
+    review::list_review_items,
+    review::refresh_review_items,
+    review::claim_review_item,
+    review::approve_review_item,
+    review::reject_review_item,
+    health,
+    metrics,
+))]
+struct ApiDoc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Load environment variables
-    let workspace_env = format!(
-        "{}/.env",
-        env::var("CARGO_WORKSPACE_DIR").unwrap_or_default()
-    );
-    let env_paths = [
-        ".env",                 // カレントディレクトリ
-        "../.env",              // 一つ上のディレクトリ
-        "../../.env",           // 二つ上のディレクトリ
-        workspace_env.as_str(), // CARGO_WORKSPACE_DIRが設定されている場合
-    ];
-
-    for path in &env_paths {
-        if std::path::Path::new(path).exists() {
-            dotenvy::from_filename(path).ok();
-            break;
-        }
-    }
+    let config = Config::load_or_exit();
 
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "fixed_data_server=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    // Database connection
-    let database_url = std::env::var("DATABASE_URL")
-        .or_else(|_| -> Result<String, std::env::VarError> {
-            // Fallback: construct from individual components
-            let host = env::var("DB_HOST").unwrap_or_else(|_| "localhost".to_string());
-            let port = env::var("DB_PORT").unwrap_or_else(|_| "5432".to_string());
-            let user = env::var("DB_USER").unwrap_or_else(|_| "postgres".to_string());
-            let password = env::var("DB_PASSWORD").unwrap_or_else(|_| "".to_string());
-            let db_name = env::var("DB_NAME").unwrap_or_else(|_| "postgres".to_string());
-            Ok(format!(
-                "postgres://{}:{}@{}:{}/{}",
-                user, password, host, port, db_name
-            ))
-        })
-        .expect("DATABASE_URL or DB_* components must be set");
-    
+    webapp::logging::init_tracing(
+        "fixed_data_server=debug,tower_http=debug",
+        config.log.format,
+    );
+    webapp::metrics::prometheus_handle();
+
     let pool = PgPoolOptions::new()
         .max_connections(5)
-        .connect(&database_url)
+        .connect(&config.database.url())
         .await?;
 
-    // CORS configuration
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let auth = ApiKeyAuth::from_config(Arc::new(pool.clone()), &config.admin);
+    let app = app(pool, auth);
 
-    // Build application
-    let app = Router::new()
+    // Start server
+    let addr = SocketAddr::new(config.server.fixed_data_host, config.server.fixed_data_port);
+    info!("Fixed Data Server listening on {}", addr);
+    
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+/// 閲覧はreader、変更はeditorのAPIキーが必要なルート
+fn protected_routes() -> Vec<ApiRoute<AppState>> {
+    vec![
         // Override CRUD endpoints
-        .route("/api/overrides", get(overrides::list_overrides))
-        .route("/api/overrides", post(overrides::create_or_update_override))
-        .route("/api/overrides/:pronunciation", get(overrides::get_override))
-        .route("/api/overrides/:pronunciation", delete(overrides::delete_override))
-        
+        ApiRoute::get("/api/overrides", overrides::list_overrides),
+        ApiRoute::post("/api/overrides", overrides::create_or_update_override),
+        ApiRoute::get("/api/overrides/{pronunciation}", overrides::get_override),
+        ApiRoute::delete("/api/overrides/{pronunciation}", overrides::delete_override),
+        ApiRoute::get("/api/overrides/{pronunciation}/history", overrides::list_history),
+        ApiRoute::post(
+            "/api/overrides/{pronunciation}/history/{id}/revert",
+            overrides::revert_override,
+        ),
+
         // Analysis endpoints
-        .route("/api/analyze/:pronunciation", post(analyze::analyze_card))
-        
+        ApiRoute::post("/api/analyze/{pronunciation}", analyze::analyze_card),
+        ApiRoute::get("/api/analyze/jobs/{id}", analyze::get_analysis_job),
+
         // Import/Export endpoints
-        .route("/api/export", get(import_export::export_all))
-        .route("/api/import", post(import_export::import_data))
-        
+        ApiRoute::get("/api/export", import_export::export_all),
+        ApiRoute::post("/api/import", import_export::import_data),
+
         // Consistency check
-        .route("/api/check-consistency", get(overrides::check_consistency))
-        
+        ApiRoute::get("/api/check-consistency", overrides::check_consistency),
+
+        // Review queue
+        ApiRoute::get("/api/review", review::list_review_items),
+        ApiRoute::post("/api/review/refresh", review::refresh_review_items),
+        ApiRoute::post("/api/review/{id}/claim", review::claim_review_item),
+        ApiRoute::post("/api/review/{id}/approve", review::approve_review_item),
+        ApiRoute::post("/api/review/{id}/reject", review::reject_review_item),
+    ]
+}
+
+/// APIキーなしで使えるルート
+fn public_routes() -> Vec<ApiRoute<AppState>> {
+    vec![
         // Health check
-        .route("/health", get(|| async { "OK" }))
-        
+        ApiRoute::get("/health", health),
+        ApiRoute::get("/metrics", metrics),
+
+        // API specification
+        ApiRoute::get("/api/openapi.json", openapi_json).undocumented(),
+    ]
+}
+
+fn app(pool: PgPool, auth: ApiKeyAuth) -> Router {
+    // CORS configuration
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    let api = api_routes::router(protected_routes())
+        .route_layer(from_fn_with_state(auth, require_api_key));
+
+    Router::new()
+        .merge(api)
+        .merge(api_routes::router(public_routes()))
+        .layer(from_fn(webapp::metrics::track_http_metrics))
         .layer(cors)
-        .with_state(pool);
+        .with_state(AppState {
+            pool,
+            analysis_jobs: AnalysisJobs::default(),
+        })
+}
 
-    // Start server
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8004));
-    info!("Fixed Data Server listening on {}", addr);
-    
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "稼働中", body = String, content_type = "text/plain"))
+)]
+async fn health() -> &'static str {
+    "OK"
+}
 
-    Ok(())
-}
\ No newline at end of file
+/// Prometheus形式のメトリクス（リクエスト数・所要時間、DBプール、オーバーライド件数）
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "Prometheusのテキスト形式", body = String, content_type = "text/plain"))
+)]
+async fn metrics(State(pool): State<PgPool>) -> Response {
+    webapp::metrics::record_pool(&pool);
+
+    let overrides: Result<i64, sqlx::Error> =
+        sqlx::query_scalar("SELECT COUNT(*) FROM wix_card_feature_override")
+            .fetch_one(&pool)
+            .await;
+    match overrides {
+        Ok(count) => {
+            metrics::gauge!("db_up").set(1.0);
+            metrics::gauge!("wixdb_feature_overrides").set(count as f64);
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "メトリクス用のオーバーライド件数を取得できません");
+            metrics::gauge!("db_up").set(0.0);
+        }
+    }
+
+    webapp::metrics::render()
+}
+
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    /// 仕様を変えたら `UPDATE_OPENAPI=1 cargo test -p fixed_data_server` でスナップショットを更新する
+    #[test]
+    fn test_openapi_snapshot_is_up_to_date() {
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+        if env::var_os("UPDATE_OPENAPI").is_some() {
+            fs::write(SNAPSHOT, &spec).unwrap();
+            return;
+        }
+
+        let committed = fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            committed == spec,
+            "openapi.json がハンドラ定義と一致しません。UPDATE_OPENAPI=1 で再生成してください"
+        );
+    }
+
+    /// 認証を有効にするとAPIキーなしの呼び出しは401になり、ヘルスチェック・仕様・メトリクスは公開のまま
+    #[tokio::test]
+    async fn test_api_requires_key_when_auth_enabled() {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        let auth = ApiKeyAuth::new(Arc::new(pool.clone()), true);
+        let app = app(pool, auth);
+
+        let cases = [
+            (Method::GET, "/api/overrides", StatusCode::UNAUTHORIZED),
+            (Method::DELETE, "/api/overrides/test", StatusCode::UNAUTHORIZED),
+            (Method::GET, "/api/overrides/test/history", StatusCode::UNAUTHORIZED),
+            (Method::POST, "/api/overrides/test/history/1/revert", StatusCode::UNAUTHORIZED),
+            (Method::GET, "/api/review", StatusCode::UNAUTHORIZED),
+            (Method::POST, "/api/review/1/claim", StatusCode::UNAUTHORIZED),
+            (Method::GET, "/health", StatusCode::OK),
+            (Method::GET, "/api/openapi.json", StatusCode::OK),
+            (Method::GET, "/metrics", StatusCode::OK),
+        ];
+        for (method, uri, expected) in cases {
+            let request = Request::builder()
+                .method(method.clone())
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let status = app.clone().oneshot(request).await.unwrap().status();
+            assert_eq!(status, expected, "{} {}", method, uri);
+        }
+    }
+
+    /// 存在しない解析ジョブは404
+    #[tokio::test]
+    async fn test_unknown_analysis_job_is_not_found() {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        let auth = ApiKeyAuth::new(Arc::new(pool.clone()), false);
+        let request = Request::builder()
+            .uri("/api/analyze/jobs/999")
+            .body(Body::empty())
+            .unwrap();
+        let response = app(pool, auth).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// パス・クエリパラメータの誤りは他のエラーと同じJSON形式の400になる
+    #[tokio::test]
+    async fn test_malformed_parameters_are_validation_errors() {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        let auth = ApiKeyAuth::new(Arc::new(pool.clone()), false);
+        let app = app(pool, auth);
+
+        let cases = [
+            (Method::POST, "/api/review/abc/claim"),
+            (Method::GET, "/api/analyze/jobs/-1"),
+            (Method::POST, "/api/overrides/test/history/abc/revert"),
+            (Method::GET, "/api/review?status=unknown"),
+            (Method::GET, "/api/check-consistency?redundant_only=maybe"),
+            (Method::POST, "/api/analyze/test?dry_run=maybe"),
+        ];
+        for (method, uri) in cases {
+            let request = Request::builder()
+                .method(method.clone())
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{} {}", method, uri);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(body["code"], "validation_error", "{} {}", method, uri);
+        }
+    }
+
+    /// ルート表とOpenAPI定義が一致することを確認する（仕様自体の配信は除く）
+    #[test]
+    fn test_routes_match_openapi() {
+        let routes: Vec<_> = protected_routes()
+            .into_iter()
+            .chain(public_routes())
+            .collect();
+        assert_eq!(
+            api_routes::mismatches(&routes, &ApiDoc::openapi()),
+            Vec::<String>::new()
+        );
+    }
+}
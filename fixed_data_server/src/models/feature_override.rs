@@ -1,47 +1,102 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
+use webapp::repositories::{FeatureOverride, OverrideMode};
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// フィーチャーはビット位置の変更に影響されないよう列挙子名で保存し、ビット値は読み込み時に求める
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct CardFeatureOverride {
     pub pronunciation: String,
-    pub fixed_bits1: i64,
-    pub fixed_bits2: i64,
-    pub fixed_burst_bits: i64,
+    #[sqlx(try_from = "String")]
+    pub mode: OverrideMode,
+    /// CardFeatureの列挙子名（例: `Draw`）。置き換えなら修正後のすべて、部分修正なら追加するもの
+    #[sqlx(json)]
+    pub features: Vec<String>,
+    /// BurstFeatureの列挙子名
+    #[sqlx(json)]
+    pub burst_features: Vec<String>,
+    /// 部分修正で削除するCardFeatureの列挙子名
+    #[sqlx(json)]
+    pub removed_features: Vec<String>,
+    /// 部分修正で削除するBurstFeatureの列挙子名
+    #[sqlx(json)]
+    pub removed_burst_features: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub note: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl CardFeatureOverride {
+    pub fn feature_override(&self) -> FeatureOverride {
+        FeatureOverride {
+            mode: self.mode,
+            features: self.features.clone(),
+            burst_features: self.burst_features.clone(),
+            removed_features: self.removed_features.clone(),
+            removed_burst_features: self.removed_burst_features.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateOverrideRequest {
     pub pronunciation: String,
-    pub features: Vec<String>,  // CardFeature enum names
-    pub burst_features: Vec<String>,  // BurstFeature enum names
+    /// 省略時は置き換え
+    #[serde(default)]
+    pub mode: OverrideMode,
+    pub features: Vec<String>,  // CardFeature labels
+    pub burst_features: Vec<String>,  // BurstFeature labels
+    /// 部分修正で削除するフィーチャー（表示名）
+    #[serde(default)]
+    pub removed_features: Vec<String>,
+    #[serde(default)]
+    pub removed_burst_features: Vec<String>,
     pub note: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct OverrideResponse {
     pub pronunciation: String,
+    pub mode: OverrideMode,
     pub features: Vec<String>,
     pub burst_features: Vec<String>,
+    pub removed_features: Vec<String>,
+    pub removed_burst_features: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub note: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// 修正とルールベースの解析結果の比較（フィーチャーは表示名）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ConsistencyCheckResult {
     pub pronunciation: String,
+    pub mode: OverrideMode,
+    /// ルールベースの結果が修正後と同じで、修正が不要になっている（すべての再録で）
     pub is_consistent: bool,
+    /// この読みのカード番号（再録を含む）
+    pub card_codes: Vec<String>,
+    /// ルールベースの結果が `rule_based_features` と異なる再録のカード番号
+    pub differing_card_codes: Vec<String>,
+    /// 再録のうち最も多いルールベースの結果
     pub rule_based_features: Vec<String>,
+    /// 置き換えなら修正後のすべて、部分修正なら追加するもの
     pub override_features: Vec<String>,
     pub rule_based_burst_features: Vec<String>,
     pub override_burst_features: Vec<String>,
+    /// 部分修正で削除するもの
+    pub removed_features: Vec<String>,
+    pub removed_burst_features: Vec<String>,
+    /// 修正を適用した後の結果
+    pub effective_features: Vec<String>,
+    pub effective_burst_features: Vec<String>,
+    /// 部分修正のうち、すべての再録でルールがすでに一致していて効果のない追加・削除
+    pub redundant_features: Vec<String>,
+    pub redundant_burst_features: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ImportExportData {
     pub overrides: Vec<CardFeatureOverride>,
     pub exported_at: DateTime<Utc>,
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+use super::CardFeatureOverride;
+use webapp::repositories::{FeatureOverride, OverrideMode};
+
+/// 変更の種類（`wix_card_feature_override_history.action`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryAction {
+    Create,
+    Update,
+    Delete,
+    Revert,
+}
+
+impl HistoryAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryAction::Create => "create",
+            HistoryAction::Update => "update",
+            HistoryAction::Delete => "delete",
+            HistoryAction::Revert => "revert",
+        }
+    }
+}
+
+impl TryFrom<String> for HistoryAction {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "create" => Ok(HistoryAction::Create),
+            "update" => Ok(HistoryAction::Update),
+            "delete" => Ok(HistoryAction::Delete),
+            "revert" => Ok(HistoryAction::Revert),
+            _ => Err(format!("不明な操作です: {}", value)),
+        }
+    }
+}
+
+/// オーバーライドの中身（履歴の変更前・変更後の1件分）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverrideState {
+    /// フィーチャーは列挙子名
+    pub feature_override: FeatureOverride,
+    pub note: Option<String>,
+}
+
+impl From<&CardFeatureOverride> for OverrideState {
+    fn from(o: &CardFeatureOverride) -> Self {
+        Self {
+            feature_override: o.feature_override(),
+            note: o.note.clone(),
+        }
+    }
+}
+
+/// 履歴の1行。作成時は変更前、削除時は変更後が空になる
+#[derive(Debug, Clone, FromRow)]
+pub struct CardFeatureOverrideHistory {
+    pub id: i64,
+    pub pronunciation: String,
+    #[sqlx(try_from = "String")]
+    pub action: HistoryAction,
+    pub old_mode: Option<String>,
+    #[sqlx(json(nullable))]
+    pub old_features: Option<Vec<String>>,
+    #[sqlx(json(nullable))]
+    pub old_burst_features: Option<Vec<String>>,
+    #[sqlx(json(nullable))]
+    pub old_removed_features: Option<Vec<String>>,
+    #[sqlx(json(nullable))]
+    pub old_removed_burst_features: Option<Vec<String>>,
+    pub old_note: Option<String>,
+    pub new_mode: Option<String>,
+    #[sqlx(json(nullable))]
+    pub new_features: Option<Vec<String>>,
+    #[sqlx(json(nullable))]
+    pub new_burst_features: Option<Vec<String>>,
+    #[sqlx(json(nullable))]
+    pub new_removed_features: Option<Vec<String>>,
+    #[sqlx(json(nullable))]
+    pub new_removed_burst_features: Option<Vec<String>>,
+    pub new_note: Option<String>,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+    pub reverted_from_id: Option<i64>,
+}
+
+impl CardFeatureOverrideHistory {
+    pub fn old_state(&self) -> Option<OverrideState> {
+        state(
+            &self.old_mode,
+            &self.old_features,
+            &self.old_burst_features,
+            &self.old_removed_features,
+            &self.old_removed_burst_features,
+            &self.old_note,
+        )
+    }
+
+    pub fn new_state(&self) -> Option<OverrideState> {
+        state(
+            &self.new_mode,
+            &self.new_features,
+            &self.new_burst_features,
+            &self.new_removed_features,
+            &self.new_removed_burst_features,
+            &self.new_note,
+        )
+    }
+}
+
+/// 部分修正の導入前の履歴は種類が空なので置き換えとして扱う
+fn state(
+    mode: &Option<String>,
+    features: &Option<Vec<String>>,
+    burst_features: &Option<Vec<String>>,
+    removed_features: &Option<Vec<String>>,
+    removed_burst_features: &Option<Vec<String>>,
+    note: &Option<String>,
+) -> Option<OverrideState> {
+    Some(OverrideState {
+        feature_override: FeatureOverride {
+            mode: mode
+                .clone()
+                .and_then(|mode| OverrideMode::try_from(mode).ok())
+                .unwrap_or_default(),
+            features: features.clone()?,
+            burst_features: burst_features.clone().unwrap_or_default(),
+            removed_features: removed_features.clone().unwrap_or_default(),
+            removed_burst_features: removed_burst_features.clone().unwrap_or_default(),
+        },
+        note: note.clone(),
+    })
+}
+
+/// 変更前・変更後の内容（フィーチャーは表示名で表す）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct OverrideSnapshot {
+    pub mode: OverrideMode,
+    pub features: Vec<String>,
+    pub burst_features: Vec<String>,
+    pub removed_features: Vec<String>,
+    pub removed_burst_features: Vec<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistoryEntryResponse {
+    pub id: i64,
+    pub pronunciation: String,
+    pub action: HistoryAction,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+    /// 変更前（作成時は空）
+    pub old: Option<OverrideSnapshot>,
+    /// 変更後（削除時は空）
+    pub new: Option<OverrideSnapshot>,
+    /// 差し戻しの場合、取り消した履歴のID
+    pub reverted_from: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(action: &str) -> CardFeatureOverrideHistory {
+        CardFeatureOverrideHistory {
+            id: 1,
+            pronunciation: "テスト".to_string(),
+            action: HistoryAction::try_from(action.to_string()).unwrap(),
+            old_mode: None,
+            old_features: None,
+            old_burst_features: None,
+            old_removed_features: None,
+            old_removed_burst_features: None,
+            old_note: None,
+            new_mode: Some("partial".to_string()),
+            new_features: Some(vec!["Draw".to_string()]),
+            new_burst_features: Some(vec!["Search".to_string()]),
+            new_removed_features: Some(vec!["Banish".to_string()]),
+            new_removed_burst_features: Some(Vec::new()),
+            new_note: Some("メモ".to_string()),
+            changed_by: "anonymous".to_string(),
+            changed_at: Utc::now(),
+            reverted_from_id: None,
+        }
+    }
+
+    /// 作成の履歴は変更前が空で、差し戻すと削除になる
+    #[test]
+    fn test_created_entry_has_no_old_state() {
+        let entry = entry("create");
+        assert_eq!(entry.action, HistoryAction::Create);
+        assert_eq!(entry.old_state(), None);
+        assert_eq!(
+            entry.new_state(),
+            Some(OverrideState {
+                feature_override: FeatureOverride {
+                    mode: OverrideMode::Partial,
+                    features: vec!["Draw".to_string()],
+                    burst_features: vec!["Search".to_string()],
+                    removed_features: vec!["Banish".to_string()],
+                    removed_burst_features: Vec::new(),
+                },
+                note: Some("メモ".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_action_round_trip() {
+        for action in [
+            HistoryAction::Create,
+            HistoryAction::Update,
+            HistoryAction::Delete,
+            HistoryAction::Revert,
+        ] {
+            assert_eq!(
+                HistoryAction::try_from(action.as_str().to_string()),
+                Ok(action)
+            );
+        }
+        assert!(HistoryAction::try_from("rename".to_string()).is_err());
+    }
+}
@@ -1,3 +1,7 @@
 pub mod feature_override;
+pub mod history;
+pub mod review;
 
-pub use feature_override::*;
\ No newline at end of file
+pub use feature_override::*;
+pub use history::*;
+pub use review::*;
@@ -0,0 +1,451 @@
+use chrono::{DateTime, Utc};
+use feature::feature::{BurstFeature, CardFeature};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use webapp::analyze::wixoss::card::CardType;
+
+/// パターンで説明できた割合がこれ未満のカードを確認待ちにする
+pub const LOW_COVERAGE_RATIO: f64 = 0.5;
+
+/// キューに積んだ理由（`wix_card_review_item.reason`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewReason {
+    /// テキストがあるのにフィーチャーがひとつもない
+    NoFeatures,
+    /// フィーチャーがカード種類やライフバーストの有無と矛盾する
+    TypeConflict,
+    /// ルール更新後の再解析でフィーチャーが変わった
+    DetectionChanged,
+    /// スキルテキストの多くがどのパターンにも消費されていない
+    LowCoverage,
+}
+
+impl ReviewReason {
+    /// 一括更新（`/api/review/refresh`）で検出し直す理由。検出結果の変化は再解析時にだけ積む
+    pub const REFRESHED: [ReviewReason; 3] = [
+        ReviewReason::NoFeatures,
+        ReviewReason::TypeConflict,
+        ReviewReason::LowCoverage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewReason::NoFeatures => "no_features",
+            ReviewReason::TypeConflict => "type_conflict",
+            ReviewReason::DetectionChanged => "detection_changed",
+            ReviewReason::LowCoverage => "low_coverage",
+        }
+    }
+
+    /// 大きいほど先に確認する
+    pub fn priority(&self) -> i32 {
+        match self {
+            ReviewReason::TypeConflict => 40,
+            ReviewReason::NoFeatures => 30,
+            ReviewReason::DetectionChanged => 20,
+            ReviewReason::LowCoverage => 10,
+        }
+    }
+}
+
+impl TryFrom<String> for ReviewReason {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "no_features" => Ok(ReviewReason::NoFeatures),
+            "type_conflict" => Ok(ReviewReason::TypeConflict),
+            "detection_changed" => Ok(ReviewReason::DetectionChanged),
+            "low_coverage" => Ok(ReviewReason::LowCoverage),
+            _ => Err(format!("不明な理由です: {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    Pending,
+    Claimed,
+    Approved,
+    Rejected,
+}
+
+impl ReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewStatus::Pending => "pending",
+            ReviewStatus::Claimed => "claimed",
+            ReviewStatus::Approved => "approved",
+            ReviewStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        matches!(self, ReviewStatus::Approved | ReviewStatus::Rejected)
+    }
+}
+
+impl TryFrom<String> for ReviewStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "pending" => Ok(ReviewStatus::Pending),
+            "claimed" => Ok(ReviewStatus::Claimed),
+            "approved" => Ok(ReviewStatus::Approved),
+            "rejected" => Ok(ReviewStatus::Rejected),
+            _ => Err(format!("不明な状態です: {}", value)),
+        }
+    }
+}
+
+/// 確認キューの操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewAction {
+    Claim,
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CardReviewItem {
+    pub id: i64,
+    pub pronunciation: String,
+    #[sqlx(try_from = "String")]
+    pub reason: ReviewReason,
+    pub detail: String,
+    pub priority: i32,
+    #[sqlx(try_from = "String")]
+    pub status: ReviewStatus,
+    pub claimed_by: Option<String>,
+    pub claimed_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CardReviewItem {
+    /// `caller` が `action` を行った後の状態。行えない場合は理由を返す
+    ///
+    /// 担当は同じ人なら何度でも行え、承認・却下は担当前でも行える。他の人が担当中のものと対応済みのものは変更できない
+    pub fn next_status(&self, action: ReviewAction, caller: &str) -> Result<ReviewStatus, String> {
+        if self.status.is_resolved() {
+            return Err(format!("対応済みです: {}", self.status.as_str()));
+        }
+        if let Some(claimed_by) = &self.claimed_by {
+            if self.status == ReviewStatus::Claimed && claimed_by != caller {
+                return Err(format!("{} が担当中です", claimed_by));
+            }
+        }
+        Ok(match action {
+            ReviewAction::Claim => ReviewStatus::Claimed,
+            ReviewAction::Approve => ReviewStatus::Approved,
+            ReviewAction::Reject => ReviewStatus::Rejected,
+        })
+    }
+}
+
+/// キューに積む候補（同じ読みと理由の組は1件にまとめる）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewCandidate {
+    pub pronunciation: String,
+    pub reason: ReviewReason,
+    pub detail: String,
+}
+
+/// ヒューリスティクスで調べる1枚分の情報。フィーチャーはオーバーライド適用後
+#[derive(Debug, Clone, Default)]
+pub struct ReviewTarget {
+    pub pronunciation: String,
+    pub card_type: i32,
+    /// バーストあり1、なし2、無関係0
+    pub has_burst: i32,
+    pub skill_text: String,
+    pub feature_bits1: i64,
+    pub feature_bits2: i64,
+    pub burst_bits: i64,
+}
+
+/// 特定のカード種類にしか現れないフィーチャー
+fn type_restricted_features() -> [(CardFeature, Vec<CardType>); 3] {
+    [
+        (
+            CardFeature::Vanilla,
+            vec![CardType::Signi, CardType::SigniCraft],
+        ),
+        (
+            CardFeature::Rise,
+            vec![CardType::Signi, CardType::SigniCraft],
+        ),
+        (
+            CardFeature::Exceed,
+            vec![CardType::Lrig, CardType::LrigAssist],
+        ),
+    ]
+}
+
+impl ReviewTarget {
+    /// 一括更新で検出する候補。`coverage_ratio` はスキルテキストをパターンで説明できた割合
+    pub fn candidates(&self, coverage_ratio: Option<f64>) -> Vec<ReviewCandidate> {
+        let mut candidates = Vec::new();
+        let features = CardFeature::from_bits(self.feature_bits1, self.feature_bits2);
+        let burst_features = BurstFeature::from_bits(self.burst_bits);
+        let has_text = !self.skill_text.trim().is_empty();
+
+        if has_text && features.is_empty() && burst_features.is_empty() {
+            candidates.push(self.candidate(
+                ReviewReason::NoFeatures,
+                "テキストがあるのにフィーチャーがありません".to_string(),
+            ));
+        }
+
+        let conflicts = self.type_conflicts(&features, &burst_features, has_text);
+        if !conflicts.is_empty() {
+            candidates.push(self.candidate(ReviewReason::TypeConflict, conflicts.join(" / ")));
+        }
+
+        if let Some(ratio) = coverage_ratio.filter(|ratio| has_text && *ratio < LOW_COVERAGE_RATIO)
+        {
+            candidates.push(self.candidate(
+                ReviewReason::LowCoverage,
+                format!(
+                    "パターンで説明できたのはテキストの{:.0}%です",
+                    ratio * 100.0
+                ),
+            ));
+        }
+
+        candidates
+    }
+
+    fn type_conflicts(
+        &self,
+        features: &[CardFeature],
+        burst_features: &[BurstFeature],
+        has_text: bool,
+    ) -> Vec<String> {
+        let mut conflicts = Vec::new();
+
+        if self.has_burst != 1 && !burst_features.is_empty() {
+            conflicts.push(format!(
+                "ライフバーストがないのにバーストフィーチャーがあります: {}",
+                join_labels(burst_features)
+            ));
+        }
+        if self.has_burst == 1 && burst_features.is_empty() {
+            conflicts.push("ライフバーストがあるのにバーストフィーチャーがありません".to_string());
+        }
+        if has_text && features.contains(&CardFeature::Vanilla) {
+            conflicts.push(format!("テキストがあるのに{}です", CardFeature::Vanilla));
+        }
+        for (feature, card_types) in type_restricted_features() {
+            if features.contains(&feature)
+                && !card_types.iter().any(|t| t.to_db_id() == self.card_type)
+            {
+                let allowed: Vec<String> = card_types.iter().map(ToString::to_string).collect();
+                conflicts.push(format!(
+                    "{}は{}にしかありません",
+                    feature,
+                    allowed.join("・")
+                ));
+            }
+        }
+
+        conflicts
+    }
+
+    fn candidate(&self, reason: ReviewReason, detail: String) -> ReviewCandidate {
+        ReviewCandidate {
+            pronunciation: self.pronunciation.clone(),
+            reason,
+            detail,
+        }
+    }
+}
+
+fn join_labels<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 確認キューの絞り込み
+#[derive(Debug, Clone, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReviewQuery {
+    /// 状態（省略時は確認待ちと担当中）
+    pub status: Option<ReviewStatus>,
+    pub reason: Option<ReviewReason>,
+    pub pronunciation: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ResolveReviewRequest {
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RefreshReviewResponse {
+    /// 調べた読みの数
+    pub checked: usize,
+    /// 検出した候補の数（既存の項目の更新を含む）
+    pub flagged: usize,
+    /// 検出されなくなったため削除した未着手の項目の数（担当中の項目は残す）
+    pub removed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feature::feature::HashSetToBits;
+    use std::collections::HashSet;
+
+    fn item(status: ReviewStatus, claimed_by: Option<&str>) -> CardReviewItem {
+        CardReviewItem {
+            id: 1,
+            pronunciation: "テスト".to_string(),
+            reason: ReviewReason::NoFeatures,
+            detail: String::new(),
+            priority: ReviewReason::NoFeatures.priority(),
+            status,
+            claimed_by: claimed_by.map(str::to_string),
+            claimed_at: None,
+            resolved_by: None,
+            resolved_at: None,
+            resolution_note: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn signi(features: &[CardFeature]) -> ReviewTarget {
+        let (feature_bits1, feature_bits2) =
+            features.iter().cloned().collect::<HashSet<_>>().to_bits();
+        ReviewTarget {
+            pronunciation: "テスト".to_string(),
+            card_type: CardType::Signi.to_db_id(),
+            has_burst: 2,
+            skill_text: "【出】カードを1枚引く。".to_string(),
+            feature_bits1,
+            feature_bits2,
+            burst_bits: 0,
+        }
+    }
+
+    fn reasons(candidates: &[ReviewCandidate]) -> Vec<ReviewReason> {
+        candidates.iter().map(|c| c.reason).collect()
+    }
+
+    #[test]
+    fn test_transitions() {
+        let pending = item(ReviewStatus::Pending, None);
+        assert_eq!(
+            pending.next_status(ReviewAction::Claim, "alice"),
+            Ok(ReviewStatus::Claimed)
+        );
+        assert_eq!(
+            pending.next_status(ReviewAction::Approve, "alice"),
+            Ok(ReviewStatus::Approved)
+        );
+
+        let claimed = item(ReviewStatus::Claimed, Some("alice"));
+        assert_eq!(
+            claimed.next_status(ReviewAction::Claim, "alice"),
+            Ok(ReviewStatus::Claimed)
+        );
+        assert_eq!(
+            claimed.next_status(ReviewAction::Reject, "alice"),
+            Ok(ReviewStatus::Rejected)
+        );
+        assert!(claimed.next_status(ReviewAction::Approve, "bob").is_err());
+
+        let approved = item(ReviewStatus::Approved, Some("alice"));
+        assert!(approved.next_status(ReviewAction::Claim, "alice").is_err());
+    }
+
+    #[test]
+    fn test_no_features() {
+        assert_eq!(
+            reasons(&signi(&[]).candidates(None)),
+            vec![ReviewReason::NoFeatures]
+        );
+        assert!(signi(&[CardFeature::Draw]).candidates(None).is_empty());
+
+        let blank = ReviewTarget {
+            skill_text: " ".to_string(),
+            ..signi(&[])
+        };
+        assert!(blank.candidates(None).is_empty());
+    }
+
+    #[test]
+    fn test_type_conflicts() {
+        let lrig_rise = ReviewTarget {
+            card_type: CardType::Lrig.to_db_id(),
+            has_burst: 0,
+            ..signi(&[CardFeature::Rise])
+        };
+        assert_eq!(
+            reasons(&lrig_rise.candidates(None)),
+            vec![ReviewReason::TypeConflict]
+        );
+
+        let burst_without_features = ReviewTarget {
+            has_burst: 1,
+            ..signi(&[CardFeature::Draw])
+        };
+        let candidates = burst_without_features.candidates(None);
+        assert_eq!(reasons(&candidates), vec![ReviewReason::TypeConflict]);
+        assert!(candidates[0]
+            .detail
+            .contains("バーストフィーチャーがありません"));
+
+        let vanilla_with_text = signi(&[CardFeature::Vanilla]);
+        assert_eq!(
+            reasons(&vanilla_with_text.candidates(None)),
+            vec![ReviewReason::TypeConflict]
+        );
+    }
+
+    #[test]
+    fn test_low_coverage() {
+        let target = signi(&[CardFeature::Draw]);
+        let candidates = target.candidates(Some(0.25));
+        assert_eq!(reasons(&candidates), vec![ReviewReason::LowCoverage]);
+        assert!(candidates[0].detail.contains("25%"));
+        assert!(target.candidates(Some(LOW_COVERAGE_RATIO)).is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for reason in [
+            ReviewReason::NoFeatures,
+            ReviewReason::TypeConflict,
+            ReviewReason::DetectionChanged,
+            ReviewReason::LowCoverage,
+        ] {
+            assert_eq!(
+                ReviewReason::try_from(reason.as_str().to_string()),
+                Ok(reason)
+            );
+        }
+        for status in [
+            ReviewStatus::Pending,
+            ReviewStatus::Claimed,
+            ReviewStatus::Approved,
+            ReviewStatus::Rejected,
+        ] {
+            assert_eq!(
+                ReviewStatus::try_from(status.as_str().to_string()),
+                Ok(status)
+            );
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! パターン候補を全カードのスキルテキストに当てて評価する
+//!
+//! 正解データはないため、精度・再現率は現在の検出結果（wix_cardのフィーチャー）との一致で近似する。
+
+use feature::feature::CardFeature;
+use feature::labels::FEATURE_LABELS;
+use feature::text::to_half;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// 返すヒット例の最大数
+const MAX_SAMPLES: usize = 20;
+
+/// 返す確認済みカードの変化の最大数
+const MAX_REVIEWED_CHANGES: usize = 100;
+
+/// ヒット例に含める前後の文字数
+const CONTEXT_CHARS: usize = 15;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EvaluatePatternRequest {
+    pub pattern: String,
+    /// 付けるフィーチャー（列挙子名または表示名）
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub positive_examples: Vec<String>,
+    #[serde(default)]
+    pub negative_examples: Vec<String>,
+}
+
+/// 評価対象の1枚（wix_rawcardと、解析済みならwix_cardの内容）
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct CorpusCard {
+    pub card_number: String,
+    pub name: String,
+    pub skill_text: String,
+    pub pronunciation: Option<String>,
+    pub feature_bits1: Option<i64>,
+    pub feature_bits2: Option<i64>,
+    /// フィーチャーの手動修正がある
+    pub overridden: bool,
+    /// 確認キューで承認済み
+    pub approved: bool,
+}
+
+impl CorpusCard {
+    fn is_reviewed(&self) -> bool {
+        self.overridden || self.approved
+    }
+
+    fn features(&self) -> Vec<CardFeature> {
+        match (self.feature_bits1, self.feature_bits2) {
+            (Some(bits1), Some(bits2)) => CardFeature::from_bits(bits1, bits2),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PatternHit {
+    pub card_number: String,
+    pub name: String,
+    /// マッチした部分
+    pub matched: String,
+    /// マッチした部分の前後を含む抜粋
+    pub context: String,
+}
+
+/// 人が確認済みのカードで、パターンを追加すると検出結果が変わるもの
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ReviewedCardChange {
+    pub card_number: String,
+    pub pronunciation: String,
+    /// 新たに付くフィーチャー（表示名）
+    pub added_features: Vec<String>,
+    pub overridden: bool,
+    pub approved: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PatternEvaluation {
+    /// 例の検証に通り、フィーチャーもすべて解決できた
+    pub valid: bool,
+    /// マッチしなかったマッチすべき例
+    pub positive_failures: Vec<String>,
+    /// マッチしたマッチすべきでない例
+    pub negative_failures: Vec<String>,
+    /// 定義にないフィーチャー
+    pub unknown_features: Vec<String>,
+    pub total_cards: usize,
+    pub matched_cards: usize,
+    /// マッチしたうち、すでにフィーチャーがすべて付いているカード
+    pub already_detected: usize,
+    /// マッチしたうち、フィーチャーが新たに付くカード
+    pub newly_detected: usize,
+    /// フィーチャーが付いているのにマッチしなかったカード
+    pub missed: usize,
+    /// already_detected / matched_cards（フィーチャー未指定・マッチなしなら空）
+    pub precision: Option<f64>,
+    /// already_detected / (already_detected + missed)
+    pub recall: Option<f64>,
+    pub samples: Vec<PatternHit>,
+    /// 確認済みカードの変化（先頭 `MAX_REVIEWED_CHANGES` 件まで）
+    pub reviewed_changes: Vec<ReviewedCardChange>,
+    /// 確認済みカードの変化の総数
+    pub reviewed_change_count: usize,
+}
+
+/// 列挙子名・表示名のどちらでも受け付ける
+pub fn resolve_feature(name: &str) -> Option<CardFeature> {
+    CardFeature::from_name(name).or_else(|| FEATURE_LABELS.get(name).cloned())
+}
+
+/// `regex` を例とカード全体に当てて評価する（テキストは解析時と同じく半角化してから当てる）
+pub fn evaluate(
+    regex: &Regex,
+    request: &EvaluatePatternRequest,
+    cards: &[CorpusCard],
+) -> PatternEvaluation {
+    let matches = |text: &str| regex.is_match(&to_half(text));
+    let positive_failures: Vec<String> = request
+        .positive_examples
+        .iter()
+        .filter(|example| !matches(example))
+        .cloned()
+        .collect();
+    let negative_failures: Vec<String> = request
+        .negative_examples
+        .iter()
+        .filter(|example| matches(example))
+        .cloned()
+        .collect();
+
+    let mut features = Vec::new();
+    let mut unknown_features = Vec::new();
+    for name in &request.features {
+        match resolve_feature(name) {
+            Some(feature) => features.push(feature),
+            None => unknown_features.push(name.clone()),
+        }
+    }
+
+    let mut matched_cards = 0;
+    let mut already_detected = 0;
+    let mut missed = 0;
+    let mut samples = Vec::new();
+    let mut reviewed_changes = Vec::new();
+    let mut reviewed_change_count = 0;
+    for card in cards {
+        let text = to_half(&card.skill_text);
+        let current = card.features();
+        let added: Vec<&CardFeature> = features.iter().filter(|f| !current.contains(f)).collect();
+        let detected = !features.is_empty() && added.is_empty();
+
+        let Some(found) = regex.find(&text) else {
+            if detected {
+                missed += 1;
+            }
+            continue;
+        };
+
+        matched_cards += 1;
+        if detected {
+            already_detected += 1;
+        }
+        if samples.len() < MAX_SAMPLES {
+            samples.push(PatternHit {
+                card_number: card.card_number.clone(),
+                name: card.name.clone(),
+                matched: found.as_str().to_string(),
+                context: context(&text, found.start(), found.end()),
+            });
+        }
+        if let (true, false, Some(pronunciation)) =
+            (card.is_reviewed(), added.is_empty(), &card.pronunciation)
+        {
+            reviewed_change_count += 1;
+            if reviewed_changes.len() >= MAX_REVIEWED_CHANGES {
+                continue;
+            }
+            reviewed_changes.push(ReviewedCardChange {
+                card_number: card.card_number.clone(),
+                pronunciation: pronunciation.clone(),
+                added_features: added.iter().map(|f| f.to_string()).collect(),
+                overridden: card.overridden,
+                approved: card.approved,
+            });
+        }
+    }
+
+    let ratio = |numerator: usize, denominator: usize| {
+        (!features.is_empty() && denominator > 0).then(|| numerator as f64 / denominator as f64)
+    };
+    PatternEvaluation {
+        valid: positive_failures.is_empty()
+            && negative_failures.is_empty()
+            && unknown_features.is_empty(),
+        positive_failures,
+        negative_failures,
+        unknown_features,
+        total_cards: cards.len(),
+        matched_cards,
+        already_detected,
+        newly_detected: if features.is_empty() {
+            0
+        } else {
+            matched_cards - already_detected
+        },
+        missed,
+        precision: ratio(already_detected, matched_cards),
+        recall: ratio(already_detected, already_detected + missed),
+        samples,
+        reviewed_changes,
+        reviewed_change_count,
+    }
+}
+
+/// マッチ位置の前後 `CONTEXT_CHARS` 文字を切り出す
+fn context(text: &str, start: usize, end: usize) -> String {
+    let before: String = text[..start]
+        .chars()
+        .rev()
+        .take(CONTEXT_CHARS)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let after: String = text[end..].chars().take(CONTEXT_CHARS).collect();
+    format!("{}{}{}", before, &text[start..end], after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use feature::feature::HashSetToBits;
+    use std::collections::HashSet;
+
+    fn card(number: &str, text: &str, features: &[CardFeature], overridden: bool) -> CorpusCard {
+        let (bits1, bits2) = features.iter().cloned().collect::<HashSet<_>>().to_bits();
+        CorpusCard {
+            card_number: number.to_string(),
+            name: number.to_string(),
+            skill_text: text.to_string(),
+            pronunciation: Some(number.to_string()),
+            feature_bits1: Some(bits1),
+            feature_bits2: Some(bits2),
+            overridden,
+            approved: false,
+        }
+    }
+
+    fn request(features: &[&str]) -> EvaluatePatternRequest {
+        EvaluatePatternRequest {
+            pattern: "手札に加え".to_string(),
+            features: features.iter().map(|s| s.to_string()).collect(),
+            positive_examples: vec!["カードを１枚手札に加える。".to_string()],
+            negative_examples: vec!["カードを１枚引く。".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_evaluate_counts_and_reviewed_changes() {
+        let cards = vec![
+            card(
+                "A",
+                "トラッシュからシグニ１枚を手札に加える。",
+                &[CardFeature::Salvage],
+                false,
+            ),
+            card("B", "デッキからカード１枚を手札に加える。", &[], true),
+            card("C", "カードを１枚引く。", &[], false),
+            card(
+                "D",
+                "トラッシュから回収する。",
+                &[CardFeature::Salvage],
+                false,
+            ),
+        ];
+        let regex = Regex::new("手札に加え").unwrap();
+        let evaluation = evaluate(&regex, &request(&["トラッシュ回収"]), &cards);
+
+        assert!(evaluation.valid);
+        assert_eq!(evaluation.total_cards, 4);
+        assert_eq!(evaluation.matched_cards, 2);
+        assert_eq!(evaluation.already_detected, 1);
+        assert_eq!(evaluation.newly_detected, 1);
+        assert_eq!(evaluation.missed, 1);
+        assert_eq!(evaluation.precision, Some(0.5));
+        assert_eq!(evaluation.recall, Some(0.5));
+        assert_eq!(evaluation.samples[0].matched, "手札に加え");
+        assert_eq!(
+            evaluation.reviewed_changes,
+            vec![ReviewedCardChange {
+                card_number: "B".to_string(),
+                pronunciation: "B".to_string(),
+                added_features: vec!["トラッシュ回収".to_string()],
+                overridden: true,
+                approved: false,
+            }]
+        );
+        assert_eq!(evaluation.reviewed_change_count, 1);
+    }
+
+    #[test]
+    fn test_reviewed_changes_are_capped() {
+        let cards: Vec<CorpusCard> = (0..MAX_REVIEWED_CHANGES + 5)
+            .map(|i| card(&i.to_string(), "カードを１枚手札に加える。", &[], true))
+            .collect();
+        let regex = Regex::new("手札に加え").unwrap();
+        let evaluation = evaluate(&regex, &request(&["トラッシュ回収"]), &cards);
+
+        assert_eq!(evaluation.matched_cards, MAX_REVIEWED_CHANGES + 5);
+        assert_eq!(evaluation.samples.len(), MAX_SAMPLES);
+        assert_eq!(evaluation.reviewed_changes.len(), MAX_REVIEWED_CHANGES);
+        assert_eq!(evaluation.reviewed_change_count, MAX_REVIEWED_CHANGES + 5);
+    }
+
+    #[test]
+    fn test_examples_and_unknown_features_make_invalid() {
+        let regex = Regex::new("引く").unwrap();
+        let evaluation = evaluate(&regex, &request(&["Salvage", "存在しない"]), &[]);
+        assert!(!evaluation.valid);
+        assert_eq!(evaluation.positive_failures.len(), 1);
+        assert_eq!(evaluation.negative_failures.len(), 1);
+        assert_eq!(evaluation.unknown_features, vec!["存在しない".to_string()]);
+        assert_eq!(evaluation.precision, None);
+    }
+
+    #[test]
+    fn test_context_is_char_safe() {
+        let text = "あいうえおかきくけこさしすせそたちつてとなにぬねの手札に加える";
+        let start = text.find("手札").unwrap();
+        assert_eq!(
+            context(text, start, start + "手札".len()),
+            "さしすせそたちつてとなにぬねの手札に加える"
+        );
+    }
+}
@@ -0,0 +1,555 @@
+//! 保存済みパターンを `generated_patterns.rs` に書き出す前の検査とコード生成
+//!
+//! 手書きのパターン（`feature::create_detect_patterns`）と先に書き出すパターンに突き合わせ、
+//! エラーが1件でもあればコードを生成しない。
+//! 重なりはマッチすべき例文と、各パターンの正規表現から作ったマッチする文字列で調べるため、例文のないパターンも対象になる。
+
+use feature::feature::CardFeature;
+use feature::text::to_half;
+use regex::Regex;
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde::Serialize;
+use std::fmt;
+
+use crate::evaluate::resolve_feature;
+use crate::models::rule_pattern::RulePattern;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// コードを生成しない
+    Error,
+    /// 書き出す（重複なら書き出さない）が確認が必要
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExportIssue {
+    pub severity: Severity,
+    pub pattern_id: i32,
+    pub keyword: String,
+    pub pattern: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ExportReport {
+    /// 書き出したパターン数
+    pub exported: usize,
+    /// 重複・フィーチャーなしで書き出さなかったパターン数
+    pub skipped: usize,
+    pub issues: Vec<ExportIssue>,
+}
+
+impl ExportReport {
+    pub fn error_count(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .count()
+    }
+
+    /// CLIに表示する形式
+    pub fn summary(&self) -> String {
+        let mut lines = vec![format!(
+            "書き出し: {}件 / 見送り: {}件 / エラー: {}件 / 警告: {}件",
+            self.exported,
+            self.skipped,
+            self.error_count(),
+            self.issues.len() - self.error_count()
+        )];
+        for issue in &self.issues {
+            let severity = match issue.severity {
+                Severity::Error => "エラー",
+                Severity::Warning => "警告",
+            };
+            lines.push(format!(
+                "  [{}] #{} {} ({}): {}",
+                severity, issue.pattern_id, issue.keyword, issue.pattern, issue.message
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Database(String),
+    /// 検査でエラーがあった
+    Rejected(ExportReport),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Database(e) => write!(f, "Failed to fetch patterns: {}", e),
+            ExportError::Rejected(report) => write!(
+                f,
+                "検査でエラーが{}件あったため書き出しを中止しました",
+                report.error_count()
+            ),
+        }
+    }
+}
+
+/// 既に検出に使われている（または先に書き出す）パターン
+pub struct KnownPattern {
+    /// 報告用の出どころ
+    pub source: String,
+    pub pattern: String,
+    pub regex: Regex,
+    pub features: Vec<CardFeature>,
+    /// `regex` にマッチする文字列（`samples` で作る）
+    pub samples: Vec<String>,
+}
+
+impl KnownPattern {
+    pub fn new(source: String, regex: Regex, features: Vec<CardFeature>) -> Self {
+        Self {
+            source,
+            pattern: regex.as_str().to_string(),
+            samples: samples(&regex),
+            regex,
+            features,
+        }
+    }
+}
+
+/// shared/feature に手書きされている置換・検出パターン
+pub fn hand_written_patterns() -> Vec<KnownPattern> {
+    let (replace_patterns, detect_patterns) = feature::create_detect_patterns();
+    let replace = replace_patterns
+        .into_iter()
+        .map(|p| (p.pattern, p.pattern_r, p.features_detected));
+    let detect = detect_patterns
+        .into_iter()
+        .map(|p| (p.pattern, p.pattern_r, p.features_detected));
+    replace
+        .chain(detect)
+        .map(|(pattern, regex, features)| {
+            KnownPattern::new(
+                format!("shared/feature の r\"{}\"", pattern),
+                regex,
+                features.to_vec(),
+            )
+        })
+        .collect()
+}
+
+/// 検査してコードを生成する。エラーがあれば報告だけを返す
+pub fn generate(
+    patterns: &[RulePattern],
+    mut known: Vec<KnownPattern>,
+) -> Result<(String, ExportReport), ExportReport> {
+    let mut report = ExportReport::default();
+    let mut output = String::from("// Auto-generated by rule_editor\n");
+    output.push_str("// このファイルは自動生成されています。直接編集しないでください。\n\n");
+
+    for pattern in patterns {
+        let mut issue = |severity: Severity, message: String| {
+            report.issues.push(ExportIssue {
+                severity,
+                pattern_id: pattern.id,
+                keyword: pattern.keyword.clone(),
+                pattern: pattern.pattern.clone(),
+                message,
+            })
+        };
+
+        let regex = match Regex::new(&pattern.pattern) {
+            Ok(regex) => regex,
+            Err(e) => {
+                issue(Severity::Error, format!("正規表現が不正です: {}", e));
+                continue;
+            }
+        };
+
+        let mut features = Vec::new();
+        let mut unknown = Vec::new();
+        for name in pattern.features_as_vec() {
+            match resolve_feature(&name) {
+                Some(feature) if !features.contains(&feature) => features.push(feature),
+                Some(_) => {}
+                None => unknown.push(name),
+            }
+        }
+        if !unknown.is_empty() {
+            issue(
+                Severity::Error,
+                format!("定義にないフィーチャーです: {}", unknown.join(", ")),
+            );
+            continue;
+        }
+        if features.is_empty() {
+            issue(
+                Severity::Warning,
+                "フィーチャーがないため書き出しません".to_string(),
+            );
+            report.skipped += 1;
+            continue;
+        }
+
+        // 書き方が違っても、互いにマッチする文字列全体にマッチするなら同じパターンとみなす
+        let samples = samples(&regex);
+        if let Some(same) = known
+            .iter()
+            .find(|k| k.pattern == pattern.pattern || equivalent(&regex, &samples, k))
+        {
+            let same_text = if same.pattern == pattern.pattern {
+                "同じ"
+            } else {
+                "同等の"
+            };
+            if same_features(&same.features, &features) {
+                issue(
+                    Severity::Warning,
+                    format!(
+                        "{} と{}パターンのため書き出しません",
+                        same.source, same_text
+                    ),
+                );
+                report.skipped += 1;
+            } else {
+                issue(
+                    Severity::Error,
+                    format!(
+                        "{} と{}パターンで別のフィーチャー（{}）を検出します",
+                        same.source,
+                        same_text,
+                        labels(&same.features)
+                    ),
+                );
+            }
+            continue;
+        }
+
+        let texts: Vec<String> = pattern
+            .positive_examples_as_vec()
+            .iter()
+            .map(|example| to_half(example))
+            .chain(samples)
+            .collect();
+        let mut conflicted = false;
+        for (other, matched, exact) in overlaps(&regex, &texts, &known) {
+            if same_features(&other.features, &features) {
+                issue(
+                    Severity::Warning,
+                    format!(
+                        "{} とマッチ範囲が重なります（同じフィーチャー）: 「{}」",
+                        other.source, matched
+                    ),
+                );
+            } else if exact {
+                conflicted = true;
+                issue(
+                    Severity::Error,
+                    format!(
+                        "{} が同じ箇所「{}」に別のフィーチャー（{}）を付けます",
+                        other.source,
+                        matched,
+                        labels(&other.features)
+                    ),
+                );
+            } else {
+                issue(
+                    Severity::Warning,
+                    format!(
+                        "{} とマッチ範囲が重なり、別のフィーチャー（{}）を付けます: 「{}」",
+                        other.source,
+                        labels(&other.features),
+                        matched
+                    ),
+                );
+            }
+        }
+        if conflicted {
+            continue;
+        }
+
+        output.push_str(&format!(
+            "detect_pattern![{}, {}], // {}\n",
+            raw_string(&pattern.pattern),
+            features
+                .iter()
+                .map(|f| format!("CardFeature::{}", f.name()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            pattern.keyword.replace(['\r', '\n'], " ")
+        ));
+        report.exported += 1;
+        known.push(KnownPattern::new(
+            format!("#{} r\"{}\"", pattern.id, pattern.pattern),
+            regex,
+            features,
+        ));
+    }
+
+    if report.error_count() > 0 {
+        Err(report)
+    } else {
+        Ok((output, report))
+    }
+}
+
+/// `texts` と既知のパターンの `samples` の上で `regex` のマッチと範囲が重なる既知のパターン
+/// （パターンごとに最初の1件）
+///
+/// 返り値は（パターン、マッチした部分、範囲が完全に一致したか）
+fn overlaps<'a>(
+    regex: &Regex,
+    texts: &[String],
+    known: &'a [KnownPattern],
+) -> Vec<(&'a KnownPattern, String, bool)> {
+    known
+        .iter()
+        .filter_map(|other| {
+            texts.iter().chain(&other.samples).find_map(|text| {
+                regex.find_iter(text).find_map(|m| {
+                    other
+                        .regex
+                        .find_iter(text)
+                        .find(|o| o.start() < m.end() && m.start() < o.end())
+                        .map(|o| {
+                            let exact = o.range() == m.range();
+                            (other, m.as_str().to_string(), exact)
+                        })
+                })
+            })
+        })
+        .collect()
+}
+
+/// `regex` と `other` が互いの `samples` の全体にマッチする
+fn equivalent(regex: &Regex, samples: &[String], other: &KnownPattern) -> bool {
+    let matches_whole = |regex: &Regex, text: &String| {
+        regex
+            .find(text)
+            .is_some_and(|m| m.start() == 0 && m.end() == text.len())
+    };
+    !samples.is_empty()
+        && !other.samples.is_empty()
+        && samples.iter().all(|text| matches_whole(&other.regex, text))
+        && other.samples.iter().all(|text| matches_whole(regex, text))
+}
+
+/// 正規表現にマッチする文字列（繰り返しを最少にしたものと、省略できる部分を1回入れたもの）
+///
+/// 選択は先頭の候補、文字クラスは制御文字でない最初の文字を使う。
+/// 単語境界などの条件を満たさずマッチしなかったものと、空文字列は除く
+fn samples(regex: &Regex) -> Vec<String> {
+    let Ok(hir) = regex_syntax::Parser::new().parse(regex.as_str()) else {
+        return Vec::new();
+    };
+    let mut samples: Vec<String> = Vec::new();
+    for optional in [false, true] {
+        let mut text = String::new();
+        push_sample(&hir, optional, &mut text);
+        if !text.is_empty() && !samples.contains(&text) && regex.is_match(&text) {
+            samples.push(text);
+        }
+    }
+    samples
+}
+
+fn push_sample(hir: &Hir, optional: bool, text: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => text.push_str(&String::from_utf8_lossy(&literal.0)),
+        HirKind::Class(Class::Unicode(class)) => text.extend(
+            class
+                .ranges()
+                .iter()
+                .find_map(|range| (range.start()..=range.end()).find(|c| !c.is_control())),
+        ),
+        HirKind::Class(Class::Bytes(class)) => text.extend(
+            class
+                .ranges()
+                .iter()
+                .find_map(|range| (range.start()..=range.end()).find(u8::is_ascii_graphic))
+                .map(char::from),
+        ),
+        HirKind::Repetition(repetition) => {
+            let count = if optional {
+                repetition
+                    .min
+                    .max(1)
+                    .min(repetition.max.unwrap_or(u32::MAX))
+            } else {
+                repetition.min
+            };
+            for _ in 0..count {
+                push_sample(&repetition.sub, optional, text);
+            }
+        }
+        HirKind::Capture(capture) => push_sample(&capture.sub, optional, text),
+        HirKind::Concat(hirs) => hirs.iter().for_each(|hir| push_sample(hir, optional, text)),
+        HirKind::Alternation(hirs) => push_sample(&hirs[0], optional, text),
+    }
+}
+
+fn same_features(a: &[CardFeature], b: &[CardFeature]) -> bool {
+    a.len() == b.len() && a.iter().all(|feature| b.contains(feature))
+}
+
+fn labels(features: &[CardFeature]) -> String {
+    features
+        .iter()
+        .map(|f| f.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 中身に `"` があっても閉じないよう、必要な数の `#` を付けた生文字列リテラルにする
+fn raw_string(pattern: &str) -> String {
+    let mut hashes = String::new();
+    while pattern.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("r{}\"{}\"{}", hashes, pattern, hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn rule(id: i32, pattern: &str, features: &[&str], examples: &[&str]) -> RulePattern {
+        RulePattern {
+            id,
+            keyword: pattern.to_string(),
+            pattern: pattern.to_string(),
+            features: json!(features),
+            positive_examples: json!(examples),
+            negative_examples: json!([]),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            is_active: true,
+        }
+    }
+
+    fn known(pattern: &str, features: &[CardFeature]) -> KnownPattern {
+        KnownPattern::new(
+            format!("手書き {}", pattern),
+            Regex::new(pattern).unwrap(),
+            features.to_vec(),
+        )
+    }
+
+    #[test]
+    fn test_generate_resolves_labels_and_skips_duplicates() {
+        let patterns = vec![
+            rule(1, "手札に加え", &["トラッシュ回収"], &[]),
+            rule(2, "バニッシュ", &["Banish"], &[]),
+            rule(3, "\"引く\"", &["ドロー"], &[]),
+        ];
+        let (code, report) =
+            generate(&patterns, vec![known("バニッシュ", &[CardFeature::Banish])]).unwrap();
+
+        assert!(code
+            .contains("detect_pattern![r\"手札に加え\", CardFeature::Salvage], // 手札に加え\n"));
+        assert!(code.contains("detect_pattern![r#\"\"引く\"\"#, CardFeature::Draw]"));
+        assert!(!code.contains("バニッシュ"));
+        assert_eq!((report.exported, report.skipped), (2, 1));
+        assert_eq!(report.issues[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_generate_rejects_broken_and_conflicting_patterns() {
+        let patterns = vec![
+            rule(1, "(手札", &["トラッシュ回収"], &[]),
+            rule(2, "手札に", &["存在しない"], &[]),
+            rule(3, "バニッシュ", &["ドロー"], &[]),
+            rule(
+                4,
+                "手札に加える",
+                &["ドロー"],
+                &["トラッシュから１枚を手札に加える。"],
+            ),
+        ];
+        let report = generate(
+            &patterns,
+            vec![
+                known("バニッシュ", &[CardFeature::Banish]),
+                known("手札に加え(る)?", &[CardFeature::Salvage]),
+            ],
+        )
+        .unwrap_err();
+
+        let errors: Vec<i32> = report
+            .issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.pattern_id)
+            .collect();
+        assert_eq!(errors, vec![1, 2, 3, 4]);
+        assert_eq!(report.exported, 0);
+    }
+
+    #[test]
+    fn test_overlap_with_earlier_pattern_is_reported() {
+        let patterns = vec![
+            rule(1, "トラッシュから.*手札に加え", &["トラッシュ回収"], &[]),
+            rule(
+                2,
+                "手札に加え",
+                &["ドロー"],
+                &["トラッシュから１枚を手札に加える。"],
+            ),
+        ];
+        let (_, report) = generate(&patterns, Vec::new()).unwrap();
+        assert_eq!(report.exported, 2);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].severity, Severity::Warning);
+        assert_eq!(report.issues[0].pattern_id, 2);
+    }
+
+    #[test]
+    fn test_samples_match_the_pattern() {
+        assert_eq!(
+            samples(&Regex::new("手札に加え(る)?").unwrap()),
+            vec!["手札に加え", "手札に加える"]
+        );
+        assert_eq!(samples(&Regex::new("(引く|ドロー)").unwrap()), vec!["引く"]);
+        assert_eq!(
+            samples(&Regex::new("トラッシュから.*手札に加え").unwrap()),
+            vec!["トラッシュから手札に加え", "トラッシュから 手札に加え"]
+        );
+        assert!(samples(&Regex::new("^$").unwrap()).is_empty());
+        assert!(samples(&Regex::new(r"\<.+\>のルリグ").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_patterns_without_examples_are_checked_against_known_patterns() {
+        let patterns = vec![
+            rule(1, "手札に加える?", &["ドロー"], &[]),
+            rule(2, "(バニッシュ)", &["Banish"], &[]),
+            rule(3, "カードを1枚引く", &["Banish"], &[]),
+        ];
+        let report = generate(
+            &patterns,
+            vec![
+                known("手札に加え(る)?", &[CardFeature::Salvage]),
+                known("バニッシュ", &[CardFeature::Banish]),
+                known("1枚引く", &[CardFeature::Draw]),
+            ],
+        )
+        .unwrap_err();
+
+        let issues: Vec<(i32, Severity)> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.pattern_id, issue.severity))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (1, Severity::Error),
+                (2, Severity::Warning),
+                (3, Severity::Warning)
+            ]
+        );
+        assert!(report.issues[0].message.contains("同等のパターン"));
+        assert_eq!((report.exported, report.skipped), (1, 1));
+    }
+}
@@ -0,0 +1,354 @@
+//! パターン生成に使うLLM
+//!
+//! 設定の `LLM_PROVIDER` で OpenAI・OpenAI互換のローカルサーバー・モックを切り替える。
+//! プロンプトはどれも共通で、応答の受け取り方（Tool Calling / JSONモード）だけが異なる。
+
+use async_openai::{
+    config::OpenAIConfig,
+    types::{
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
+        ChatCompletionRequestSystemMessageContent, ChatCompletionRequestUserMessage,
+        ChatCompletionRequestUserMessageContent, ChatCompletionToolChoiceOption,
+        CreateChatCompletionRequest, ResponseFormat,
+    },
+    Client,
+};
+use config::{LlmProviderKind, OpenAiConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+
+pub type LlmError = Box<dyn std::error::Error + Send + Sync>;
+
+/// LLMが返すパターンの候補
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegexPatternResponse {
+    pub patterns: Vec<String>,
+    pub explanation: String,
+    pub success: bool,
+}
+
+/// パターン生成の入力（前処理済み）
+#[derive(Debug, Clone)]
+pub struct PatternPrompt {
+    pub keyword: String,
+    /// `Salvage(トラッシュ回収)` の形式
+    pub feature_context: Vec<String>,
+    /// 検索キーワードを含む文
+    pub sentences: Vec<String>,
+}
+
+const SYSTEM_PROMPT: &str = "あなたはWIXOSSトレーディングカードゲームのテキスト解析専門家です。\
+    カードのスキルテキストから特定の機能を検出するための正規表現パターンを生成してください。\n\n\
+    重要な原則：\n\
+    - 既存の成功例を参考にシンプルなパターンを作成してください\n\
+    - 例文全体をマッチングしようとせず、核となる機能部分のみを検出\n\
+    - 条件部分（【使用条件】等）は無視し、実際の効果部分に注目\n\
+    - Rustの正規表現構文を使用\n\
+    - パターンにはr\"\"は含めず、正規表現のみを返す\n\n\
+    成功パターンの例：\n\
+    - トラッシュ回収: \"手札に加える\"\n\
+    - バニッシュ: \"バニッシュ\"\n\
+    - ドロー: \"ドロー\"\n\
+    - エナチャージ: \"エナチャージ\"\n\
+    - アサシン: \"【アサシン】\"\n\
+    - ガード: \"【ガード】\"\n\n\
+    これらの例のように、機能を表す核心的なキーワードや記号に焦点を当ててください。";
+
+/// JSONモードで応答させるときに追加する指示（Tool Callingの関数定義の代わり）
+const JSON_FORMAT_PROMPT: &str = "\n\n応答は次の形式のJSONオブジェクトのみとしてください：\n\
+    {\"patterns\": [\"正規表現\", ...], \"explanation\": \"パターンの選び方と動作の説明\", \"success\": true}";
+
+impl PatternPrompt {
+    fn user_prompt(&self) -> String {
+        format!(
+            "対象機能: {}\n検索キーワード: {}\n\n\
+            以下の例文を参考に、機能を検出するためのシンプルな正規表現パターンを生成してください：\n{}\n\n\
+            重要な指針：\n\
+            1. 例文から共通する核心的なキーワードを抽出してください\n\
+            2. 【使用条件】や複雑な条件文は無視し、実際の効果部分に焦点を当ててください\n\
+            3. 上記の成功例のように、機能を表す最小限のキーワードでパターンを作成してください\n\
+            4. 例: 「○○を手札に加える」なら「手札に加える」、「○○をバニッシュする」なら「バニッシュ」\n\
+            5. 能力名が【】で囲まれている場合は記号も含めてください（例：【アサシン】）\n\
+            6. 複数の表現がある場合は、最もシンプルで一般的なパターンを優先してください",
+            self.feature_context.join(", "),
+            self.keyword,
+            self.sentences
+                .iter()
+                .enumerate()
+                .map(|(i, s)| format!("{}. {}", i + 1, s))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+
+    fn messages(&self, system_suffix: &str) -> Vec<ChatCompletionRequestMessage> {
+        vec![
+            ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
+                content: ChatCompletionRequestSystemMessageContent::Text(format!(
+                    "{}{}",
+                    SYSTEM_PROMPT, system_suffix
+                )),
+                name: None,
+            }),
+            ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+                content: ChatCompletionRequestUserMessageContent::Text(self.user_prompt()),
+                name: None,
+            }),
+        ]
+    }
+}
+
+/// パターンを生成するLLM
+#[async_trait::async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// ログ・表示用の名前
+    fn name(&self) -> &'static str;
+
+    async fn generate_patterns(
+        &self,
+        prompt: &PatternPrompt,
+    ) -> Result<RegexPatternResponse, LlmError>;
+}
+
+/// 設定に従ってプロバイダーを作る
+pub fn from_config(config: &OpenAiConfig) -> Arc<dyn LlmProvider> {
+    match config.provider {
+        LlmProviderKind::OpenAi => Arc::new(OpenAiProvider::new(config)),
+        LlmProviderKind::Local => Arc::new(LocalProvider::new(config)),
+        LlmProviderKind::Mock => Arc::new(MockProvider::default()),
+    }
+}
+
+fn client(config: &OpenAiConfig) -> Client<OpenAIConfig> {
+    let mut openai_config =
+        OpenAIConfig::new().with_api_key(config.api_key.clone().unwrap_or_default());
+    if let Some(base_url) = &config.base_url {
+        openai_config = openai_config.with_api_base(base_url);
+    }
+    Client::with_config(openai_config)
+}
+
+/// OpenAI API。Tool Callingで構造化された応答を受け取る
+pub struct OpenAiProvider {
+    client: Client<OpenAIConfig>,
+    model: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(config: &OpenAiConfig) -> Self {
+        Self {
+            client: client(config),
+            model: config.model.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn generate_patterns(
+        &self,
+        prompt: &PatternPrompt,
+    ) -> Result<RegexPatternResponse, LlmError> {
+        // Tool Calling用のfunction schema
+        let function = json!({
+            "type": "function",
+            "function": {
+                "name": "generate_regex_patterns",
+                "description": "Generate regex patterns for WIXOSS card feature detection",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "patterns": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Rust regex patterns (e.g. r\"手札に加える\")"
+                        },
+                        "explanation": {
+                            "type": "string",
+                            "description": "Reasoning for pattern choices and how they work"
+                        },
+                        "success": {
+                            "type": "boolean",
+                            "description": "Whether satisfactory patterns were generated"
+                        }
+                    },
+                    "required": ["patterns", "explanation", "success"]
+                }
+            }
+        });
+
+        let request = CreateChatCompletionRequest {
+            model: self.model.clone(),
+            messages: prompt.messages(""),
+            tools: Some(vec![serde_json::from_value(function)?]),
+            tool_choice: Some(ChatCompletionToolChoiceOption::Required),
+            ..Default::default()
+        };
+
+        let response = self.client.chat().create(request).await?;
+
+        if let Some(choice) = response.choices.first() {
+            if let Some(tool_calls) = &choice.message.tool_calls {
+                if let Some(tool_call) = tool_calls.first() {
+                    let regex_response: RegexPatternResponse =
+                        serde_json::from_str(&tool_call.function.arguments)?;
+                    return Ok(regex_response);
+                }
+            }
+        }
+
+        Err("No valid response from OpenAI".into())
+    }
+}
+
+/// OpenAI互換のローカルサーバー（llama.cppのserverなど）
+///
+/// Tool Callingに対応していないサーバーやモデルが多いため、JSONモードで応答させて本文を読む
+pub struct LocalProvider {
+    client: Client<OpenAIConfig>,
+    model: String,
+}
+
+impl LocalProvider {
+    pub fn new(config: &OpenAiConfig) -> Self {
+        Self {
+            client: client(config),
+            model: config.model.clone(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn generate_patterns(
+        &self,
+        prompt: &PatternPrompt,
+    ) -> Result<RegexPatternResponse, LlmError> {
+        let request = CreateChatCompletionRequest {
+            model: self.model.clone(),
+            messages: prompt.messages(JSON_FORMAT_PROMPT),
+            response_format: Some(ResponseFormat::JsonObject),
+            ..Default::default()
+        };
+
+        let response = self.client.chat().create(request).await?;
+        let content = response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.content.as_deref())
+            .ok_or("No valid response from local LLM")?;
+
+        parse_json_content(content)
+    }
+}
+
+/// JSONモードの本文を読む（コードブロックで囲んで返すモデルもあるので外す）
+fn parse_json_content(content: &str) -> Result<RegexPatternResponse, LlmError> {
+    let trimmed = content.trim();
+    let json = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(trimmed);
+    Ok(serde_json::from_str(json.trim())?)
+}
+
+/// APIを呼ばないプロバイダー（オフラインでの開発・テスト用）
+///
+/// 応答を指定しなければ、検索キーワードをエスケープしたものを唯一のパターンとして返す
+#[derive(Default)]
+pub struct MockProvider {
+    response: Option<RegexPatternResponse>,
+}
+
+impl MockProvider {
+    /// 常に `response` を返す
+    #[cfg(test)]
+    pub fn with_response(response: RegexPatternResponse) -> Self {
+        Self {
+            response: Some(response),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn generate_patterns(
+        &self,
+        prompt: &PatternPrompt,
+    ) -> Result<RegexPatternResponse, LlmError> {
+        Ok(self
+            .response
+            .clone()
+            .unwrap_or_else(|| RegexPatternResponse {
+                patterns: vec![regex::escape(&prompt.keyword)],
+                explanation: "モック: 検索キーワードをそのままパターンにしました".to_string(),
+                success: true,
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt() -> PatternPrompt {
+        PatternPrompt {
+            keyword: "【ガード】".to_string(),
+            feature_context: vec!["Guard(ガード)".to_string()],
+            sentences: vec!["【ガード】を持つシグニ".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_provider_is_deterministic() {
+        let mock = MockProvider::default();
+        let first = mock.generate_patterns(&prompt()).await.unwrap();
+        assert_eq!(first.patterns, vec!["【ガード】".to_string()]);
+        assert_eq!(first, mock.generate_patterns(&prompt()).await.unwrap());
+    }
+
+    #[test]
+    fn test_parse_json_content() {
+        let expected = RegexPatternResponse {
+            patterns: vec!["手札に加える".to_string()],
+            explanation: "説明".to_string(),
+            success: true,
+        };
+        let body = r#"{"patterns": ["手札に加える"], "explanation": "説明", "success": true}"#;
+        assert_eq!(parse_json_content(body).unwrap(), expected);
+        assert_eq!(
+            parse_json_content(&format!("```json\n{}\n```", body)).unwrap(),
+            expected
+        );
+        assert!(parse_json_content("パターンは「手札に加える」です").is_err());
+    }
+
+    #[test]
+    fn test_provider_from_config() {
+        let mut config = OpenAiConfig {
+            provider: LlmProviderKind::Mock,
+            api_key: None,
+            model: "gpt-4o-mini".to_string(),
+            base_url: None,
+        };
+        assert_eq!(from_config(&config).name(), "mock");
+
+        config.provider = LlmProviderKind::Local;
+        config.base_url = Some("http://localhost:8080/v1".to_string());
+        assert_eq!(from_config(&config).name(), "local");
+    }
+}
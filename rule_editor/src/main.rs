@@ -3,10 +3,13 @@
     windows_subsystem = "windows"
 )]
 
+mod evaluate;
+mod export;
+mod llm;
 mod models;
 mod web_server;
 
-use dotenvy::from_filename;
+use config::Config;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::{Pool, Postgres};
 use std::env;
@@ -17,7 +20,8 @@ use std::time::Duration;
 #[derive(Clone)]
 pub struct AppState {
     pub pool: Arc<Pool<Postgres>>,
-    pub openai_client: async_openai::Client<async_openai::config::OpenAIConfig>,
+    /// パターン生成に使うLLM（`LLM_PROVIDER`）
+    pub llm: Arc<dyn llm::LlmProvider>,
 }
 
 #[tokio::main]
@@ -44,37 +48,13 @@ async fn main() {
 }
 
 async fn run_export_mode() {
-    // .env ファイルの読み込み
-    let workspace_env = format!(
-        "{}/.env",
-        env::var("CARGO_WORKSPACE_DIR").unwrap_or_default()
-    );
-    let env_paths = [".env", "../.env", "../../.env", workspace_env.as_str()];
-
-    for path in &env_paths {
-        if std::path::Path::new(path).exists() {
-            from_filename(path).ok();
-            break;
-        }
-    }
+    let config = Config::load_or_exit();
 
     // データベース接続
-    let db_url = {
-        let host = env::var("DB_HOST").unwrap_or_else(|_| "192.168.56.10".to_string());
-        let port = env::var("DB_PORT").unwrap_or_else(|_| "5432".to_string());
-        let user = env::var("DB_USER").unwrap_or_else(|_| "wix".to_string());
-        let password = env::var("DB_PASSWORD").unwrap_or_else(|_| "wix".to_string());
-        let db_name = env::var("DB_NAME").unwrap_or_else(|_| "wixdb".to_string());
-        format!(
-            "postgres://{}:{}@{}:{}/{}",
-            user, password, host, port, db_name
-        )
-    };
-
     let pool = PgPoolOptions::new()
         .max_connections(5)
         .acquire_timeout(Duration::from_secs(5))
-        .connect(&format!("{db_url}?connect_timeout=5"))
+        .connect(&config.database.url_with_connect_timeout(5))
         .await
         .expect("Failed to connect to database");
 
@@ -83,12 +63,13 @@ async fn run_export_mode() {
     // パターンのエクスポート
     let state = AppState {
         pool: pool.clone(),
-        openai_client: async_openai::Client::new(),
+        llm: llm::from_config(&config.openai),
     };
 
     // アプリケーション状態を直接使用してエクスポート
     match export_patterns_direct(&state).await {
-        Ok(code) => {
+        Ok((code, report)) => {
+            println!("{}", report.summary());
             // ファイルに書き出し
             let output_path = "shared/feature/src/generated_patterns.rs";
             let mut file =
@@ -98,13 +79,18 @@ async fn run_export_mode() {
             println!("Successfully exported patterns to {}", output_path);
         }
         Err(e) => {
+            if let export::ExportError::Rejected(report) = &e {
+                eprintln!("{}", report.summary());
+            }
             eprintln!("Failed to export patterns: {}", e);
             std::process::exit(1);
         }
     }
 }
 
-async fn export_patterns_direct(state: &AppState) -> Result<String, String> {
+async fn export_patterns_direct(
+    state: &AppState,
+) -> Result<(String, export::ExportReport), export::ExportError> {
     let pool = &state.pool;
 
     let query = r#"
@@ -118,85 +104,39 @@ async fn export_patterns_direct(state: &AppState) -> Result<String, String> {
     let patterns = sqlx::query_as::<_, models::rule_pattern::RulePattern>(query)
         .fetch_all(pool.as_ref())
         .await
-        .map_err(|e| format!("Failed to fetch patterns: {}", e))?;
-
-    let mut output = String::from("// Auto-generated by rule_editor\n");
-    output.push_str("// このファイルは自動生成されています。直接編集しないでください。\n\n");
-
-    // detect_pattern! マクロを使った形式で出力
-    for pattern in patterns {
-        let features = pattern.features_as_vec();
-        let features_str = features
-            .iter()
-            .map(|f| format!("CardFeature::{}", f))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        output.push_str(&format!(
-            "detect_pattern![r\"{}\", {}], // {}\n",
-            pattern.pattern.replace("\"", "\\\""),
-            features_str,
-            pattern.keyword
-        ));
-    }
+        .map_err(|e| export::ExportError::Database(e.to_string()))?;
 
-    Ok(output)
+    // 手書きのパターンと突き合わせ、壊れたコードや矛盾するパターンは書き出さない
+    export::generate(&patterns, export::hand_written_patterns())
+        .map_err(export::ExportError::Rejected)
 }
 
 async fn run_web_mode() {
-    // .env ファイルの読み込み
-    let workspace_env = format!(
-        "{}/.env",
-        env::var("CARGO_WORKSPACE_DIR").unwrap_or_default()
-    );
-    let env_paths = [".env", "../.env", "../../.env", workspace_env.as_str()];
-
-    for path in &env_paths {
-        if std::path::Path::new(path).exists() {
-            from_filename(path).ok();
-            break;
-        }
-    }
+    let config = Config::load_or_exit();
 
     // データベース接続
-    let db_url = {
-        let host = env::var("DB_HOST").unwrap_or_else(|_| "192.168.56.10".to_string());
-        let port = env::var("DB_PORT").unwrap_or_else(|_| "5432".to_string());
-        let user = env::var("DB_USER").unwrap_or_else(|_| "wix".to_string());
-        let password = env::var("DB_PASSWORD").unwrap_or_else(|_| "wix".to_string());
-        let db_name = env::var("DB_NAME").unwrap_or_else(|_| "wixdb".to_string());
-        format!(
-            "postgres://{}:{}@{}:{}/{}",
-            user, password, host, port, db_name
-        )
-    };
-
     let pool = PgPoolOptions::new()
         .max_connections(5)
         .acquire_timeout(Duration::from_secs(5))
-        .connect(&format!("{db_url}?connect_timeout=5"))
+        .connect(&config.database.url_with_connect_timeout(5))
         .await
         .expect("Failed to connect to database");
 
     let pool = Arc::new(pool);
 
-    // OpenAI クライアントの初期化
-    let openai_config = async_openai::config::OpenAIConfig::new()
-        .with_api_key(env::var("OPENAI_API_KEY").unwrap_or_default());
-    let openai_client = async_openai::Client::with_config(openai_config);
+    let llm = llm::from_config(&config.openai);
+    println!("LLM provider: {} (model: {})", llm.name(), config.openai.model);
 
-    let app_state = AppState {
-        pool,
-        openai_client,
-    };
+    let app_state = AppState { pool, llm };
 
     let app = web_server::create_router(app_state);
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3030")
+    let port = config.server.rule_editor_port;
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
         .await
-        .expect("Failed to bind to port 3030");
+        .unwrap_or_else(|e| panic!("Failed to bind to port {}: {}", port, e));
 
-    println!("🚀 Rule Editor web server starting at http://localhost:3030");
+    println!("🚀 Rule Editor web server starting at http://localhost:{}", port);
     println!("Use Ctrl+C to stop the server");
 
     axum::serve(listener, app)
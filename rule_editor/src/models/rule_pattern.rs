@@ -1,14 +1,18 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct RulePattern {
     pub id: i32,
     pub keyword: String,
     pub pattern: String,
+    #[schema(value_type = Vec<String>)]
     pub features: serde_json::Value,
+    #[schema(value_type = Vec<String>)]
     pub positive_examples: serde_json::Value,
+    #[schema(value_type = Vec<String>)]
     pub negative_examples: serde_json::Value,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -1,34 +1,30 @@
-use async_openai::{
-    config::OpenAIConfig,
-    types::{
-        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
-        ChatCompletionRequestSystemMessageContent, ChatCompletionRequestUserMessage,
-        ChatCompletionRequestUserMessageContent, ChatCompletionToolChoiceOption,
-        CreateChatCompletionRequest,
-    },
-    Client,
-};
+use api_routes::ApiRoute;
 use axum::{
     extract::{Query, State},
-    http::{HeaderValue, Method},
+    http::{HeaderValue, Method, StatusCode},
     response::{Html, Json},
-    routing::{get, post},
     Router,
 };
 use feature::feature::{export_features, ExportedCardFeature};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use std::collections::HashMap;
 use tower_http::cors::CorsLayer;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use crate::{
+    evaluate::{evaluate, resolve_feature, CorpusCard, EvaluatePatternRequest, PatternEvaluation},
+    export::ExportError,
+    llm::PatternPrompt,
+    models::rule_pattern::RulePattern,
+    AppState,
+};
 
-use crate::{models::rule_pattern::RulePattern, AppState};
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct SearchQuery {
     keyword: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SentenceResult {
     pub id: String,
     pub text: String,
@@ -36,7 +32,7 @@ pub struct SentenceResult {
     pub card_name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct PatternRequest {
     pub keyword: String,
     pub positive_examples: Vec<String>,
@@ -44,26 +40,36 @@ pub struct PatternRequest {
     pub features: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PatternSuggestion {
+    /// 第一候補（`patterns` の先頭）
     pub pattern: String,
+    /// AIが提案した候補（コンパイルできないものは除く）
+    pub patterns: Vec<String>,
     pub explanation: String,
     pub features: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct FeatureResponse {
-    pub features_by_tag: HashMap<String, Vec<ExportedCardFeature>>,
+impl PatternSuggestion {
+    /// 候補が1つだけの提案
+    fn single(pattern: String, explanation: String, features: Vec<String>) -> Self {
+        Self {
+            pattern: pattern.clone(),
+            patterns: vec![pattern],
+            explanation,
+            features,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct RegexPatternResponse {
-    patterns: Vec<String>,
-    explanation: String,
-    success: bool,
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FeatureResponse {
+    /// タグ名ごとのフィーチャ一覧
+    #[schema(value_type = Object)]
+    pub features_by_tag: HashMap<String, Vec<ExportedCardFeature>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct SavePatternRequest {
     pub keyword: String,
     pub pattern: String,
@@ -72,15 +78,36 @@ pub struct SavePatternRequest {
     pub negative_examples: Vec<String>,
 }
 
+/// API定義（`/api/openapi.json` で配信）
+#[derive(OpenApi)]
+#[openapi(paths(
+    search_rawcards,
+    generate_pattern,
+    get_patterns,
+    save_pattern,
+    evaluate_pattern,
+    export_patterns,
+    get_features,
+))]
+struct ApiDoc;
+
+/// ルート表（UIと仕様自体の配信はOpenAPI定義に載せない）
+fn routes() -> Vec<ApiRoute<AppState>> {
+    vec![
+        ApiRoute::get("/", serve_index).undocumented(),
+        ApiRoute::get("/api/search", search_rawcards),
+        ApiRoute::post("/api/generate-pattern", generate_pattern),
+        ApiRoute::get("/api/patterns", get_patterns),
+        ApiRoute::post("/api/patterns", save_pattern),
+        ApiRoute::post("/api/patterns/evaluate", evaluate_pattern),
+        ApiRoute::post("/api/export", export_patterns),
+        ApiRoute::get("/api/features", get_features),
+        ApiRoute::get("/api/openapi.json", openapi_json).undocumented(),
+    ]
+}
+
 pub fn create_router(app_state: AppState) -> Router {
-    Router::new()
-        .route("/", get(serve_index))
-        .route("/api/search", get(search_rawcards))
-        .route("/api/generate-pattern", post(generate_pattern))
-        .route("/api/patterns", get(get_patterns))
-        .route("/api/patterns", post(save_pattern))
-        .route("/api/export", post(export_patterns))
-        .route("/api/features", get(get_features))
+    api_routes::router(routes())
         .layer(
             CorsLayer::new()
                 .allow_origin("*".parse::<HeaderValue>().unwrap())
@@ -94,6 +121,12 @@ async fn serve_index() -> Html<String> {
     Html(include_str!("../ui/dist/index.html").to_string())
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    params(SearchQuery),
+    responses((status = 200, description = "キーワードを含むスキルテキスト", body = Vec<SentenceResult>))
+)]
 async fn search_rawcards(
     Query(query): Query<SearchQuery>,
     State(app_state): State<AppState>,
@@ -130,6 +163,12 @@ async fn search_rawcards(
     Json(results)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/generate-pattern",
+    request_body = PatternRequest,
+    responses((status = 200, description = "正規表現パターンの提案", body = PatternSuggestion))
+)]
 async fn generate_pattern(
     State(app_state): State<AppState>,
     Json(request): Json<PatternRequest>,
@@ -164,22 +203,10 @@ async fn generate_pattern(
         }
     }
 
-    // CardFeatureの日本語ラベルから内部コードを逆引きする処理
     let feature_context = request
         .features
         .iter()
-        .map(|feature_label| {
-            let internal_code = match feature_label.as_str() {
-                "トラッシュ回収" => "Salvage",
-                "バニッシュ" => "Banish",
-                "ドロー" => "Draw",
-                "エナチャージ" => "Charge",
-                "アサシン" => "Assassin",
-                "ガード" => "Guard",
-                _ => "Unknown",
-            };
-            format!("{}({})", internal_code, feature_label)
-        })
+        .map(|feature| feature_context(feature))
         .collect::<Vec<String>>();
 
     println!("=== 前処理後（AI APIに送信予定のデータ） ===");
@@ -200,164 +227,89 @@ async fn generate_pattern(
             request.keyword
         );
 
-        return Json(PatternSuggestion {
-            pattern: request.keyword.clone(),
-            explanation:
-                "検索キーワードを含む例文が見つからないため、シンプルなパターンを返しました。"
-                    .to_string(),
-            features: request.features,
-        });
+        return Json(PatternSuggestion::single(
+            regex::escape(&request.keyword),
+            "検索キーワードを含む例文が見つからないため、シンプルなパターンを返しました。"
+                .to_string(),
+            request.features,
+        ));
     }
 
-    // OpenAI APIを呼び出し
-    match call_openai_for_pattern(
-        &app_state.openai_client,
-        &request.keyword,
-        &feature_context,
-        &filtered_sentences,
-    )
-    .await
-    {
+    // 設定したLLMを呼び出し
+    let prompt = PatternPrompt {
+        keyword: request.keyword.clone(),
+        feature_context,
+        sentences: filtered_sentences,
+    };
+    match app_state.llm.generate_patterns(&prompt).await {
         Ok(ai_response) => {
-            println!("=== OpenAI API Response ===");
+            println!("=== LLM Response ({}) ===", app_state.llm.name());
             println!("Success: {}", ai_response.success);
             println!("Patterns: {:?}", ai_response.patterns);
             println!("Explanation: {}", ai_response.explanation);
             println!("============================");
 
-            if ai_response.success && !ai_response.patterns.is_empty() {
-                // 複数パターンがある場合は最初のパターンを使用
-                // TODO: 複数パターンに対応した PatternSuggestion の設計変更を検討
+            let patterns = valid_patterns(ai_response.patterns);
+            if ai_response.success && !patterns.is_empty() {
                 Json(PatternSuggestion {
-                    pattern: ai_response.patterns[0].clone(),
+                    pattern: patterns[0].clone(),
+                    patterns,
                     explanation: ai_response.explanation,
                     features: request.features,
                 })
             } else {
-                Json(PatternSuggestion {
-                    pattern: request.keyword.clone(),
-                    explanation:
-                        "AIによるパターン生成に失敗しました。シンプルなパターンを返します。"
-                            .to_string(),
-                    features: request.features,
-                })
+                Json(PatternSuggestion::single(
+                    regex::escape(&request.keyword),
+                    "AIによるパターン生成に失敗しました。シンプルなパターンを返します。"
+                        .to_string(),
+                    request.features,
+                ))
             }
         }
         Err(e) => {
-            println!("OpenAI API Error: {}", e);
-            Json(PatternSuggestion {
-                pattern: request.keyword.clone(),
-                explanation: format!(
+            println!("LLM Error ({}): {}", app_state.llm.name(), e);
+            Json(PatternSuggestion::single(
+                regex::escape(&request.keyword),
+                format!(
                     "APIエラーのため、シンプルなパターンを返します。エラー: {}",
                     e
                 ),
-                features: request.features,
-            })
+                request.features,
+            ))
         }
     }
 }
 
-async fn call_openai_for_pattern(
-    client: &Client<OpenAIConfig>,
-    keyword: &str,
-    feature_context: &[String],
-    filtered_sentences: &[String],
-) -> Result<RegexPatternResponse, Box<dyn std::error::Error + Send + Sync>> {
-    // Tool Calling用のfunction schema
-    let function = json!({
-        "type": "function",
-        "function": {
-            "name": "generate_regex_patterns",
-            "description": "Generate regex patterns for WIXOSS card feature detection",
-            "parameters": {
-                "type": "object",
-                "properties": {
-                    "patterns": {
-                        "type": "array",
-                        "items": {"type": "string"},
-                        "description": "Rust regex patterns (e.g. r\"手札に加える\")"
-                    },
-                    "explanation": {
-                        "type": "string",
-                        "description": "Reasoning for pattern choices and how they work"
-                    },
-                    "success": {
-                        "type": "boolean",
-                        "description": "Whether satisfactory patterns were generated"
-                    }
-                },
-                "required": ["patterns", "explanation", "success"]
-            }
-        }
-    });
-
-    let system_prompt = "あなたはWIXOSSトレーディングカードゲームのテキスト解析専門家です。\
-    カードのスキルテキストから特定の機能を検出するための正規表現パターンを生成してください。\n\n\
-    重要な原則：\n\
-    - 既存の成功例を参考にシンプルなパターンを作成してください\n\
-    - 例文全体をマッチングしようとせず、核となる機能部分のみを検出\n\
-    - 条件部分（【使用条件】等）は無視し、実際の効果部分に注目\n\
-    - Rustの正規表現構文を使用\n\
-    - パターンにはr\"\"は含めず、正規表現のみを返す\n\n\
-    成功パターンの例：\n\
-    - トラッシュ回収: \"手札に加える\"\n\
-    - バニッシュ: \"バニッシュ\"\n\
-    - ドロー: \"ドロー\"\n\
-    - エナチャージ: \"エナチャージ\"\n\
-    - アサシン: \"【アサシン】\"\n\
-    - ガード: \"【ガード】\"\n\n\
-    これらの例のように、機能を表す核心的なキーワードや記号に焦点を当ててください。";
-
-    let user_prompt = format!(
-        "対象機能: {}\n検索キーワード: {}\n\n\
-        以下の例文を参考に、機能を検出するためのシンプルな正規表現パターンを生成してください：\n{}\n\n\
-        重要な指針：\n\
-        1. 例文から共通する核心的なキーワードを抽出してください\n\
-        2. 【使用条件】や複雑な条件文は無視し、実際の効果部分に焦点を当ててください\n\
-        3. 上記の成功例のように、機能を表す最小限のキーワードでパターンを作成してください\n\
-        4. 例: 「○○を手札に加える」なら「手札に加える」、「○○をバニッシュする」なら「バニッシュ」\n\
-        5. 能力名が【】で囲まれている場合は記号も含めてください（例：【アサシン】）\n\
-        6. 複数の表現がある場合は、最もシンプルで一般的なパターンを優先してください",
-        feature_context.join(", "),
-        keyword,
-        filtered_sentences.iter().enumerate()
-            .map(|(i, s)| format!("{}. {}", i + 1, s))
-            .collect::<Vec<String>>()
-            .join("\n")
-    );
-
-    let request = CreateChatCompletionRequest {
-        model: "gpt-4o-mini".to_string(),
-        messages: vec![
-            ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
-                content: ChatCompletionRequestSystemMessageContent::Text(system_prompt.to_string()),
-                name: None,
-            }),
-            ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
-                content: ChatCompletionRequestUserMessageContent::Text(user_prompt),
-                name: None,
-            }),
-        ],
-        tools: Some(vec![serde_json::from_value(function)?]),
-        tool_choice: Some(ChatCompletionToolChoiceOption::Required),
-        ..Default::default()
-    };
-
-    let response = client.chat().create(request).await?;
+/// LLMに渡すフィーチャーの説明（`Salvage(トラッシュ回収)` の形式）
+///
+/// UIからは表示名が届くが、列挙子名も受け付ける。定義にないものは `Unknown(...)` とする
+fn feature_context(feature: &str) -> String {
+    match resolve_feature(feature) {
+        Some(feature) => format!("{}({})", feature.name(), feature),
+        None => format!("Unknown({})", feature),
+    }
+}
 
-    if let Some(choice) = response.choices.first() {
-        if let Some(tool_calls) = &choice.message.tool_calls {
-            if let Some(tool_call) = tool_calls.first() {
-                let regex_response: RegexPatternResponse =
-                    serde_json::from_str(&tool_call.function.arguments)?;
-                return Ok(regex_response);
-            }
+/// コンパイルできない・重複したパターンを除く（順序は保つ）
+fn valid_patterns(patterns: Vec<String>) -> Vec<String> {
+    let mut valid: Vec<String> = Vec::new();
+    for pattern in patterns {
+        if valid.contains(&pattern) {
+            continue;
+        }
+        match regex::Regex::new(&pattern) {
+            Ok(_) => valid.push(pattern),
+            Err(e) => println!("⚠️  不正なパターンを除外しました: {} ({})", pattern, e),
         }
     }
-
-    Err("No valid response from OpenAI".into())
+    valid
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/patterns",
+    responses((status = 200, description = "有効なパターン", body = Vec<RulePattern>))
+)]
 async fn get_patterns(State(app_state): State<AppState>) -> Json<Vec<RulePattern>> {
     let pool = &app_state.pool;
 
@@ -377,6 +329,12 @@ async fn get_patterns(State(app_state): State<AppState>) -> Json<Vec<RulePattern
     Json(patterns)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/patterns",
+    request_body = SavePatternRequest,
+    responses((status = 200, description = "`{success, id}` または `{success, error}`", body = Object))
+)]
 async fn save_pattern(
     State(app_state): State<AppState>,
     Json(request): Json<SavePatternRequest>,
@@ -408,31 +366,211 @@ async fn save_pattern(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/patterns/evaluate",
+    request_body = EvaluatePatternRequest,
+    responses(
+        (status = 200, description = "例の検証結果と全カードへの適用結果", body = PatternEvaluation),
+        (status = 400, description = "正規表現が不正（`{success, error}`）", body = Object),
+        (status = 500, description = "データベースエラー（`{success, error}`）", body = Object)
+    )
+)]
+async fn evaluate_pattern(
+    State(app_state): State<AppState>,
+    Json(request): Json<EvaluatePatternRequest>,
+) -> Result<Json<PatternEvaluation>, (StatusCode, Json<serde_json::Value>)> {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(serde_json::json!({"success": false, "error": message})),
+        )
+    };
+
+    let regex = regex::Regex::new(&request.pattern).map_err(|e| {
+        error(
+            StatusCode::BAD_REQUEST,
+            format!("正規表現が不正です: {}", e),
+        )
+    })?;
+
+    // 確認済み = フィーチャーの手動修正があるか、確認キューで承認された読み
+    let query = r#"
+        SELECT r.card_number, r.name, r.skill_text,
+               c.pronunciation, c.feature_bits1, c.feature_bits2,
+               EXISTS (
+                   SELECT 1 FROM wix_card_feature_override o
+                   WHERE o.pronunciation = c.pronunciation
+               ) AS overridden,
+               EXISTS (
+                   SELECT 1 FROM wix_card_review_item i
+                   WHERE i.pronunciation = c.pronunciation AND i.status = 'approved'
+               ) AS approved
+        FROM wix_rawcard r
+        LEFT JOIN wix_card c ON c.code = r.card_number
+        ORDER BY r.card_number
+    "#;
+    let cards = sqlx::query_as::<_, CorpusCard>(query)
+        .fetch_all(app_state.pool.as_ref())
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // 全カードへの正規表現の適用は重いのでブロッキングスレッドで行う
+    let evaluation = tokio::task::spawn_blocking(move || evaluate(&regex, &request, &cards))
+        .await
+        .map_err(|e| {
+            error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("評価に失敗しました: {}", e),
+            )
+        })?;
+    Ok(Json(evaluation))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/export",
+    responses((status = 200, description = "`{success, code, path, report}` または `{success, error, report}`（検査でエラーがあれば書き出さない）", body = Object))
+)]
 async fn export_patterns(State(app_state): State<AppState>) -> Json<serde_json::Value> {
     match crate::export_patterns_direct(&app_state).await {
-        Ok(code) => {
+        Ok((code, report)) => {
             // ファイルに書き出し
             let output_path = "shared/feature/src/generated_patterns.rs";
             match std::fs::write(output_path, &code) {
                 Ok(_) => Json(serde_json::json!({
                     "success": true,
                     "code": code,
-                    "path": output_path
+                    "path": output_path,
+                    "report": report
                 })),
                 Err(e) => Json(serde_json::json!({
                     "success": false,
-                    "error": format!("Failed to write file: {}", e)
+                    "error": format!("Failed to write file: {}", e),
+                    "report": report
                 })),
             }
         }
-        Err(e) => Json(serde_json::json!({
-            "success": false,
-            "error": e
-        })),
+        Err(e) => {
+            let error = e.to_string();
+            let report = match e {
+                ExportError::Rejected(report) => Some(report),
+                ExportError::Database(_) => None,
+            };
+            Json(serde_json::json!({
+                "success": false,
+                "error": error,
+                "report": report
+            }))
+        }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/features",
+    responses((status = 200, description = "タグ別のフィーチャ一覧", body = FeatureResponse))
+)]
 async fn get_features() -> Json<FeatureResponse> {
     let features_by_tag = export_features();
     Json(FeatureResponse { features_by_tag })
 }
+
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{MockProvider, RegexPatternResponse};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    /// 仕様を変えたら `UPDATE_OPENAPI=1 cargo test -p rule_editor` でスナップショットを更新する
+    #[test]
+    fn test_openapi_snapshot_is_up_to_date() {
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SNAPSHOT, &spec).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            committed == spec,
+            "openapi.json がハンドラ定義と一致しません。UPDATE_OPENAPI=1 で再生成してください"
+        );
+    }
+
+    /// ルート表とOpenAPI定義が一致することを確認する（UIと仕様自体の配信は除く）
+    #[test]
+    fn test_routes_match_openapi() {
+        assert_eq!(
+            api_routes::mismatches(&routes(), &ApiDoc::openapi()),
+            Vec::<String>::new()
+        );
+    }
+
+    /// LLMの応答の先頭のパターンを提案として返す（モックでAPIを呼ばずに確認する）
+    #[tokio::test]
+    async fn test_generate_pattern_uses_configured_provider() {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        let app = create_router(AppState {
+            pool: Arc::new(pool),
+            llm: Arc::new(MockProvider::with_response(RegexPatternResponse {
+                patterns: vec![
+                    "手札に加える".to_string(),
+                    "(手札に".to_string(),
+                    "手札に加え".to_string(),
+                    "手札に加える".to_string(),
+                ],
+                explanation: "モック".to_string(),
+                success: true,
+            })),
+        });
+
+        let body = serde_json::json!({
+            "keyword": "手札に加える",
+            "positive_examples": ["カード1枚を手札に加える。"],
+            "negative_examples": [],
+            "features": ["トラッシュ回収"],
+        });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/generate-pattern")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let suggestion: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(suggestion["pattern"], "手札に加える");
+        assert_eq!(
+            suggestion["patterns"],
+            serde_json::json!(["手札に加える", "手札に加え"])
+        );
+        assert_eq!(suggestion["explanation"], "モック");
+    }
+
+    #[test]
+    fn test_feature_context_covers_all_features() {
+        assert_eq!(feature_context("トラッシュ回収"), "Salvage(トラッシュ回収)");
+        assert_eq!(feature_context("Salvage"), "Salvage(トラッシュ回収)");
+        assert_eq!(feature_context("存在しない"), "Unknown(存在しない)");
+        for feature in feature::feature::CardFeature::create_vec() {
+            assert!(!feature_context(&feature.to_string()).starts_with("Unknown"));
+        }
+    }
+}
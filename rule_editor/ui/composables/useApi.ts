@@ -15,6 +15,7 @@ export interface PatternRequest {
 
 export interface PatternSuggestion {
   pattern: string
+  patterns: string[]
   explanation: string
   features: string[]
 }
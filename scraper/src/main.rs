@@ -1,9 +1,10 @@
 // スクレイピング、キャッシング、HTML解析、データベース保存を行うメインクレート
 
-use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use clap::Parser;
+use config::Config;
 use tokio::sync::Mutex;
 
 use webapp::analyze::{cache_product_index, collect_card_detail_links, try_mkdir, ProductType};
@@ -29,9 +30,9 @@ struct Args {
     /// 製品タイプがpr以外の場合は必須
     code: Option<String>,
 
-    /// キャッシュディレクトリ（デフォルト: ./text_cache）
-    #[arg(long, default_value = "./text_cache")]
-    cache_dir: String,
+    /// キャッシュディレクトリ（省略時は設定の CACHE_DIR、既定 ./text_cache）
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
 
     /// ダウンロード間隔の最小値（ミリ秒、デフォルト: 1000）
     #[arg(long, default_value = "1000")]
@@ -53,6 +54,7 @@ struct Args {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
+    let config = Config::load()?;
 
     println!("WIXOSS カード情報スクレイピングツール");
     println!("製品タイプ: {}", args.product_type);
@@ -64,10 +66,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // キャッシュディレクトリを作成
-    let cache_path = Path::new(&args.cache_dir);
-    try_mkdir(cache_path)
+    let cache_path = args
+        .cache_dir
+        .or_else(|| config.cache.dir.clone())
+        .unwrap_or_else(|| PathBuf::from("./text_cache"));
+    try_mkdir(&cache_path)
         .map_err(|e| format!("キャッシュディレクトリの作成に失敗しました: {}", e))?;
-    println!("キャッシュディレクトリ: {}", args.cache_dir);
+    println!("キャッシュディレクトリ: {}", cache_path.display());
 
     // 製品タイプを判定
     let product_type = match args.product_type.to_ascii_lowercase().as_str() {
@@ -97,7 +102,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // スクレイピングサービスを初期化
     let scraping_service = ScrapingService::new(
-        cache_path.to_path_buf(),
+        cache_path,
         args.min_delay,
         args.max_delay,
         args.concurrency,
@@ -116,7 +121,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // データベース接続プールを作成
     println!("データベースに接続中...");
-    let pool = Arc::new(create_database_pool().await?);
+    let pool = Arc::new(create_database_pool(&config).await?);
 
     // リポジトリを初期化
     let card_type_repo = Arc::new(Mutex::new(CardTypeRepository::new(pool.clone())));
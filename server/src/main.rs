@@ -1,104 +1,402 @@
-use axum::Router;
-use dotenvy::from_filename;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::{Pool, Postgres};
-use std::env;
+use api_routes::ApiRoute;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{Request, StatusCode};
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::response::Response;
+use axum::{Json, Router};
+use config::Config;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{ConnectOptions, Pool, Postgres};
+use std::str::FromStr;
+use tower::ServiceBuilder;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::services::ServeDir;
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tower_http::LatencyUnit;
+use tracing::{Level, Span};
 
 #[allow(unused_imports)]
-use axum::routing::get;
+use axum::routing::{get, post};
 use std::sync::Arc;
 use std::time::Duration;
+use utoipa::OpenApi;
+use webapp::auth::{require_api_key, ApiKeyAuth};
+use webapp::metrics::track_http_metrics;
+use webapp::readiness::{check_readiness, ReadinessReport};
+use webapp::repositories::CardRepository;
 use webapp::routers::{
-    admin_process::create_admin_portal_router, card_router::create_card_router,
-    product_router::create_product_router,
+    admin_process::{create_admin_portal_router, DjangoProcess, DjangoProcessState},
+    card_router::{card_routes, CardApiDoc},
+    product_router::{product_routes, ProductApiDoc},
 };
+use webapp::error::ApiErrorBody;
+use webapp::snapshot::SnapshotCache;
 use webapp::state::AppState;
 
+/// 公開APIのOpenAPI定義（`/api/openapi.json` で配信）
+#[derive(OpenApi)]
+#[openapi(
+    paths(healthz, readyz, metrics, reload_cache),
+    nest(
+        (path = "/api/card", api = CardApiDoc),
+        (path = "/api/product", api = ProductApiDoc)
+    )
+)]
+struct ApiDoc;
+
 #[tokio::main]
 async fn main() -> Result<(), sqlx::Error> {
-    let workspace_env = format!(
-        "{}/.env",
-        env::var("CARGO_WORKSPACE_DIR").unwrap_or_default()
+    let config = Config::load_or_exit();
+
+    webapp::logging::init_tracing(
+        "server=info,webapp=info,tower_http=info,sqlx=warn",
+        config.log.format,
     );
-    let env_paths = [
-        ".env",                 // カレントディレクトリ
-        "../.env",              // 一つ上のディレクトリ
-        "../../.env",           // 二つ上のディレクトリ（nested crateの場合）
-        workspace_env.as_str(), // CARGO_WORKSPACE_DIRが設定されている場合
-    ];
-    for path in &env_paths {
-        if std::path::Path::new(path).exists() {
-            from_filename(path).ok();
-            break;
-        }
-    }
+    webapp::metrics::prometheus_handle();
 
-    let web_port: u16 = env::var("WEB_PORT")
-        .unwrap_or("8000".to_string())
-        .parse()
-        .unwrap();
-    let django_admin_port: u16 = env::var("DJANGO_ADMIN_PORT")
-        .unwrap_or("8200".to_string())
-        .parse()
-        .unwrap();
-
-    let db_url = {
-        let host = env::var("DB_HOST").expect("DB_HOST not found in .env");
-        let port = env::var("DB_PORT").expect("DB_PORT not found in .env");
-        let user = env::var("DB_USER").expect("DB_USER not found in .env");
-        let password = env::var("DB_PASSWORD").expect("DB_PASSWORD not found in .env");
-        let db_name = env::var("DB_NAME").expect("DB_NAME not found in .env");
-        format!(
-            "postgres://{}:{}@{}:{}/{}",
-            user, password, host, port, db_name
-        )
-    };
+    let web_port = config.server.web_port;
+    let django_admin_port = config.admin.django_port;
+
+    // 全クエリはdebug、DB_SLOW_QUERY_MS（既定500ms）を超えたクエリはwarnで所要時間を出力する
+    let connect_options = PgConnectOptions::from_str(&config.database.url_with_connect_timeout(5))?
+        .log_statements(log::LevelFilter::Debug)
+        .log_slow_statements(
+            log::LevelFilter::Warn,
+            Duration::from_millis(config.database.slow_query_ms),
+        );
 
     let pool: Pool<Postgres> = PgPoolOptions::new()
         .max_connections(5)
         .acquire_timeout(Duration::from_secs(5))
-        .connect(format!("{db_url}?connect_timeout=5").as_str())
+        .connect_with(connect_options)
         .await
         .expect("Failed to connect to database");
 
     let pool = Arc::new(pool);
 
-    let a_routers = create_admin_portal_router(django_admin_port, web_port);
-    let card_router = create_card_router(pool.clone());
-    let product_router = create_product_router(pool.clone());
-    let api_router = Router::new()
-        .nest("/card/", card_router)
-        .nest("/product/", product_router);
+    let django_process = DjangoProcess::new();
+    let a_routers =
+        create_admin_portal_router(django_admin_port, web_port, django_process.clone());
 
     let app_state = AppState {
+        auth: ApiKeyAuth::from_config(pool.clone(), &config.admin),
         db_pool: pool,
         django_admin_port,
+        snapshots: SnapshotCache::new(),
+        django_process,
+        datapack_version_file: config.server.datapack_version_file.clone(),
     };
 
-    let app = Router::new()
+    let admin_operation = a_routers
+        .0
+        .route_layer(from_fn_with_state(app_state.auth.clone(), require_api_key));
+
+    let app = api_routes(&app_state)
         // .route("/hello", get(hello_handler))
-        // .nest("/card/", card_router)
-        // .nest("/product/", product_router)
-        .route("/healthz", get(|| async { "OK" }))
-        .nest("/api/", api_router)
-        .nest("/admin_operation/", a_routers.0)
+        .nest("/admin_operation/", admin_operation)
         .nest("/admin_proxy/", a_routers.1)
         .nest("/a_static/", a_routers.2)
         .fallback_service(ServeDir::new("../front/dist"))
+        .layer(from_fn(track_http_metrics))
         .with_state(app_state);
+    let app = with_request_tracing(app);
 
     let web_addr = format!("0.0.0.0:{}", web_port);
     let listener = tokio::net::TcpListener::bind(&web_addr)
         .await
         .expect("Failed to bind port");
-    println!("Server is running on http://{}", web_addr);
+    tracing::info!("Server is running on http://{}", web_addr);
     axum::serve(listener, app).await?;
 
     Ok(())
 }
 
+/// リクエストIDの付与（`x-request-id`、なければUUIDを生成）とリクエスト単位のトレース
+///
+/// リクエスト中のログはすべて `request` スパン（method・uri・request_id）の下に出力され、
+/// レスポンス時に所要時間（ミリ秒）が記録される
+fn with_request_tracing(app: Router) -> Router {
+    app.layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(request_span)
+                    .on_response(
+                        DefaultOnResponse::new()
+                            .level(Level::INFO)
+                            .latency_unit(LatencyUnit::Millis),
+                    ),
+            )
+            .layer(PropagateRequestIdLayer::x_request_id()),
+    )
+}
+
+fn request_span(request: &Request<Body>) -> Span {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or("-");
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id = %request_id,
+    )
+}
+
+/// ヘルスチェックとJSON APIのルート表（OpenAPI定義との突き合わせにも使う）
+fn route_table(state: &AppState) -> Vec<ApiRoute<AppState>> {
+    let card_routes = card_routes(state.db_pool.clone(), state.snapshots.clone())
+        .into_iter()
+        .map(|route| route.nest("/api/card"));
+    let product_routes = product_routes(state.db_pool.clone(), state.snapshots.clone())
+        .into_iter()
+        .map(|route| route.nest("/api/product"));
+    let auth = state.auth.clone();
+
+    vec![
+        ApiRoute::get("/healthz", healthz),
+        ApiRoute::get("/readyz", readyz),
+        ApiRoute::get("/metrics", metrics),
+        ApiRoute::post("/api/cache/reload", reload_cache)
+            .map(|route| route.route_layer(from_fn_with_state(auth, require_api_key))),
+        ApiRoute::get("/api/openapi.json", openapi_json).undocumented(),
+    ]
+    .into_iter()
+    .chain(card_routes)
+    .chain(product_routes)
+    .collect()
+}
+
+/// ヘルスチェックとJSON APIのルート
+fn api_routes(state: &AppState) -> Router<AppState> {
+    ::api_routes::router(route_table(state))
+}
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = 200, description = "稼働中", body = String, content_type = "text/plain"))
+)]
+async fn healthz() -> &'static str {
+    "OK"
+}
+
+/// デプロイ後にトラフィックを流してよいかの判定
+///
+/// DB接続、wix_cardが空でないこと、全カードの商品・カード種類が存在すること、
+/// datapack（`cargo make static` の出力）がDBの内容と一致することを確認し、
+/// 1つでも失敗すれば503を返す
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "すべてのチェックが通った", body = ReadinessReport),
+        (status = 503, description = "失敗したチェックがある", body = ReadinessReport)
+    )
+)]
+async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<ReadinessReport>) {
+    let report = check_readiness(&state.db_pool, &state.datapack_version_file).await;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
+
+/// Prometheus形式のメトリクス
+///
+/// HTTPリクエスト数・所要時間に加え、DBプールの使用状況、Django管理サーバーの状態、
+/// カード件数とデータバージョンをスクレイプのたびに更新して返す
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "Prometheusのテキスト形式", body = String, content_type = "text/plain"))
+)]
+async fn metrics(State(state): State<AppState>) -> Response {
+    webapp::metrics::record_pool(&state.db_pool);
+
+    let django_state = state.django_process.state().await;
+    metrics::gauge!("django_process_up")
+        .set((django_state == DjangoProcessState::Running) as u8 as f64);
+    metrics::gauge!("django_process_exited")
+        .set((django_state == DjangoProcessState::Exited) as u8 as f64);
+
+    let card_repo = CardRepository::new(state.db_pool.clone());
+    match tokio::try_join!(card_repo.count(), card_repo.data_version()) {
+        Ok((count, version)) => {
+            metrics::gauge!("db_up").set(1.0);
+            metrics::gauge!("wixdb_cards").set(count as f64);
+            metrics::gauge!("wixdb_card_data_version").set(version as f64);
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "メトリクス用のカード件数を取得できません");
+            metrics::gauge!("db_up").set(0.0);
+        }
+    }
+
+    webapp::metrics::render()
+}
+
+/// 一覧キャッシュを破棄して次のリクエストで作り直させる
+///
+/// wix_card・wix_productへの書き込みはデータバージョンで自動的に反映されるため、
+/// それ以外のテーブルだけを直接書き換えたときに呼ぶ。
+/// 編集権限のAPIキーが必要
+#[utoipa::path(
+    post,
+    path = "/api/cache/reload",
+    tag = "cache",
+    responses(
+        (status = 204, description = "破棄した"),
+        (status = 401, description = "APIキーがない・無効", body = ApiErrorBody),
+        (status = 403, description = "閲覧専用のAPIキー", body = ApiErrorBody)
+    )
+)]
+async fn reload_cache(State(state): State<AppState>) -> StatusCode {
+    state.snapshots.invalidate().await;
+    StatusCode::NO_CONTENT
+}
+
+async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
 #[allow(dead_code)]
 async fn hello_handler() -> &'static str {
     "Hello World"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use tower::ServiceExt;
+
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    /// 仕様を変えたら `UPDATE_OPENAPI=1 cargo test -p server` でスナップショットを更新する
+    #[test]
+    fn test_openapi_snapshot_is_up_to_date() {
+        let spec = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+        if env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SNAPSHOT, &spec).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            committed == spec,
+            "openapi.json がハンドラ定義と一致しません。UPDATE_OPENAPI=1 で再生成してください"
+        );
+    }
+
+    /// リクエストIDは受け取った値をそのまま返し、なければ生成して返す
+    #[tokio::test]
+    async fn test_request_id_is_propagated() {
+        let app = with_request_tracing(Router::new().route("/healthz", get(healthz)));
+
+        let request = Request::builder()
+            .uri("/healthz")
+            .header("x-request-id", "req-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["x-request-id"], "req-123");
+
+        let request = Request::builder()
+            .uri("/healthz")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let generated = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(generated.len(), 36, "UUIDが付与される: {}", generated);
+    }
+
+    /// 接続できないDBを指す状態（DBに触れるハンドラはすぐにタイムアウトする）
+    fn unreachable_db_state() -> AppState {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        let pool = Arc::new(pool);
+        AppState {
+            auth: ApiKeyAuth::new(pool.clone(), false),
+            db_pool: pool,
+            django_admin_port: 0,
+            snapshots: SnapshotCache::new(),
+            django_process: DjangoProcess::new(),
+            datapack_version_file: "/nonexistent/version.json".into(),
+        }
+    }
+
+    /// ルートごとのリクエスト数とDB・Djangoの状態が `/metrics` に出る
+    #[tokio::test]
+    async fn test_metrics_report_routes_and_status() {
+        webapp::metrics::prometheus_handle();
+        let state = unreachable_db_state();
+        let app = api_routes(&state)
+            .layer(from_fn(track_http_metrics))
+            .with_state(state);
+
+        let request = Request::builder()
+            .uri("/healthz")
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap();
+
+        let request = Request::builder()
+            .uri("/metrics")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body.contains(r#"route="/healthz""#), "{}", body);
+        assert!(body.contains("db_pool_max_connections"), "{}", body);
+        assert!(body.contains("django_process_up 0"), "{}", body);
+        assert!(body.contains("db_up 0"), "{}", body);
+    }
+
+    /// DBに接続できなければ503とチェックごとの結果を返す
+    #[tokio::test]
+    async fn test_readyz_reports_unreachable_database() {
+        let state = unreachable_db_state();
+        let app = api_routes(&state).with_state(state);
+
+        let request = Request::builder()
+            .uri("/readyz")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["ready"], false);
+        assert_eq!(report["checks"][0]["name"], "database");
+        assert_eq!(report["checks"][0]["ok"], false);
+    }
+
+    /// ルート表とOpenAPI定義が一致することを確認する（仕様自体の配信は除く）
+    #[tokio::test]
+    async fn test_routes_match_openapi() {
+        let state = unreachable_db_state();
+        assert_eq!(
+            ::api_routes::mismatches(&route_table(&state), &ApiDoc::openapi()),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! ルートの表（パス・メソッド・ハンドラ）からaxumのルーターを組み立て、同じ表をOpenAPI定義と突き合わせる
+use axum::{
+    handler::Handler,
+    http::Method,
+    routing::{on, MethodFilter, MethodRouter},
+    Router,
+};
+use utoipa::openapi::{path::Operation, OpenApi};
+
+/// ルーターに登録する1つのルート
+pub struct ApiRoute<S = ()> {
+    pub method: Method,
+    pub path: String,
+    /// OpenAPI定義に載せないルート（定義そのものやUIなど）
+    pub undocumented: bool,
+    method_router: MethodRouter<S>,
+}
+
+impl<S> ApiRoute<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    pub fn new<H, T>(method: Method, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        let filter = MethodFilter::try_from(method.clone())
+            .unwrap_or_else(|_| panic!("ルートに使えないメソッドです: {method}"));
+        Self {
+            method,
+            path: path.to_string(),
+            undocumented: false,
+            method_router: on(filter, handler),
+        }
+    }
+
+    pub fn get<H, T>(path: &str, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        Self::new(Method::GET, path, handler)
+    }
+
+    pub fn post<H, T>(path: &str, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        Self::new(Method::POST, path, handler)
+    }
+
+    pub fn delete<H, T>(path: &str, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        Self::new(Method::DELETE, path, handler)
+    }
+
+    pub fn undocumented(mut self) -> Self {
+        self.undocumented = true;
+        self
+    }
+
+    /// `route_layer` などをこのルートだけに掛ける
+    pub fn map(mut self, f: impl FnOnce(MethodRouter<S>) -> MethodRouter<S>) -> Self {
+        self.method_router = f(self.method_router);
+        self
+    }
+
+    /// パスの前に `prefix` を付ける（`nest` の代わり）
+    pub fn nest(mut self, prefix: &str) -> Self {
+        self.path = format!("{}{}", prefix.trim_end_matches('/'), self.path);
+        self
+    }
+
+    pub fn with_state<S2>(self, state: S) -> ApiRoute<S2> {
+        ApiRoute {
+            method: self.method,
+            path: self.path,
+            undocumented: self.undocumented,
+            method_router: self.method_router.with_state(state),
+        }
+    }
+}
+
+/// 表のルートをすべて登録したルーター。同じパスのメソッドはまとめられる
+pub fn router<S>(routes: Vec<ApiRoute<S>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    routes.into_iter().fold(Router::new(), |router, route| {
+        router.route(&route.path, route.method_router)
+    })
+}
+
+/// 表とOpenAPI定義の食い違い。表にあって定義にない操作と、定義にあって表にない操作を返す
+pub fn mismatches<S>(routes: &[ApiRoute<S>], spec: &OpenApi) -> Vec<String> {
+    let documented: Vec<(Method, &str)> = spec
+        .paths
+        .paths
+        .iter()
+        .flat_map(|(path, item)| {
+            let operations: [(Method, &Option<Operation>); 8] = [
+                (Method::GET, &item.get),
+                (Method::PUT, &item.put),
+                (Method::POST, &item.post),
+                (Method::DELETE, &item.delete),
+                (Method::OPTIONS, &item.options),
+                (Method::HEAD, &item.head),
+                (Method::PATCH, &item.patch),
+                (Method::TRACE, &item.trace),
+            ];
+            operations
+                .into_iter()
+                .filter(|(_, operation)| operation.is_some())
+                .map(move |(method, _)| (method, path.as_str()))
+        })
+        .collect();
+
+    let mut errors = Vec::new();
+    for route in routes.iter().filter(|route| !route.undocumented) {
+        if !documented
+            .iter()
+            .any(|(method, path)| *method == route.method && *path == route.path)
+        {
+            errors.push(format!(
+                "{} {} はOpenAPI定義にありません",
+                route.method, route.path
+            ));
+        }
+    }
+    for (method, path) in &documented {
+        if !routes
+            .iter()
+            .any(|route| route.method == *method && route.path == *path)
+        {
+            errors.push(format!("{method} {path} はルーターに登録されていません"));
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::openapi::{
+        path::OperationBuilder, HttpMethod, OpenApiBuilder, PathItem, PathsBuilder,
+    };
+
+    async fn handler() {}
+
+    fn spec(paths: &[(HttpMethod, &str)]) -> OpenApi {
+        let builder = paths
+            .iter()
+            .fold(PathsBuilder::new(), |builder, (method, path)| {
+                builder.path(
+                    *path,
+                    PathItem::new(method.clone(), OperationBuilder::new().build()),
+                )
+            });
+        OpenApiBuilder::new().paths(builder.build()).build()
+    }
+
+    #[test]
+    fn test_mismatches_reports_both_directions() {
+        let routes: Vec<ApiRoute> = vec![
+            ApiRoute::get("/items", handler),
+            ApiRoute::post("/items", handler),
+            ApiRoute::get("/openapi.json", handler).undocumented(),
+        ];
+        assert_eq!(
+            mismatches(
+                &routes,
+                &spec(&[(HttpMethod::Get, "/items"), (HttpMethod::Post, "/items")])
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            mismatches(
+                &routes,
+                &spec(&[(HttpMethod::Get, "/items"), (HttpMethod::Delete, "/items")])
+            ),
+            vec![
+                "POST /items はOpenAPI定義にありません".to_string(),
+                "DELETE /items はルーターに登録されていません".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nest_prefixes_path() {
+        let route: ApiRoute = ApiRoute::get("/{code}", handler).nest("/api/card/");
+        assert_eq!(route.path, "/api/card/{code}");
+    }
+}
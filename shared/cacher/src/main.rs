@@ -1,8 +1,7 @@
 use cacher::ProductCacher;
-use dotenvy::from_filename;
+use config::Config;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::{Pool, Postgres};
-use std::env;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::time::Duration;
@@ -10,10 +9,15 @@ use webapp::repositories::ProductRepository;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let pool = Arc::new(create_db_pool().await?);
+    let config = Config::load()?;
+    let pool = Arc::new(create_db_pool(&config).await?);
     let product_repo = ProductRepository::new(pool.clone());
 
-    let cache_dir = PathBuf::from("../webapp/text_cache");
+    let cache_dir = config
+        .cache
+        .dir
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("../webapp/text_cache"));
 
     let products = product_repo.get_all().await?;
     println!("Found {} products to cache", products.len());
@@ -31,45 +35,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn create_db_pool() -> Result<Pool<Postgres>, Box<dyn std::error::Error>> {
-    let db_url = build_database_url()?;
-
+async fn create_db_pool(config: &Config) -> Result<Pool<Postgres>, Box<dyn std::error::Error>> {
     let pool = PgPoolOptions::new()
         .max_connections(5)
         .acquire_timeout(Duration::from_secs(5))
-        .connect(&format!("{}?connect_timeout=5", db_url))
+        .connect(&config.database.url_with_connect_timeout(5))
         .await?;
 
     Ok(pool)
 }
-
-fn build_database_url() -> Result<String, Box<dyn std::error::Error>> {
-    let workspace_env = format!(
-        "{}/.env",
-        env::var("CARGO_WORKSPACE_DIR").unwrap_or_default()
-    );
-    let env_paths = [
-        ".env",                 // カレントディレクトリ
-        "../.env",              // 一つ上のディレクトリ
-        "../../.env",           // 二つ上のディレクトリ（nested crateの場合）
-        workspace_env.as_str(), // CARGO_WORKSPACE_DIRが設定されている場合
-    ];
-
-    for path in &env_paths {
-        if std::path::Path::new(path).exists() {
-            from_filename(path).ok();
-            break;
-        }
-    }
-
-    let host = env::var("DB_HOST")?;
-    let port = env::var("DB_PORT")?;
-    let user = env::var("DB_USER")?;
-    let password = env::var("DB_PASSWORD")?;
-    let db_name = env::var("DB_NAME")?;
-
-    Ok(format!(
-        "postgres://{}:{}@{}:{}/{}",
-        user, password, host, port, db_name
-    ))
-}
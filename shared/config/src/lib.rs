@@ -0,0 +1,608 @@
+//! 各バイナリ共通の設定
+//!
+//! 値は次の順に探し、先に見つかったものを使う。
+//!
+//! 1. 環境変数
+//! 2. `.env`（カレントディレクトリから2つ上まで、または `CARGO_WORKSPACE_DIR`）
+//! 3. `config.toml`（`.env` と同じ場所、または `CONFIG_FILE` で指定したパス）
+//! 4. 既定値
+//!
+//! `config.toml` では環境変数名の代わりにセクションとキーで書く（`DB_HOST` → `[database] host`）。
+//! 読み込みは [`Config::load`] の一度だけで、必須項目の不足や形式の誤りはまとめて報告する。
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+
+/// 環境変数名と `config.toml` のキーの対応
+const KEYS: &[(&str, &str)] = &[
+    ("DATABASE_URL", "database.url"),
+    ("DB_HOST", "database.host"),
+    ("DB_PORT", "database.port"),
+    ("DB_USER", "database.user"),
+    ("DB_PASSWORD", "database.password"),
+    ("DB_NAME", "database.name"),
+    ("DB_SLOW_QUERY_MS", "database.slow_query_ms"),
+    ("LOG_FORMAT", "log.format"),
+    ("WEB_PORT", "server.web_port"),
+    ("FIXED_DATA_HOST", "server.fixed_data_host"),
+    ("FIXED_DATA_PORT", "server.fixed_data_port"),
+    ("RULE_EDITOR_PORT", "server.rule_editor_port"),
+    ("DATAPACK_VERSION_FILE", "server.datapack_version_file"),
+    ("CACHE_DIR", "cache.dir"),
+    ("OPENAI_API_KEY", "openai.api_key"),
+    ("OPENAI_MODEL", "openai.model"),
+    ("LLM_PROVIDER", "openai.provider"),
+    ("OPENAI_BASE_URL", "openai.base_url"),
+    ("DJANGO_ADMIN_PORT", "admin.django_port"),
+    ("API_AUTH_ENABLED", "admin.api_auth_enabled"),
+];
+
+/// 設定の読み込みエラー
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("{path} を読めません: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{path} の形式が不正です: {message}")]
+    File { path: PathBuf, message: String },
+    #[error(
+        "設定に誤りがあります（環境変数・.env・config.tomlを確認してください）\n{}",
+        .0.iter().map(|problem| format!("  - {}", problem)).collect::<Vec<_>>().join("\n")
+    )]
+    Invalid(Vec<String>),
+}
+
+/// URLのユーザー情報でそのまま使える文字（RFC 3986 の unreserved）以外
+const USERINFO: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// DB接続
+#[derive(Clone, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    /// `DATABASE_URL` が指定されていればそのまま使う
+    pub url: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    pub name: String,
+    /// この時間（ミリ秒）を超えたクエリをwarnで出力する
+    pub slow_query_ms: u64,
+}
+
+impl DatabaseConfig {
+    /// `postgres://` の接続URL（ユーザー名とパスワードはパーセントエンコードする）
+    pub fn url(&self) -> String {
+        match &self.url {
+            Some(url) => url.clone(),
+            None => format!(
+                "postgres://{}:{}@{}:{}/{}",
+                utf8_percent_encode(&self.user, USERINFO),
+                utf8_percent_encode(&self.password, USERINFO),
+                self.host,
+                self.port,
+                self.name
+            ),
+        }
+    }
+
+    /// 接続タイムアウト（秒）を付けた接続URL
+    pub fn url_with_connect_timeout(&self, seconds: u64) -> String {
+        let url = self.url();
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{url}{separator}connect_timeout={seconds}")
+    }
+}
+
+// パスワードをログに出さない
+impl fmt::Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("url", &self.url.as_ref().map(|_| "<redacted>"))
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("name", &self.name)
+            .field("slow_query_ms", &self.slow_query_ms)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 各サーバーの待ち受けポートなど
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// server（既定8000）
+    pub web_port: u16,
+    /// fixed_data_serverの待ち受けアドレス（既定127.0.0.1。コンテナなどで外から受けるなら0.0.0.0）
+    pub fixed_data_host: IpAddr,
+    /// fixed_data_server（既定8004）
+    pub fixed_data_port: u16,
+    /// rule_editorのWebサーバー（既定3030）
+    pub rule_editor_port: u16,
+    /// `/readyz` で比較するdatapackのバージョン（`cargo make static` が書き出す）
+    pub datapack_version_file: PathBuf,
+}
+
+/// ログの出力形式（`LOG_FORMAT`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// 人が読むテキスト（既定）
+    #[default]
+    Text,
+    /// 1行1JSON
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("text / json のいずれかで指定してください".to_string()),
+        }
+    }
+}
+
+/// ログ出力（出力レベルは `RUST_LOG` で指定する）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogConfig {
+    pub format: LogFormat,
+}
+
+/// スクレイピング結果のキャッシュ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// 未指定なら各バイナリの既定（scraperは `./text_cache`、cacherはwebappが読む `../webapp/text_cache`）
+    pub dir: Option<PathBuf>,
+}
+
+/// rule_editorのパターン生成に使うLLM（`LLM_PROVIDER`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LlmProviderKind {
+    /// OpenAI API（既定）
+    #[default]
+    OpenAi,
+    /// OpenAI互換のローカルサーバー（llama.cppのserverなど。`OPENAI_BASE_URL` が必要）
+    Local,
+    /// APIを呼ばずに決まった応答を返す（オフラインでの開発・テスト用）
+    Mock,
+}
+
+impl std::str::FromStr for LlmProviderKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "openai" => Ok(LlmProviderKind::OpenAi),
+            "local" => Ok(LlmProviderKind::Local),
+            "mock" => Ok(LlmProviderKind::Mock),
+            _ => Err("openai / local / mock のいずれかで指定してください".to_string()),
+        }
+    }
+}
+
+/// OpenAI API（rule_editorのパターン生成）
+#[derive(Clone, PartialEq, Eq)]
+pub struct OpenAiConfig {
+    pub provider: LlmProviderKind,
+    pub api_key: Option<String>,
+    /// 既定 `gpt-4o-mini`
+    pub model: String,
+    /// APIのベースURL（例: `http://localhost:8080/v1`）。省略時はOpenAI
+    pub base_url: Option<String>,
+}
+
+impl fmt::Debug for OpenAiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenAiConfig")
+            .field("provider", &self.provider)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("model", &self.model)
+            .field("base_url", &self.base_url)
+            .finish()
+    }
+}
+
+/// 管理機能
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminConfig {
+    /// Django管理サーバー（既定8200）
+    pub django_port: u16,
+    /// 管理系APIのAPIキー認証（既定は有効。無効にするのはローカル開発のみ）
+    pub api_auth_enabled: bool,
+}
+
+/// 全体の設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    pub server: ServerConfig,
+    pub log: LogConfig,
+    pub cache: CacheConfig,
+    pub openai: OpenAiConfig,
+    pub admin: AdminConfig,
+}
+
+impl Config {
+    /// 環境変数・`.env`・`config.toml` から読み込んで検証する
+    pub fn load() -> Result<Self, ConfigError> {
+        let search_dirs = search_dirs();
+
+        if let Some(dotenv) = search_dirs
+            .iter()
+            .map(|dir| dir.join(".env"))
+            .find(|path| path.exists())
+        {
+            dotenvy::from_path(&dotenv).ok();
+        }
+
+        let file_path = match env::var_os("CONFIG_FILE") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => search_dirs
+                .iter()
+                .map(|dir| dir.join("config.toml"))
+                .find(|path| path.exists()),
+        };
+        let file = match file_path {
+            Some(path) => read_file(&path)?,
+            None => HashMap::new(),
+        };
+
+        Self::from_lookup(|key| env::var(key).ok().or_else(|| file.get(key).cloned()))
+    }
+
+    /// [`Config::load`] と同じだが、失敗したらエラーを表示して終了する（各バイナリの `main` 用）
+    pub fn load_or_exit() -> Self {
+        Self::load().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        })
+    }
+
+    /// 環境変数名で値を引く関数から組み立てる
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let mut reader = Reader {
+            lookup: &lookup,
+            problems: Vec::new(),
+        };
+
+        let url = reader.optional("DATABASE_URL");
+        // DATABASE_URLがあれば個別の項目は不要
+        let required = |reader: &mut Reader, key| match &url {
+            Some(_) => reader.optional(key).unwrap_or_default(),
+            None => reader.required(key),
+        };
+        let database = DatabaseConfig {
+            host: required(&mut reader, "DB_HOST"),
+            port: reader.parse("DB_PORT", 5432),
+            user: required(&mut reader, "DB_USER"),
+            password: reader.optional("DB_PASSWORD").unwrap_or_default(),
+            name: required(&mut reader, "DB_NAME"),
+            slow_query_ms: reader.parse("DB_SLOW_QUERY_MS", 500),
+            url,
+        };
+
+        let server = ServerConfig {
+            web_port: reader.parse("WEB_PORT", 8000),
+            fixed_data_host: reader.parse("FIXED_DATA_HOST", IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            fixed_data_port: reader.parse("FIXED_DATA_PORT", 8004),
+            rule_editor_port: reader.parse("RULE_EDITOR_PORT", 3030),
+            datapack_version_file: reader
+                .optional("DATAPACK_VERSION_FILE")
+                .unwrap_or_else(|| "../datapack/src/gen/version.json".to_string())
+                .into(),
+        };
+
+        let log = LogConfig {
+            format: reader.parse("LOG_FORMAT", LogFormat::default()),
+        };
+
+        let cache = CacheConfig {
+            dir: reader.optional("CACHE_DIR").map(PathBuf::from),
+        };
+
+        let openai = OpenAiConfig {
+            provider: reader.parse("LLM_PROVIDER", LlmProviderKind::default()),
+            api_key: reader.optional("OPENAI_API_KEY"),
+            model: reader
+                .optional("OPENAI_MODEL")
+                .unwrap_or_else(|| "gpt-4o-mini".to_string()),
+            base_url: reader.optional("OPENAI_BASE_URL"),
+        };
+        if openai.provider == LlmProviderKind::Local && openai.base_url.is_none() {
+            reader.problems.push(
+                "LLM_PROVIDER=local には OPENAI_BASE_URL が必要です（openai.base_url）".to_string(),
+            );
+        }
+
+        let admin = AdminConfig {
+            django_port: reader.parse("DJANGO_ADMIN_PORT", 8200),
+            api_auth_enabled: reader.flag("API_AUTH_ENABLED", true),
+        };
+
+        if !reader.problems.is_empty() {
+            return Err(ConfigError::Invalid(reader.problems));
+        }
+        Ok(Self {
+            database,
+            server,
+            log,
+            cache,
+            openai,
+            admin,
+        })
+    }
+}
+
+/// 値を読みながら問題点を集める
+struct Reader<'a> {
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+    problems: Vec<String>,
+}
+
+impl Reader<'_> {
+    /// 空文字は未設定として扱う
+    fn optional(&mut self, key: &str) -> Option<String> {
+        (self.lookup)(key).filter(|value| !value.trim().is_empty())
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        self.optional(key).unwrap_or_else(|| {
+            self.problems
+                .push(format!("{} が設定されていません（{}）", key, file_key(key)));
+            String::new()
+        })
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self, key: &str, default: T) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        match self.optional(key) {
+            None => default,
+            Some(value) => value.trim().parse().unwrap_or_else(|e| {
+                self.problems
+                    .push(format!("{} の値 `{}` が不正です: {}", key, value, e));
+                default
+            }),
+        }
+    }
+
+    fn flag(&mut self, key: &str, default: bool) -> bool {
+        match self.optional(key) {
+            None => default,
+            Some(value) => match value.trim().to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => true,
+                "0" | "false" | "no" | "off" => false,
+                _ => {
+                    self.problems.push(format!(
+                        "{} の値 `{}` が不正です: true/false で指定してください",
+                        key, value
+                    ));
+                    false
+                }
+            },
+        }
+    }
+}
+
+fn file_key(env_key: &str) -> &'static str {
+    KEYS.iter()
+        .find(|(key, _)| *key == env_key)
+        .map(|(_, file_key)| *file_key)
+        .unwrap_or("")
+}
+
+/// `.env`・`config.toml` を探すディレクトリ
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from("."),     // カレントディレクトリ
+        PathBuf::from(".."),    // 一つ上のディレクトリ
+        PathBuf::from("../.."), // 二つ上のディレクトリ（nested crateの場合）
+    ];
+    if let Ok(workspace) = env::var("CARGO_WORKSPACE_DIR") {
+        dirs.push(PathBuf::from(workspace));
+    }
+    dirs
+}
+
+fn read_file(path: &Path) -> Result<HashMap<String, String>, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_file(&text).map_err(|message| ConfigError::File {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// `config.toml` を環境変数名をキーにした表に変換する（未知のキーはエラー）
+fn parse_file(text: &str) -> Result<HashMap<String, String>, String> {
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+
+    let mut values = HashMap::new();
+    for (section, entries) in &table {
+        let entries = entries.as_table().ok_or_else(|| {
+            format!(
+                "`{}` はセクション（[{}]）で書いてください",
+                section, section
+            )
+        })?;
+        for (name, value) in entries {
+            let file_key = format!("{}.{}", section, name);
+            let (env_key, _) = KEYS
+                .iter()
+                .find(|(_, key)| *key == file_key)
+                .ok_or_else(|| format!("不明な設定 `{}` があります", file_key))?;
+            let value = match value {
+                toml::Value::String(value) => value.clone(),
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Boolean(value) => value.to_string(),
+                _ => {
+                    return Err(format!(
+                        "`{}` は文字列・整数・真偽値で指定してください",
+                        file_key
+                    ))
+                }
+            };
+            values.insert(env_key.to_string(), value);
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        move |key| map.get(key).cloned()
+    }
+
+    #[test]
+    fn test_defaults_and_database_url() {
+        let config = Config::from_lookup(lookup(&[
+            ("DB_HOST", "db"),
+            ("DB_USER", "wix"),
+            ("DB_PASSWORD", "secret"),
+            ("DB_NAME", "wixdb"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.database.url(), "postgres://wix:secret@db:5432/wixdb");
+        assert_eq!(
+            config.database.url_with_connect_timeout(5),
+            "postgres://wix:secret@db:5432/wixdb?connect_timeout=5"
+        );
+        assert_eq!(config.server.web_port, 8000);
+        assert_eq!(config.server.fixed_data_host.to_string(), "127.0.0.1");
+        assert_eq!(config.server.fixed_data_port, 8004);
+        assert_eq!(config.log.format, LogFormat::Text);
+        assert_eq!(config.admin.django_port, 8200);
+        assert_eq!(config.cache.dir, None);
+        assert!(config.admin.api_auth_enabled);
+        assert!(!format!("{:?}", config).contains("secret"));
+    }
+
+    #[test]
+    fn test_api_auth_can_be_disabled_explicitly() {
+        let config = Config::from_lookup(lookup(&[
+            ("DATABASE_URL", "postgres://a@b/c"),
+            ("API_AUTH_ENABLED", "false"),
+        ]))
+        .unwrap();
+        assert!(!config.admin.api_auth_enabled);
+    }
+
+    #[test]
+    fn test_database_url_encodes_credentials() {
+        let config = Config::from_lookup(lookup(&[
+            ("DB_HOST", "db"),
+            ("DB_USER", "p0stgres"),
+            ("DB_PASSWORD", "p@ss:w/rd"),
+            ("DB_NAME", "wixdb"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            config.database.url(),
+            "postgres://p0stgres:p%40ss%3Aw%2Frd@db:5432/wixdb"
+        );
+    }
+
+    #[test]
+    fn test_database_url_replaces_components() {
+        let config = Config::from_lookup(lookup(&[(
+            "DATABASE_URL",
+            "postgres://a@b/c?sslmode=disable",
+        )]))
+        .unwrap();
+        assert_eq!(
+            config.database.url_with_connect_timeout(5),
+            "postgres://a@b/c?sslmode=disable&connect_timeout=5"
+        );
+    }
+
+    #[test]
+    fn test_all_problems_are_reported_together() {
+        let error = Config::from_lookup(lookup(&[
+            ("DB_HOST", "db"),
+            ("WEB_PORT", "eighty"),
+            ("FIXED_DATA_HOST", "localhost"),
+            ("LOG_FORMAT", "yaml"),
+            ("API_AUTH_ENABLED", "maybe"),
+        ]))
+        .unwrap_err();
+
+        let message = error.to_string();
+        assert!(
+            message.contains("DB_USER が設定されていません（database.user）"),
+            "{}",
+            message
+        );
+        assert!(message.contains("DB_NAME"), "{}", message);
+        assert!(message.contains("WEB_PORT の値 `eighty`"), "{}", message);
+        assert!(
+            message.contains("FIXED_DATA_HOST の値 `localhost`"),
+            "{}",
+            message
+        );
+        assert!(message.contains("LOG_FORMAT の値 `yaml`"), "{}", message);
+        assert!(message.contains("API_AUTH_ENABLED"), "{}", message);
+    }
+
+    #[test]
+    fn test_llm_provider() {
+        let base = [("DATABASE_URL", "postgres://a@b/c")];
+        let config = Config::from_lookup(lookup(&base)).unwrap();
+        assert_eq!(config.openai.provider, LlmProviderKind::OpenAi);
+
+        let config = Config::from_lookup(lookup(&[
+            base[0],
+            ("LLM_PROVIDER", "local"),
+            ("OPENAI_BASE_URL", "http://localhost:8080/v1"),
+        ]))
+        .unwrap();
+        assert_eq!(config.openai.provider, LlmProviderKind::Local);
+
+        let error = Config::from_lookup(lookup(&[base[0], ("LLM_PROVIDER", "local")])).unwrap_err();
+        assert!(error.to_string().contains("OPENAI_BASE_URL"), "{}", error);
+        assert!(Config::from_lookup(lookup(&[base[0], ("LLM_PROVIDER", "claude")])).is_err());
+    }
+
+    #[test]
+    fn test_parse_file_maps_sections_to_env_keys() {
+        let values = parse_file(
+            r#"
+            [database]
+            host = "db"
+            port = 5433
+
+            [admin]
+            api_auth_enabled = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(values["DB_HOST"], "db");
+        assert_eq!(values["DB_PORT"], "5433");
+        assert_eq!(values["API_AUTH_ENABLED"], "true");
+
+        let error = parse_file("[database]\nhots = \"db\"\n").unwrap_err();
+        assert!(error.contains("database.hots"), "{}", error);
+    }
+}
@@ -40,6 +40,24 @@ macro_rules! define_features {
                         )*
                     ]
                 }
+
+            // 4. 列挙子名（ビット位置が変わっても変わらない識別子）
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(
+                        CardFeature::$feature => stringify!($feature),
+                    )*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<CardFeature> {
+                match name {
+                    $(
+                        stringify!($feature) => Some(CardFeature::$feature),
+                    )*
+                    _ => None,
+                }
+            }
             }
 
         // 各フィーチャの文字列表現を返す関数
@@ -210,6 +228,25 @@ impl HashSetToBits for HashSet<CardFeature> {
     }
 }
 
+impl CardFeature {
+    /// 特徴ビットに含まれるフィーチャを定義順に返す
+    ///
+    /// shift2が0のフィーチャはbits1側、それ以外はbits2側のビットで判定する
+    pub fn from_bits(bits1: i64, bits2: i64) -> Vec<CardFeature> {
+        CardFeature::create_vec()
+            .into_iter()
+            .filter(|feature| {
+                let (shift1, shift2) = feature.to_bit_shifts();
+                if shift2 == 0 {
+                    bits1 & (1_i64 << shift1) != 0
+                } else {
+                    bits2 & (1_i64 << shift2) != 0
+                }
+            })
+            .collect()
+    }
+}
+
 // ユーザーが見る形式のデータを定義
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExportedCardFeature {
@@ -291,6 +328,24 @@ macro_rules! define_burst_features {
                     )*
                 ]
             }
+
+            // 4. 列挙子名（ビット位置が変わっても変わらない識別子）
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(
+                        BurstFeature::$feature => stringify!($feature),
+                    )*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<BurstFeature> {
+                match name {
+                    $(
+                        stringify!($feature) => Some(BurstFeature::$feature),
+                    )*
+                    _ => None,
+                }
+            }
         }
 
         // Display implementation for Japanese labels
@@ -341,6 +396,16 @@ impl BurstHashSetToBits for HashSet<BurstFeature> {
     }
 }
 
+impl BurstFeature {
+    /// バーストビットに含まれるバーストフィーチャを定義順に返す
+    pub fn from_bits(bits: i64) -> Vec<BurstFeature> {
+        BurstFeature::create_vec()
+            .into_iter()
+            .filter(|feature| bits & (1_i64 << feature.to_bit_shift()) != 0)
+            .collect()
+    }
+}
+
 // BurstFeature用のExport構造体
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExportedBurstFeature {
@@ -1,6 +1,7 @@
 use regex::Regex;
 
 pub mod feature;
+pub mod text;
 use crate::BurstFeature::{
     BlockLrig, BlockSigni, Charge, Defend1, Defend2, Discard, Draw, EraseSkill, Freeze, Guard,
     Heal, OffenciveDefend, Others, Salvage, Search,
@@ -86,10 +87,11 @@ macro_rules! replace_pattern {
 }
 
 // 置換ぜすテキストをそのまま残すが効果検出もしない、考慮漏れではないことを示すケース`
+// カバレッジ計測で「説明済み」として扱えるよう、パターン文字列は定数として公開する
 macro_rules! concerned {
-    // ($pat:expr) => {};
-    // ($pat:expr,*) => {};
-    ($( $x:expr ),*) => {};
+    ($( $x:expr ),* $(,)?) => {
+        pub const CONCERNED_PATTERNS: &[&str] = &[$($x),*];
+    };
 }
 
 macro_rules! detect_pattern {
@@ -173,6 +175,14 @@ concerned![
     r"\(ゲームを開始する際に、このルリグを表向きにしたとき、このルリグがセンタールリグであるなら、(《コインアイコン》)+を得る\)"
 ];
 
+/// 考慮済み（検出対象外）テキストの正規表現をコンパイルして返す
+pub fn create_concerned_patterns() -> Vec<Regex> {
+    CONCERNED_PATTERNS
+        .iter()
+        .map(|pat| Regex::new(pat).unwrap())
+        .collect()
+}
+
 pub const PATTERNS_AMOUNT_R: usize = 83;
 pub const PATTERNS_AMOUNT_D: usize = 180;
 
@@ -14,6 +14,17 @@ pub fn create_klass(source: &str) -> CreateKlass {
     }
 }
 
+/// `大:小/細` 形式の表示名（空文字のカテゴリーはないものとして扱う）
+pub fn klass_label(cat1: &str, cat2: Option<&str>, cat3: Option<&str>) -> String {
+    let cat2 = cat2.filter(|c| !c.is_empty());
+    let cat3 = cat3.filter(|c| !c.is_empty());
+    match (cat2, cat3) {
+        (Some(cat2), Some(cat3)) => format!("{}:{}/{}", cat1, cat2, cat3),
+        (Some(cat2), None) => format!("{}:{}", cat1, cat2),
+        _ => cat1.to_string(),
+    }
+}
+
 fn create_klass_source_from_str(s: &str) -> (String, Option<String>, Option<String>) {
     // 大カテゴリーと小カテゴリーを「：」で分割
     let parts: Vec<&str> = s.split('：').collect();
@@ -0,0 +1,58 @@
+use analyzer::card_analyzer::SimpleRawCardAnalyzer;
+use analyzer::pipeline::CrossCheck;
+use models::gen::django_models::RawCardDb;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use webapp::analyze::raw_card_integration::WebAppRawCardAnalyzer;
+
+/// analyzerのHTML走査とwebappのカード種別パーサーを同じRawCardに適用し、食い違いをカードごとに報告する
+///
+/// usage: cargo run --example cross_check_analyzers -- [limit]
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let limit: i64 = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(100);
+
+    let pool = PgPool::connect(&database_url).await?;
+
+    let raw_cards: Vec<RawCardDb> = sqlx::query_as::<_, RawCardDb>(
+        r#"
+        SELECT id, card_number, name, raw_html, skill_text, life_burst_text,
+               source_url, scraped_at, last_analyzed_at, is_analyzed, analysis_error
+        FROM wix_rawcard
+        ORDER BY id
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(&pool)
+    .await?;
+
+    let cross_check = CrossCheck::new(SimpleRawCardAnalyzer::new(), WebAppRawCardAnalyzer::new());
+    let reports = cross_check.check_batch(&raw_cards).await;
+
+    let mut field_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for report in reports.iter().filter(|report| !report.is_consistent()) {
+        println!("{}", report.card_number);
+        for disagreement in &report.disagreements {
+            println!(
+                "  {}: analyzer={} webapp={}",
+                disagreement.field, disagreement.left, disagreement.right
+            );
+            *field_counts.entry(disagreement.field).or_default() += 1;
+        }
+    }
+
+    let inconsistent = reports.iter().filter(|r| !r.is_consistent()).count();
+    println!("\n{} / {} cards disagree", inconsistent, reports.len());
+    for (field, count) in field_counts {
+        println!("  {}: {}", field, count);
+    }
+
+    Ok(())
+}
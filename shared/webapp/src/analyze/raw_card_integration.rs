@@ -1,10 +1,75 @@
 use crate::analyze::wixoss::Card;
+use analyzer::card_analyzer::CreateCardWithKlass;
+use analyzer::pipeline::{AnalysisPipeline, AnalysisStage, StageKind};
 use analyzer::raw_card_analyzer::{AnalysisError, RawCardAnalyzer};
+use analyzer::raw_card_fields::RawCardFields;
 use models::card::CreateCard;
 use models::gen::django_models::RawCardDb;
 
+/// カード種別ごとのHTMLパーサー（`Card::card_from_html`）によるフィールド抽出ステージ
+///
+/// クラス・特徴・バーストはanalyzerの標準ステージに任せる
+pub struct WebAppFieldStage;
+
+impl AnalysisStage for WebAppFieldStage {
+    fn name(&self) -> &'static str {
+        "webapp_fields"
+    }
+
+    fn kind(&self) -> StageKind {
+        StageKind::Fields
+    }
+
+    fn apply(
+        &self,
+        raw_card: &RawCardDb,
+        _fields: &RawCardFields,
+        card: &mut CreateCardWithKlass,
+    ) -> Result<(), AnalysisError> {
+        let parsed: CreateCard = Card::card_from_html(&raw_card.raw_html)
+            .ok_or_else(|| {
+                AnalysisError::new("Failed to parse card HTML".to_string(), raw_card.id)
+            })?
+            .into();
+
+        // Ensure the code matches what was scraped
+        if parsed.code != raw_card.card_number {
+            return Err(AnalysisError::new(
+                format!(
+                    "Card number mismatch: expected {}, got {}",
+                    raw_card.card_number, parsed.code
+                ),
+                raw_card.id,
+            ));
+        }
+
+        let create_card = &mut card.create_card;
+        create_card.name = parsed.name;
+        create_card.code = parsed.code;
+        create_card.pronunciation = parsed.pronunciation;
+        create_card.color = parsed.color;
+        create_card.cost = parsed.cost;
+        create_card.level = parsed.level;
+        create_card.limit = parsed.limit;
+        create_card.limit_ex = parsed.limit_ex;
+        create_card.power = parsed.power;
+        create_card.card_type = parsed.card_type;
+        create_card.format = parsed.format;
+        create_card.story = parsed.story;
+        create_card.rarity = parsed.rarity;
+        create_card.timing = parsed.timing;
+        create_card.ex1 = parsed.ex1;
+
+        Ok(())
+    }
+}
+
 /// WebApp integrated RawCardAnalyzer that uses the full HTML parsing capabilities
-pub struct WebAppRawCardAnalyzer;
+///
+/// analyzerの標準パイプラインのフィールド抽出ステージを `WebAppFieldStage` に差し替えたもの
+pub struct WebAppRawCardAnalyzer {
+    pipeline: AnalysisPipeline,
+}
 
 impl Default for WebAppRawCardAnalyzer {
     fn default() -> Self {
@@ -14,74 +79,32 @@ impl Default for WebAppRawCardAnalyzer {
 
 impl WebAppRawCardAnalyzer {
     pub fn new() -> Self {
-        Self
+        Self {
+            pipeline: AnalysisPipeline::standard().with_stage(WebAppFieldStage),
+        }
     }
 }
 
 #[async_trait::async_trait]
 impl RawCardAnalyzer for WebAppRawCardAnalyzer {
     async fn analyze(&self, raw_card: &RawCardDb) -> Result<CreateCard, AnalysisError> {
-        // Parse the HTML to get a Card struct
-        let card = match Card::card_from_html(&raw_card.raw_html) {
-            Some(card) => card,
-            None => {
-                return Err(AnalysisError::new(
-                    "Failed to parse card HTML".to_string(),
-                    raw_card.id,
-                ));
-            }
-        };
-
-        // Convert Card to CreateCard
-        let mut create_card: CreateCard = card.into();
-
-        // Override some fields with data from RawCardDb
-        // TODO: Use raw_card.product_id when field is available
-        create_card.url = Some(raw_card.source_url.clone());
-
-        // Ensure the code matches what was scraped
-        if create_card.code != raw_card.card_number {
-            return Err(AnalysisError::new(
-                format!(
-                    "Card number mismatch: expected {}, got {}",
-                    raw_card.card_number, create_card.code
-                ),
-                raw_card.id,
-            ));
-        }
+        self.pipeline.analyze(raw_card).await
+    }
 
-        Ok(create_card)
+    async fn analyze_with_klass(
+        &self,
+        raw_card: &RawCardDb,
+    ) -> Result<CreateCardWithKlass, AnalysisError> {
+        self.pipeline.analyze_with_klass(raw_card).await
     }
 }
 
 /// Utility function to analyze a single raw card and save to database
-/// NOTE: This function is currently disabled due to cyclic dependency removal
 pub async fn analyze_and_save_card(
     raw_card: &RawCardDb,
     pool: &sqlx::PgPool,
 ) -> Result<i64, Box<dyn std::error::Error>> {
-    // let analyzer = WebAppRawCardAnalyzer::new();
-
-    // Analyze the raw card using Card::card_from_html directly
-    let card = match Card::card_from_html(&raw_card.raw_html) {
-        Some(card) => card,
-        None => {
-            return Err(format!("Failed to parse card HTML for card {}", raw_card.id).into());
-        }
-    };
-
-    // Convert Card to CreateCard
-    let mut create_card: CreateCard = card.into();
-    create_card.url = Some(raw_card.source_url.clone());
-
-    // Ensure the code matches what was scraped
-    if create_card.code != raw_card.card_number {
-        return Err(format!(
-            "Card number mismatch: expected {}, got {}",
-            raw_card.card_number, create_card.code
-        )
-        .into());
-    }
+    let create_card = WebAppRawCardAnalyzer::new().analyze(raw_card).await?;
 
     // Save to database using the repository
     use crate::repositories::CardRepository;
@@ -106,33 +129,12 @@ pub async fn analyze_raw_cards_batch(
     raw_cards: Vec<RawCardDb>,
     pool: &sqlx::PgPool,
 ) -> Vec<Result<i64, Box<dyn std::error::Error>>> {
-    // let analyzer = WebAppRawCardAnalyzer::new();
+    let analyzer = WebAppRawCardAnalyzer::new();
     let mut results = Vec::new();
 
     for raw_card in raw_cards {
         let result: Result<i64, Box<dyn std::error::Error>> = async {
-            // Analyze using Card::card_from_html directly
-            let card = match Card::card_from_html(&raw_card.raw_html) {
-                Some(card) => card,
-                None => {
-                    return Err(
-                        format!("Failed to parse card HTML for card {}", raw_card.id).into(),
-                    );
-                }
-            };
-
-            // Convert Card to CreateCard
-            let mut create_card: CreateCard = card.into();
-            create_card.url = Some(raw_card.source_url.clone());
-
-            // Ensure the code matches what was scraped
-            if create_card.code != raw_card.card_number {
-                return Err(format!(
-                    "Card number mismatch: expected {}, got {}",
-                    raw_card.card_number, create_card.code
-                )
-                .into());
-            }
+            let create_card = analyzer.analyze(&raw_card).await?;
 
             use crate::repositories::CardRepository;
             use std::sync::Arc;
@@ -170,6 +172,60 @@ pub async fn analyze_raw_cards_batch(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use analyzer::card_analyzer::SimpleRawCardAnalyzer;
+    use analyzer::pipeline::CrossCheck;
+    use sqlx::types::chrono::Utc;
+
+    const SPELL_WITH_LIFE_BURST: &str = r#"
+        <div class="cardDetail">
+            <div class="cardttlwrap">
+                <p class="cardNum">WXDi-P14-061</p>
+                <p class="cardName">TEMPO　UP<br class="sp"><span>＜テンポアップ＞</span></p>
+                <div class="cardRarity">C</div>
+            </div>
+            <div class="cardImg"><p>Illust <span>茶ちえ</span></p></div>
+            <div class="cardData">
+                <dl>
+                    <dt>カード種類</dt><dd>スペル</dd>
+                    <dt>カードタイプ</dt><dd>-</dd>
+                    <dt>色</dt><dd>青</dd>
+                    <dt>レベル</dt><dd>-</dd>
+                    <dt>グロウコスト</dt><dd>-</dd>
+                    <dt>コスト</dt><dd>《青》×１</dd>
+                    <dt>リミット</dt><dd>-</dd>
+                    <dt>パワー</dt><dd>-</dd>
+                    <dt>限定条件</dt><dd>-</dd>
+                    <dt>ガード</dt><dd>-</dd>
+                    <dt>フォーマット</dt><dd><img src="icon_txt_format_diva.png" height="23" alt="《ディーヴァアイコン》" /></dd>
+                    <dt>ストーリー</dt><dd>-</dd>
+                </dl>
+                <div class="cardSkill">
+                    あなたの青のシグニ１体を対象とし、ターン終了時まで、そのパワーを＋3000する。
+                </div>
+                <div class="cardSkill">
+                    <img src="icon_txt_burst.png" width="26" height="24" alt="ライフバースト" />：対戦相手のシグニを２体まで対象とし、それらをダウンする。
+                </div>
+            </div>
+        </div>
+    "#;
+
+    fn raw_card(life_burst_text: &str) -> RawCardDb {
+        RawCardDb {
+            id: 1,
+            card_number: "WXDi-P14-061".to_string(),
+            name: "TEMPO　UP".to_string(),
+            raw_html: SPELL_WITH_LIFE_BURST.to_string(),
+            skill_text: "あなたの青のシグニ１体を対象とし、ターン終了時まで、そのパワーを＋3000する。"
+                .to_string(),
+            // スクレイパーが保存する形式（アイコン後の「：」以降）
+            life_burst_text: life_burst_text.to_string(),
+            source_url: "https://example.com/card/WXDi-P14-061".to_string(),
+            scraped_at: Utc::now(),
+            last_analyzed_at: None,
+            is_analyzed: false,
+            analysis_error: String::new(),
+        }
+    }
 
     #[tokio::test]
     async fn test_webapp_analyzer_creation() {
@@ -177,4 +233,36 @@ mod tests {
         // Basic creation test
         assert!(true);
     }
+
+    #[tokio::test]
+    async fn test_burst_bits_parity_with_simple_analyzer() {
+        let raw_card = raw_card("対戦相手のシグニを２体まで対象とし、それらをダウンする。");
+
+        let webapp_card = WebAppRawCardAnalyzer::new()
+            .analyze(&raw_card)
+            .await
+            .expect("webapp analysis failed");
+        let simple_card = SimpleRawCardAnalyzer::new()
+            .analyze(&raw_card)
+            .await
+            .expect("simple analysis failed");
+
+        assert_ne!(simple_card.burst_bits, 0, "burst features should be detected");
+        assert_eq!(webapp_card.burst_bits, simple_card.burst_bits);
+        assert_eq!(webapp_card.has_burst, simple_card.has_burst);
+    }
+
+    #[tokio::test]
+    async fn test_cross_check_with_simple_analyzer() {
+        let raw_card = raw_card("対戦相手のシグニを２体まで対象とし、それらをダウンする。");
+        let cross_check = CrossCheck::new(SimpleRawCardAnalyzer::new(), WebAppRawCardAnalyzer::new());
+
+        let report = cross_check.check(&raw_card).await;
+        let fields: Vec<&str> = report.disagreements.iter().map(|d| d.field).collect();
+
+        // カード種別・バースト・クラス・特徴は共通ステージで揃う
+        for field in ["card_type", "has_burst", "burst_bits", "klass", "feature_bits1", "feature_bits2"] {
+            assert!(!fields.contains(&field), "{} disagrees: {:?}", field, report.disagreements);
+        }
+    }
 }
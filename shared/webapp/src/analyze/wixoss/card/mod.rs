@@ -98,23 +98,23 @@ impl CardType {
         }
     }
 
-    /// CardTypeをデータベースIDに変換
+    /// CardTypeをデータベースID（wix_cardtypeのID、analyzerと共通）に変換
     pub fn to_db_id(&self) -> i32 {
         match self {
             CardType::Lrig => 1,
-            CardType::LrigAssist => 2,
-            CardType::Arts => 3,
-            CardType::Key => 4,
+            CardType::Arts => 2,
+            CardType::LrigAssist => 3,
+            CardType::Piece => 4,
             CardType::Signi => 5,
             CardType::Spell => 6,
             CardType::Resona => 7,
-            CardType::SigniCraft => 8,
+            CardType::Key => 8,
             CardType::ArtsCraft => 9,
-            CardType::ResonaCraft => 10,
+            CardType::SigniCraft => 10,
             CardType::SpellCraft => 11,
-            CardType::Piece => 12,
-            CardType::PieceRelay => 13,
-            CardType::PieceCraft => 14,
+            CardType::PieceRelay => 12,
+            CardType::PieceCraft => 13,
+            CardType::ResonaCraft => 14,
             CardType::Token => 15,
             CardType::Unknown => 0,
         }
@@ -17,10 +17,10 @@ use crate::analyze::wixoss::selectors::{
     SPAN_SELECTOR,
 };
 use crate::analyze::wixoss::timing::TimingList;
-// Removed analyzer dependency to avoid cyclic dependency
-// use analyzer::{AnalyzeRule, Analyzer};
+use analyzer::card_analyzer::detect_burst_features;
 use feature::feature::CardFeature;
 use models::card::CreateCard;
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use regex::Regex;
 use scraper::Html;
@@ -132,6 +132,10 @@ pub enum CardSkill {
 const SKILL_PREFIX_NORMAL: &str = "N:";
 const SKILL_PREFIX_LB: &str = "LB:";
 
+/// アイコンをalt文字列に置き換えた後のライフバースト能力の書き出し（全角・半角コロン両対応）
+static RE_LIFE_BURST_PREFIX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^【?ライフバースト】?\s*[:：]\s*").unwrap());
+
 impl CardSkill {
     fn from_string(s: String) -> Self {
         if s.starts_with(SKILL_PREFIX_LB) {
@@ -278,6 +282,7 @@ impl From<Card> for CreateCard {
         let burst = val.burst();
         let normal_skills = val.skill.get_normal_skills();
         let life_burst_skills = val.skill.get_life_burst_skills();
+        let (burst_bits, _) = detect_burst_features(&life_burst_skills.join("\n"));
         let card_number = val.no;
         let cost = convert_cost(&val.cost.value.unwrap_or("".to_string())).unwrap_or_default();
         CreateCard {
@@ -302,10 +307,11 @@ impl From<Card> for CreateCard {
                 _ => None,
             },
             burst_text: Some(life_burst_skills.join("\n")),
+            // wix_card.formatの選択肢（ビットフラグ）に合わせる
             format: match val.format {
-                Format::AllStar => 111_i32,
-                Format::KeySelection => 11_i32,
-                Format::DivaSelection => 11_i32,
+                Format::AllStar => 7_i32,
+                Format::KeySelection => 3_i32,
+                Format::DivaSelection => 1_i32,
             },
             story: val.story.value,
             rarity: Some(val.rarity),
@@ -316,7 +322,7 @@ impl From<Card> for CreateCard {
             skill_text: Some(normal_skills.join("\n")),
             feature_bits1: val.feature_bits1,
             feature_bits2: val.feature_bits2,
-            burst_bits: 0, // Note: This conversion path doesn't implement burst feature detection - analyzer path does
+            burst_bits,
             ex1: val.ex1.value,
         }
     }
@@ -397,8 +403,8 @@ impl Card {
     pub fn burst(&self) -> i32 {
         if self.card_type == CardType::Signi || self.card_type == CardType::Spell {
             match self.skill.get_life_burst_skills().is_empty() {
-                true => 1,
-                false => 2,
+                true => 2,
+                false => 1,
             }
         } else {
             0
@@ -470,9 +476,15 @@ fn parse_card_skill(source: Vec<String>) -> (Skills, HashSet<CardFeature>) {
             .split('\n')
             .map(|line| line.trim().to_string())
             .map(|line| {
-                let (line_replaced, features_detected) = rule_explain_to_feature(line);
+                let line = replace_img_with_alt(line);
+                let (line_replaced, features_detected) = rule_explain_to_feature(line.clone());
                 features.extend(features_detected);
-                line_replaced
+
+                // ライフバーストはスクレイパーと同じく置換前の本文を保持し、バースト検出にかける
+                match RE_LIFE_BURST_PREFIX.find(&line) {
+                    Some(prefix) => format!("{}{}", SKILL_PREFIX_LB, &line[prefix.end()..]),
+                    None => line_replaced,
+                }
             })
             .filter(|line| !line.is_empty()) // 空の行を除去
             .collect();
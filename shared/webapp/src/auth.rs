@@ -0,0 +1,220 @@
+//! APIキー認証
+//!
+//! 管理系のエンドポイント（`/admin_operation`、fixed_data_serverなど）を保護するミドルウェア。
+//! キーは `Authorization: Bearer <key>` または `X-API-Key: <key>` で受け取り、
+//! SHA-256ハッシュを `wix_api_key` テーブルと照合する。
+//! GET・HEAD・OPTIONSは閲覧権限（reader）、それ以外のメソッドは編集権限（editor）が必要。
+//!
+//! 既定で有効。設定の `API_AUTH_ENABLED=false`（`config.toml` では `[admin] api_auth_enabled`）で無効にすると
+//! 全リクエストを匿名の編集者として通すため、ローカル開発以外では無効にしないこと。
+
+use crate::error::ApiError;
+use config::AdminConfig;
+use axum::extract::{Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, Method};
+use axum::middleware::Next;
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+
+/// APIキーの権限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// 閲覧のみ
+    Reader,
+    /// 作成・更新・削除も可能
+    Editor,
+}
+
+impl Role {
+    /// メソッドに必要な権限
+    pub fn required_for(method: &Method) -> Self {
+        if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+            Role::Reader
+        } else {
+            Role::Editor
+        }
+    }
+
+    fn from_db(role: &str) -> Option<Self> {
+        match role {
+            "reader" => Some(Role::Reader),
+            "editor" => Some(Role::Editor),
+            _ => None,
+        }
+    }
+}
+
+/// 認証済みの呼び出し元（リクエストのextensionに入る）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    /// APIキーの名前（認証無効時は `anonymous`）
+    pub name: String,
+    pub role: Role,
+}
+
+/// APIキーのハッシュ値（`wix_api_key.key_hash` に保存する形式）
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// APIキー認証の設定とキーの照合先
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    enabled: bool,
+    pool: Arc<Pool<Postgres>>,
+}
+
+impl ApiKeyAuth {
+    pub fn new(pool: Arc<Pool<Postgres>>, enabled: bool) -> Self {
+        Self { enabled, pool }
+    }
+
+    /// 設定の `api_auth_enabled` に従って作成する
+    pub fn from_config(pool: Arc<Pool<Postgres>>, admin: &AdminConfig) -> Self {
+        let enabled = admin.api_auth_enabled;
+        if !enabled {
+            tracing::warn!("API_AUTH_ENABLED が無効のため、管理系APIは認証なしで呼び出せます");
+        }
+        Self::new(pool, enabled)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// キーを照合して呼び出し元を返す（無効・未登録のキーは `None`）
+    pub async fn authenticate(&self, key: &str) -> Result<Option<Caller>, sqlx::Error> {
+        let row: Option<(String, String)> = sqlx::query_as(
+            "UPDATE wix_api_key SET last_used_at = CURRENT_TIMESTAMP \
+             WHERE key_hash = $1 AND is_active RETURNING name, role",
+        )
+        .bind(hash_api_key(key))
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(row.and_then(|(name, role)| Role::from_db(&role).map(|role| Caller { name, role })))
+    }
+
+    /// リクエストの呼び出し元を確認し、メソッドに必要な権限があるか検証する
+    pub async fn authorize(
+        &self,
+        headers: &HeaderMap,
+        method: &Method,
+    ) -> Result<Caller, ApiError> {
+        if !self.enabled {
+            return Ok(Caller {
+                name: "anonymous".to_string(),
+                role: Role::Editor,
+            });
+        }
+
+        let key = bearer_token(headers)
+            .ok_or_else(|| ApiError::Unauthorized("APIキーが必要です".to_string()))?;
+        let caller = self
+            .authenticate(key)
+            .await?
+            .ok_or_else(|| ApiError::Unauthorized("APIキーが無効です".to_string()))?;
+
+        if caller.role < Role::required_for(method) {
+            return Err(ApiError::Forbidden(format!(
+                "{} は閲覧専用のAPIキーです",
+                caller.name
+            )));
+        }
+        Ok(caller)
+    }
+}
+
+/// リクエストヘッダーからAPIキーを取り出す
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let from_authorization = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let from_api_key = headers
+        .get("x-api-key")
+        .and_then(|value| value.to_str().ok());
+
+    from_authorization
+        .or(from_api_key)
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// `axum::middleware::from_fn_with_state(auth, require_api_key)` で使うミドルウェア
+pub async fn require_api_key(
+    State(auth): State<ApiKeyAuth>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let caller = auth.authorize(request.headers(), request.method()).await?;
+    request.extensions_mut().insert(caller);
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use sqlx::postgres::PgPoolOptions;
+
+    fn auth(enabled: bool) -> ApiKeyAuth {
+        let pool = PgPoolOptions::new()
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+        ApiKeyAuth::new(Arc::new(pool), enabled)
+    }
+
+    #[test]
+    fn test_required_role_follows_method() {
+        assert_eq!(Role::required_for(&Method::GET), Role::Reader);
+        assert_eq!(Role::required_for(&Method::OPTIONS), Role::Reader);
+        assert_eq!(Role::required_for(&Method::POST), Role::Editor);
+        assert_eq!(Role::required_for(&Method::DELETE), Role::Editor);
+        assert!(Role::Editor > Role::Reader);
+    }
+
+    #[test]
+    fn test_bearer_token_accepts_both_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert("x-api-key", HeaderValue::from_static("key-b"));
+        assert_eq!(bearer_token(&headers), Some("key-b"));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer key-a"));
+        assert_eq!(bearer_token(&headers), Some("key-a"));
+    }
+
+    #[test]
+    fn test_hash_api_key_is_sha256_hex() {
+        assert_eq!(
+            hash_api_key("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_auth_allows_anonymous_editor() {
+        let caller = auth(false)
+            .authorize(&HeaderMap::new(), &Method::DELETE)
+            .await
+            .unwrap();
+        assert_eq!(caller.role, Role::Editor);
+    }
+
+    #[tokio::test]
+    async fn test_missing_key_is_unauthorized() {
+        let error = auth(true)
+            .authorize(&HeaderMap::new(), &Method::GET)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::Unauthorized(_)));
+    }
+}
@@ -0,0 +1,217 @@
+//! APIエラーレスポンス
+//!
+//! JSON APIのハンドラが返す共通のエラー型。
+//! クライアントには `{"code", "message", "details"}` のJSONと対応するステータスコードを返し、
+//! データベースエラーなど内部の詳細はサーバーログにのみ出力する。
+
+use crate::repositories::CardSearchError;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::http::header::WWW_AUTHENTICATE;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// APIエラー
+#[derive(Debug, Error)]
+pub enum ApiError {
+    /// 対象が存在しない（404）
+    #[error("{0}")]
+    NotFound(String),
+    /// APIキーがない・無効（401）
+    #[error("{0}")]
+    Unauthorized(String),
+    /// APIキーの権限が足りない（403）
+    #[error("{0}")]
+    Forbidden(String),
+    /// リクエストの内容が不正（400）
+    #[error("{message}")]
+    Validation {
+        message: String,
+        details: Option<Value>,
+    },
+    /// データベースの応答待ちがタイムアウトした（503）
+    #[error("データベースの応答がタイムアウトしました")]
+    Timeout,
+    /// その他のデータベースエラー（500）
+    #[error("データベースエラー: {0}")]
+    Database(sqlx::Error),
+    /// その他の内部エラー（500）
+    #[error("内部エラー: {0}")]
+    Internal(String),
+}
+
+/// エラーレスポンスのJSON
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiErrorBody {
+    /// 機械判定用のエラーコード（`not_found` / `unauthorized` / `forbidden` / `validation_error` / `timeout` / `database_error` / `internal_error`）
+    pub code: &'static str,
+    /// 利用者向けのメッセージ
+    pub message: String,
+    /// 補足情報（不正なパラメータなど）
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound(message.into())
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        ApiError::Validation {
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::Validation { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Timeout => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Database(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::Validation { .. } => "validation_error",
+            ApiError::Timeout => "timeout",
+            ApiError::Database(_) => "database_error",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// クライアントに返す本文（内部エラーの詳細は含めない）
+    pub fn body(&self) -> ApiErrorBody {
+        let (message, details) = match self {
+            ApiError::NotFound(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message) => (message.clone(), None),
+            ApiError::Validation { message, details } => (message.clone(), details.clone()),
+            ApiError::Timeout => (self.to_string(), None),
+            ApiError::Database(_) => ("データベースエラーが発生しました".to_string(), None),
+            ApiError::Internal(_) => ("内部エラーが発生しました".to_string(), None),
+        };
+        ApiErrorBody {
+            code: self.code(),
+            message,
+            details,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if matches!(self, ApiError::Timeout) {
+            metrics::counter!(crate::metrics::DB_TIMEOUTS_TOTAL).increment(1);
+        }
+        if self.status().is_server_error() {
+            tracing::error!(error = ?self, "APIエラー");
+        }
+        let mut response = (self.status(), Json(self.body())).into_response();
+        if matches!(self, ApiError::Unauthorized(_)) {
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::RowNotFound => ApiError::not_found("対象が見つかりません"),
+            sqlx::Error::PoolTimedOut => ApiError::Timeout,
+            e => ApiError::Database(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Internal(e.to_string())
+    }
+}
+
+impl From<CardSearchError> for ApiError {
+    fn from(e: CardSearchError) -> Self {
+        ApiError::validation(e.to_string())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::Validation {
+            message: "クエリパラメータが不正です".to_string(),
+            details: Some(Value::String(rejection.body_text())),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::Validation {
+            message: "リクエスト本文が不正です".to_string(),
+            details: Some(Value::String(rejection.body_text())),
+        }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::Validation {
+            message: "パスパラメータが不正です".to_string(),
+            details: Some(Value::String(rejection.body_text())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+
+    async fn response_json(error: ApiError) -> (StatusCode, Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_sqlx_errors_are_mapped_to_statuses() {
+        let (status, body) = response_json(sqlx::Error::RowNotFound.into()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+
+        let (status, body) = response_json(sqlx::Error::PoolTimedOut.into()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "timeout");
+
+        let (status, body) = response_json(sqlx::Error::PoolClosed.into()).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "database_error");
+        assert_eq!(body["message"], "データベースエラーが発生しました");
+        assert_eq!(body["details"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_validation_error_keeps_message() {
+        let error: ApiError = CardSearchError::UnknownColor("紫".to_string()).into();
+        let (status, body) = response_json(error).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "validation_error");
+        assert_eq!(body["message"], "不明な色: 紫");
+    }
+}
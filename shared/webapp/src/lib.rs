@@ -1,5 +1,11 @@
 pub mod analyze;
+pub mod auth;
+pub mod error;
+pub mod logging;
+pub mod metrics;
+pub mod readiness;
 pub mod repositories;
 pub mod routers;
+pub mod snapshot;
 pub mod state;
 pub mod tokiort;
@@ -0,0 +1,35 @@
+//! ログ出力の初期化
+//!
+//! 出力レベルは `RUST_LOG`（未設定なら各バイナリの既定値）で指定する。
+//! 設定で `LOG_FORMAT=json`（`[log] format = "json"`）にすると1行1JSONで出力し、
+//! リクエストIDなど現在のスパンのフィールドも各行に含める。
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+pub use config::LogFormat;
+
+/// tracingのサブスクライバーを登録する
+///
+/// # 引数
+///
+/// * `default_filter` - `RUST_LOG` 未設定時のフィルタ（例: `"server=info,tower_http=info"`）
+/// * `format` - 出力形式（`Config::log`）
+pub fn init_tracing(default_filter: &str, format: LogFormat) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match format {
+        LogFormat::Json => registry
+            .with(
+                fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false),
+            )
+            .init(),
+        LogFormat::Text => registry.with(fmt::layer()).init(),
+    }
+}
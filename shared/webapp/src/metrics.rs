@@ -0,0 +1,95 @@
+//! Prometheusメトリクス
+//!
+//! server・fixed_data_serverの `/metrics` で共通して使う。
+//! HTTPリクエストの件数と所要時間はミドルウェアで記録し、
+//! DBプールの使用状況などはスクレイプ時に各バイナリがゲージを更新してから出力する。
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::{Pool, Postgres};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// HTTPリクエスト数（method・route・status別）
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+/// HTTPリクエストの所要時間（秒、method・route別のヒストグラム）
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+/// DBの応答待ちがタイムアウトした回数
+pub const DB_TIMEOUTS_TOTAL: &str = "db_timeouts_total";
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// メトリクスの記録先（初回呼び出しでグローバルに登録する）
+pub fn prometheus_handle() -> PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(
+                    Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+                    LATENCY_BUCKETS,
+                )
+                .expect("バケットは空でない")
+                .install_recorder()
+                .expect("メトリクスのレコーダーは一度だけ登録する")
+        })
+        .clone()
+}
+
+/// リクエスト数と所要時間を記録するミドルウェア（`Router::layer(from_fn(track_http_metrics))`）
+///
+/// routeラベルにはマッチしたルート定義（`/api/card/{code}` など）を使い、
+/// 静的ファイルなどルートにマッチしないリクエストは `<fallback>` にまとめる
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "<fallback>".to_string());
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!(
+        HTTP_REQUESTS_TOTAL,
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status
+    )
+    .increment(1);
+    metrics::histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        "method" => method,
+        "route" => route
+    )
+    .record(started.elapsed().as_secs_f64());
+
+    response
+}
+
+/// DBプールの使用状況をゲージに反映する
+///
+/// `db_pool_connections{state="in_use"}` が `db_pool_max_connections` に張り付いたら枯渇している
+pub fn record_pool(pool: &Pool<Postgres>) {
+    let size = pool.size() as f64;
+    let idle = pool.num_idle() as f64;
+    metrics::gauge!("db_pool_connections", "state" => "idle").set(idle);
+    metrics::gauge!("db_pool_connections", "state" => "in_use").set(size - idle);
+    metrics::gauge!("db_pool_max_connections").set(pool.options().get_max_connections() as f64);
+}
+
+/// 現在のメトリクスをPrometheusのテキスト形式で返す
+pub fn render() -> Response {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        prometheus_handle().render(),
+    )
+        .into_response()
+}
@@ -0,0 +1,232 @@
+//! レディネスチェック（`/readyz`）
+//!
+//! デプロイ後にトラフィックを流してよいかを判定する。`/healthz` がプロセスの生存だけを見るのに対し、
+//! DBへの接続、カードデータの有無と参照整合性、静的データ（datapack）とDBの一致までを確認する。
+//!
+//! datapackのバージョンは `cargo make static` が `datapack/src/gen/version.json` に書き出し、
+//! サーバーは設定の `DATAPACK_VERSION_FILE`（既定は起動ディレクトリからの相対パス）で読む。
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+use std::path::Path;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// datapackに書き出したカードデータのバージョン
+///
+/// `fingerprint` はdatapackの元になる行（Diva形式のカードとフィーチャー修正）の内容のハッシュで、
+/// どちらかが1行でも変われば値が変わる
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DatapackVersion {
+    pub card_count: i64,
+    pub fingerprint: String,
+}
+
+impl DatapackVersion {
+    /// DBの現在の内容から計算する
+    pub async fn current(pool: &Pool<Postgres>) -> Result<Self, sqlx::Error> {
+        let (card_count, fingerprint): (i64, String) = with_timeout(
+            sqlx::query_as(
+                r#"
+                SELECT
+                    (SELECT COUNT(*) FROM wix_card WHERE format = 1),
+                    md5(
+                        COALESCE((SELECT string_agg(c::text, E'\n' ORDER BY c.id)
+                                  FROM wix_card c WHERE c.format = 1), '')
+                        || E'\n--\n' ||
+                        COALESCE((SELECT string_agg(o::text, E'\n' ORDER BY o.pronunciation)
+                                  FROM wix_card_feature_override o), '')
+                    )
+                "#,
+            )
+            .fetch_one(pool),
+        )
+        .await?;
+        Ok(Self {
+            card_count,
+            fingerprint,
+        })
+    }
+
+    /// 書き出し済みのバージョンファイルを読む
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{} を読めません: {}", path.display(), e))?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("{} の形式が不正です: {}", path.display(), e))
+    }
+}
+
+/// 個々のチェック結果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadinessCheck {
+    /// `database`・`cards_present`・`card_references`・`datapack_version`
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// `/readyz` のレスポンス
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReadinessReport {
+    /// すべてのチェックが通ったか
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
+
+impl ReadinessReport {
+    fn new(checks: Vec<ReadinessCheck>) -> Self {
+        Self {
+            ready: checks.iter().all(|check| check.ok),
+            checks,
+        }
+    }
+}
+
+fn check(name: &'static str, ok: bool, detail: impl Into<String>) -> ReadinessCheck {
+    ReadinessCheck {
+        name,
+        ok,
+        detail: detail.into(),
+    }
+}
+
+/// すべてのチェックを実行する
+///
+/// DBに接続できない場合は残りのチェックを行わず、未実施として失敗扱いにする
+pub async fn check_readiness(pool: &Pool<Postgres>, version_file: &Path) -> ReadinessReport {
+    if let Err(e) = with_timeout(sqlx::query("SELECT 1").execute(pool)).await {
+        let skipped = "DBに接続できないため未実施";
+        return ReadinessReport::new(vec![
+            check("database", false, e.to_string()),
+            check("cards_present", false, skipped),
+            check("card_references", false, skipped),
+            check("datapack_version", false, skipped),
+        ]);
+    }
+
+    let mut checks = vec![check("database", true, "接続できます")];
+
+    checks.push(
+        match with_timeout(
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM wix_card").fetch_one(pool),
+        )
+        .await
+        {
+            Ok(0) => check("cards_present", false, "wix_card が空です"),
+            Ok(count) => check("cards_present", true, format!("{}件", count)),
+            Err(e) => check("cards_present", false, e.to_string()),
+        },
+    );
+
+    checks.push(match orphan_references(pool).await {
+        Ok((0, 0)) => check("card_references", true, "すべての商品・カード種類が存在します"),
+        Ok((products, card_types)) => check(
+            "card_references",
+            false,
+            format!(
+                "存在しない商品を参照するカードが{}件、存在しないカード種類を参照するカードが{}件あります",
+                products, card_types
+            ),
+        ),
+        Err(e) => check("card_references", false, e.to_string()),
+    });
+
+    checks.push(match DatapackVersion::load(version_file) {
+        Err(e) => check("datapack_version", false, e),
+        Ok(generated) => match DatapackVersion::current(pool).await {
+            Ok(current) if current == generated => check(
+                "datapack_version",
+                true,
+                format!("{} ({}件)", current.fingerprint, current.card_count),
+            ),
+            Ok(current) => check(
+                "datapack_version",
+                false,
+                format!(
+                    "datapackは {} ({}件)、DBは {} ({}件) です。cargo make static で再生成してください",
+                    generated.fingerprint,
+                    generated.card_count,
+                    current.fingerprint,
+                    current.card_count
+                ),
+            ),
+            Err(e) => check("datapack_version", false, e.to_string()),
+        },
+    });
+
+    ReadinessReport::new(checks)
+}
+
+/// 存在しない商品・カード種類を参照しているカードの件数
+async fn orphan_references(pool: &Pool<Postgres>) -> Result<(i64, i64), sqlx::Error> {
+    with_timeout(
+        sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE p.id IS NULL),
+                COUNT(*) FILTER (WHERE t.id IS NULL)
+            FROM wix_card c
+            LEFT JOIN wix_product p ON p.id = c.product
+            LEFT JOIN wix_cardtype t ON t.id = c.card_type
+            "#,
+        )
+        .fetch_one(pool),
+    )
+    .await
+}
+
+async fn with_timeout<T>(
+    query: impl std::future::Future<Output = Result<T, sqlx::Error>>,
+) -> Result<T, sqlx::Error> {
+    tokio::time::timeout(QUERY_TIMEOUT, query)
+        .await
+        .map_err(|_| sqlx::Error::PoolTimedOut)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+
+    #[tokio::test]
+    async fn test_unreachable_database_is_not_ready() {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/postgres")
+            .unwrap();
+
+        let report = check_readiness(&pool, Path::new("/nonexistent/version.json")).await;
+        assert!(!report.ready);
+        let names: Vec<_> = report.checks.iter().map(|check| check.name).collect();
+        assert_eq!(
+            names,
+            [
+                "database",
+                "cards_present",
+                "card_references",
+                "datapack_version"
+            ]
+        );
+        assert!(report.checks.iter().all(|check| !check.ok));
+    }
+
+    #[test]
+    fn test_load_version_file() {
+        let path =
+            std::env::temp_dir().join(format!("datapack_version_{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"card_count": 3, "fingerprint": "abc"}"#).unwrap();
+        assert_eq!(
+            DatapackVersion::load(&path).unwrap(),
+            DatapackVersion {
+                card_count: 3,
+                fingerprint: "abc".to_string()
+            }
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(DatapackVersion::load(&path).is_err());
+    }
+}
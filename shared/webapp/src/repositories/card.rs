@@ -1,6 +1,10 @@
 use crate::analyze::wixoss;
+use crate::repositories::{CardSearch, FeatureOverride, OverrideMode};
+use feature::{BurstFeature, CardFeature};
 use models::card::{Card, CardDb, CreateCard};
+use models::klass::klass_label;
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
 use sqlx::{Pool, Postgres, Row};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
@@ -8,6 +12,7 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
+use utoipa::ToSchema;
 
 pub trait ICardRepository {
     fn get_all<'a>(&'a self) -> Pin<Box<dyn Future<Output = Vec<Card>> + Send + 'a>>;
@@ -39,6 +44,158 @@ impl CardRepository {
             .map_err(|_| sqlx::Error::PoolTimedOut)?
         })
     }
+
+    /// wix_cardのデータバージョン（一覧キャッシュの無効化判定に使う）
+    pub async fn data_version(&self) -> Result<i64, sqlx::Error> {
+        crate::snapshot::data_version(&self.db_connector, &["wix_card"]).await
+    }
+
+    /// wix_cardの件数
+    pub async fn count(&self) -> Result<i64, sqlx::Error> {
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            sqlx::query_scalar("SELECT COUNT(*) FROM wix_card").fetch_one(&*self.db_connector),
+        )
+        .await
+        .map_err(|_| sqlx::Error::PoolTimedOut)?
+    }
+
+    /// 検索条件に一致するカードを1ページ分取得する
+    pub async fn search(&self, search: &CardSearch) -> Result<CardSearchPage, sqlx::Error> {
+        tokio::time::timeout(Duration::from_secs(5), self.fetch_search_page(search))
+            .await
+            .map_err(|_| sqlx::Error::PoolTimedOut)?
+    }
+
+    async fn fetch_search_page(&self, search: &CardSearch) -> Result<CardSearchPage, sqlx::Error> {
+        let total: i64 = search
+            .count_query()
+            .build_query_scalar()
+            .fetch_one(&*self.db_connector)
+            .await?;
+
+        let mut cards: Vec<CardDb> = search
+            .page_query()
+            .build_query_as()
+            .fetch_all(&*self.db_connector)
+            .await?;
+
+        let next_cursor = if cards.len() as i64 > search.limit {
+            cards.truncate(search.limit as usize);
+            cards.last().map(|card| search.cursor_after(card).encode())
+        } else {
+            None
+        };
+
+        Ok(CardSearchPage {
+            cards,
+            total,
+            next_cursor,
+        })
+    }
+
+    /// カード番号・ID・読みのいずれかで1枚を特定し、関連を解決して返す
+    ///
+    /// カード番号、（数値なら）ID、読みの順に探し、読みで複数ヒットした場合はID順で先頭を採用する
+    pub async fn get_detail(&self, key: &str) -> Result<Option<CardDetail>, sqlx::Error> {
+        tokio::time::timeout(Duration::from_secs(5), self.fetch_detail(key))
+            .await
+            .map_err(|_| sqlx::Error::PoolTimedOut)?
+    }
+
+    async fn fetch_detail(&self, key: &str) -> Result<Option<CardDetail>, sqlx::Error> {
+        let mut card = sqlx::query_as::<_, CardDb>("SELECT * FROM wix_card WHERE code = $1")
+            .bind(key)
+            .fetch_optional(&*self.db_connector)
+            .await?;
+
+        if card.is_none() {
+            if let Ok(id) = key.parse::<i64>() {
+                card = sqlx::query_as::<_, CardDb>("SELECT * FROM wix_card WHERE id = $1")
+                    .bind(id)
+                    .fetch_optional(&*self.db_connector)
+                    .await?;
+            }
+        }
+
+        if card.is_none() {
+            card = sqlx::query_as::<_, CardDb>(
+                "SELECT * FROM wix_card WHERE pronunciation = $1 ORDER BY id LIMIT 1",
+            )
+            .bind(key)
+            .fetch_optional(&*self.db_connector)
+            .await?;
+        }
+
+        let Some(card) = card else {
+            return Ok(None);
+        };
+
+        let product: Option<(String, String)> =
+            sqlx::query_as("SELECT name, product_code FROM wix_product WHERE id = $1")
+                .bind(card.product as i64)
+                .fetch_optional(&*self.db_connector)
+                .await?;
+
+        let card_type_name: Option<String> =
+            sqlx::query_scalar("SELECT name FROM wix_cardtype WHERE id = $1")
+                .bind(card.card_type as i64)
+                .fetch_optional(&*self.db_connector)
+                .await?;
+
+        let klasses: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT k.cat1, k.cat2, k.cat3
+            FROM wix_card_klass ck
+            JOIN wix_klass k ON k.id = ck.klass_id
+            WHERE ck.card_id = $1
+            ORDER BY k.sort_asc, k.id
+            "#,
+        )
+        .bind(card.id)
+        .fetch_all(&*self.db_connector)
+        .await?;
+
+        let siblings = sqlx::query_as::<_, CardPrinting>(
+            r#"
+            SELECT c.id, c.code, c.name, c.product, p.name AS product_name, c.rarity
+            FROM wix_card c
+            LEFT JOIN wix_product p ON p.id = c.product
+            WHERE c.pronunciation = $1 AND c.id <> $2
+            ORDER BY c.id
+            "#,
+        )
+        .bind(&card.pronunciation)
+        .bind(card.id)
+        .fetch_all(&*self.db_connector)
+        .await?;
+
+        let (product_name, product_code) = product.unzip();
+        Ok(Some(CardDetail {
+            features: CardFeature::from_bits(card.feature_bits1, card.feature_bits2)
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            burst_features: BurstFeature::from_bits(card.burst_bits)
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            colors: color::from_bits(card.color)
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            klasses: klasses
+                .into_iter()
+                .map(|(cat1, cat2, cat3)| klass_label(&cat1, cat2.as_deref(), cat3.as_deref()))
+                .collect(),
+            product_name,
+            product_code,
+            card_type_name,
+            siblings,
+            card,
+        }))
+    }
+
     pub async fn create_card_full(&self, source: wixoss::Card) -> Result<Card, sqlx::Error> {
         let cc: CreateCard = source.into();
         let res = self.upsert(cc).await;
@@ -185,6 +342,47 @@ impl ICardRepository for CardRepository {
     // }
 }
 
+/// 関連を解決したカード詳細
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CardDetail {
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub card: CardDb,
+    pub product_name: Option<String>,
+    pub product_code: Option<String>,
+    pub card_type_name: Option<String>,
+    /// `cat1:cat2/cat3` 形式のクラス名
+    pub klasses: Vec<String>,
+    /// 特徴ビットから復元したフィーチャ名
+    pub features: Vec<String>,
+    /// バーストビットから復元したバーストフィーチャ名
+    pub burst_features: Vec<String>,
+    pub colors: Vec<String>,
+    /// 同じ読みを持つ別の収録（再録・パラレル）
+    pub siblings: Vec<CardPrinting>,
+}
+
+/// 別収録の概要
+#[derive(Debug, Serialize, sqlx::FromRow, ToSchema)]
+pub struct CardPrinting {
+    pub id: i64,
+    pub code: String,
+    pub name: String,
+    pub product: i32,
+    pub product_name: Option<String>,
+    pub rarity: Option<String>,
+}
+
+/// 検索結果の1ページ
+#[derive(Debug)]
+pub struct CardSearchPage {
+    pub cards: Vec<CardDb>,
+    /// カーソルに関係なく条件に一致する総件数
+    pub total: i64,
+    /// 次ページがある場合のカーソル
+    pub next_cursor: Option<String>,
+}
+
 pub trait StaticCodeGenerator {
     fn code(&self) -> impl std::future::Future<Output = String> + Send;
     fn get_all_as_code(&self) -> impl std::future::Future<Output = Vec<String>> + Send;
@@ -211,10 +409,10 @@ impl StaticCodeGenerator for CardRepository {
             .await
             .unwrap(); // エラー処理は適宜修正してください
 
-        // CardFeatureOverrideを取得
+        // CardFeatureOverrideを取得（フィーチャーは列挙子名で保存されていて、ルールベースの結果に重ねて適用する）
         let overrides = sqlx::query(
             r#"
-            SELECT pronunciation, fixed_bits1, fixed_bits2, fixed_burst_bits
+            SELECT pronunciation, mode, features, burst_features, removed_features, removed_burst_features
             FROM wix_card_feature_override
             "#
         )
@@ -223,14 +421,30 @@ impl StaticCodeGenerator for CardRepository {
         .unwrap();
 
         // Override mapを作成
-        let override_map: HashMap<String, (i64, i64, i64)> = overrides
+        let override_map: HashMap<String, FeatureOverride> = overrides
             .into_iter()
             .map(|row| {
                 let pronunciation: String = row.get("pronunciation");
-                let fixed_bits1: i64 = row.get("fixed_bits1");
-                let fixed_bits2: i64 = row.get("fixed_bits2");
-                let fixed_burst_bits: i64 = row.get("fixed_burst_bits");
-                (pronunciation, (fixed_bits1, fixed_bits2, fixed_burst_bits))
+                let mode: String = row.get("mode");
+                let Json(features): Json<Vec<String>> = row.get("features");
+                let Json(burst_features): Json<Vec<String>> = row.get("burst_features");
+                let Json(removed_features): Json<Vec<String>> = row.get("removed_features");
+                let Json(removed_burst_features): Json<Vec<String>> = row.get("removed_burst_features");
+                let feature_override = FeatureOverride {
+                    mode: OverrideMode::try_from(mode).unwrap_or_else(|e| {
+                        tracing::warn!(%pronunciation, error = %e, "置き換えとして扱います");
+                        OverrideMode::Replace
+                    }),
+                    features,
+                    burst_features,
+                    removed_features,
+                    removed_burst_features,
+                };
+                // 定義から削除された名前は無視する
+                for name in feature_override.unknown_names() {
+                    tracing::warn!(%pronunciation, feature = %name, "オーバーライドに未定義のフィーチャーがあります");
+                }
+                (pronunciation, feature_override)
             })
             .collect();
 
@@ -291,9 +505,14 @@ impl StaticCodeGenerator for CardRepository {
                 let card_obj = Card::from(card);
 
                 // Apply CardFeatureOverride if exists
-                let final_card_obj = if let Some((fixed_bits1, fixed_bits2, fixed_burst_bits)) = 
+                let final_card_obj = if let Some(feature_override) =
                     override_map.get(&card_obj.pronunciation) {
-                    card_obj.with_feature_override(*fixed_bits1, *fixed_bits2, *fixed_burst_bits)
+                    let (bits1, bits2, burst_bits) = feature_override.apply(
+                        card_obj.feature_bits1,
+                        card_obj.feature_bits2,
+                        card_obj.burst_bits,
+                    );
+                    card_obj.with_feature_override(bits1, bits2, burst_bits)
                 } else {
                     card_obj
                 };
@@ -359,3 +578,54 @@ impl OnlyCardNameRepository {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_detail_serializes_card_fields_at_top_level() {
+        let card = CardDb {
+            id: 1,
+            name: "テスト".to_string(),
+            code: "WXDi-P01-001".to_string(),
+            pronunciation: "てすと".to_string(),
+            color: 2,
+            cost: None,
+            level: Some(1),
+            limit: None,
+            limit_ex: None,
+            product: 1,
+            card_type: 5,
+            power: Some("5000".to_string()),
+            has_burst: 2,
+            skill_text: None,
+            burst_text: None,
+            format: 7,
+            story: None,
+            rarity: None,
+            timing: None,
+            url: None,
+            feature_bits1: 0,
+            feature_bits2: 0,
+            burst_bits: 0,
+            ex1: None,
+        };
+        let detail = CardDetail {
+            card,
+            product_name: None,
+            product_code: None,
+            card_type_name: Some("シグニ".to_string()),
+            klasses: vec![klass_label("奏羅", Some("宇宙"), Some(""))],
+            features: Vec::new(),
+            burst_features: Vec::new(),
+            colors: vec!["白".to_string()],
+            siblings: Vec::new(),
+        };
+
+        let json = serde_json::to_value(&detail).unwrap();
+        assert_eq!(json["code"], "WXDi-P01-001");
+        assert_eq!(json["card_type_name"], "シグニ");
+        assert_eq!(json["klasses"][0], "奏羅:宇宙");
+    }
+}
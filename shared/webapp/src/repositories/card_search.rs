@@ -0,0 +1,498 @@
+//! カード検索
+//!
+//! WASM UIと同じ絞り込み条件（色・フィーチャ・カード種類・商品・レベル・パワー・クラス・テキスト）を
+//! wix_cardに対するSQLとして組み立てる。一覧は `(ソートキー, id)` のキーセットカーソルでページングする。
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use color::Color;
+use feature::feature::HashSetToBits;
+use feature::labels::FEATURE_LABELS;
+use models::card::CardDb;
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use std::collections::HashSet;
+use thiserror::Error;
+use utoipa::{IntoParams, ToSchema};
+
+/// 1ページの既定件数
+pub const DEFAULT_SEARCH_LIMIT: i64 = 50;
+/// 1ページの最大件数
+pub const MAX_SEARCH_LIMIT: i64 = 200;
+
+/// パワーは文字列で保存されているため、数値のものだけを比較対象にする
+const POWER_EXPR: &str = "CASE WHEN c.power ~ '^[0-9]+$' THEN c.power::bigint END";
+
+/// 並び順のキー
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CardSortKey {
+    #[default]
+    Code,
+    Name,
+    Power,
+    Level,
+    Id,
+}
+
+impl CardSortKey {
+    /// ORDER BYに使う式（NULLはカーソル比較のため -1 に寄せる）
+    fn expr(&self) -> String {
+        match self {
+            CardSortKey::Code => "c.code".to_string(),
+            CardSortKey::Name => "c.name".to_string(),
+            CardSortKey::Power => format!("COALESCE({}, -1)", POWER_EXPR),
+            CardSortKey::Level => "COALESCE(c.level, -1)::bigint".to_string(),
+            CardSortKey::Id => "c.id".to_string(),
+        }
+    }
+
+    fn key_of(&self, card: &CardDb) -> CursorKey {
+        match self {
+            CardSortKey::Code => CursorKey::Text(card.code.clone()),
+            CardSortKey::Name => CursorKey::Text(card.name.clone()),
+            CardSortKey::Power => CursorKey::Int(
+                card.power
+                    .as_deref()
+                    .filter(|power| power.chars().all(|c| c.is_ascii_digit()))
+                    .and_then(|power| power.parse().ok())
+                    .unwrap_or(-1),
+            ),
+            CardSortKey::Level => CursorKey::Int(card.level.map(i64::from).unwrap_or(-1)),
+            CardSortKey::Id => CursorKey::Int(card.id),
+        }
+    }
+}
+
+/// 昇順・降順
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// `/api/card/search` のクエリ文字列
+///
+/// 複数値の項目はカンマ区切りで指定する（例: `colors=白,青&features=バニッシュ,ドロー`）
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CardSearchParams {
+    /// 色名（白・青・赤・黒・緑・無）。指定した色をすべて含むカード
+    pub colors: Option<String>,
+    /// フィーチャ名。指定したフィーチャをすべて持つカード
+    pub features: Option<String>,
+    /// カード種類ID（wix_cardtype）。いずれかに該当するカード
+    pub card_types: Option<String>,
+    /// 商品ID（wix_product）。いずれかに該当するカード
+    pub products: Option<String>,
+    /// レベル。いずれかに該当するカード
+    pub levels: Option<String>,
+    pub power_min: Option<i64>,
+    pub power_max: Option<i64>,
+    /// クラスID（wix_klass）。いずれかに該当するカード
+    pub klass: Option<String>,
+    /// 空白区切りのキーワード。名前・読み・カード番号・テキストのいずれかにすべて含まれるカード
+    pub text: Option<String>,
+    #[serde(default)]
+    pub sort: CardSortKey,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub limit: Option<i64>,
+    /// 前ページのレスポンスの `next_cursor`
+    pub cursor: Option<String>,
+}
+
+/// 検索条件の検証エラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CardSearchError {
+    #[error("不明な色: {0}")]
+    UnknownColor(String),
+    #[error("不明なフィーチャ: {0}")]
+    UnknownFeature(String),
+    #[error("{field} の値が数値ではありません: {value}")]
+    InvalidNumber { field: &'static str, value: String },
+    #[error("カーソルが不正です")]
+    InvalidCursor,
+    #[error("カーソルのソートキーが一致しません")]
+    CursorSortMismatch,
+}
+
+/// カーソルに入れるソートキーの値
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CursorKey {
+    Int(i64),
+    Text(String),
+}
+
+/// 前ページ最後の行の位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchCursor {
+    pub key: CursorKey,
+    pub id: i64,
+}
+
+impl SearchCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, CardSearchError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|_| CardSearchError::InvalidCursor)?;
+        serde_json::from_slice(&bytes).map_err(|_| CardSearchError::InvalidCursor)
+    }
+}
+
+/// 検証済みの検索条件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CardSearch {
+    pub color_bits: i32,
+    pub feature_bits1: i64,
+    pub feature_bits2: i64,
+    pub card_types: Vec<i32>,
+    pub products: Vec<i32>,
+    pub levels: Vec<i32>,
+    pub power_min: Option<i64>,
+    pub power_max: Option<i64>,
+    pub klass_ids: Vec<i64>,
+    pub keywords: Vec<String>,
+    pub sort: CardSortKey,
+    pub order: SortOrder,
+    pub limit: i64,
+    pub cursor: Option<SearchCursor>,
+}
+
+impl TryFrom<CardSearchParams> for CardSearch {
+    type Error = CardSearchError;
+
+    fn try_from(params: CardSearchParams) -> Result<Self, Self::Error> {
+        let mut color_bits = 0;
+        for name in split_list(&params.colors) {
+            match Color::from(name) {
+                Color::Unknown => return Err(CardSearchError::UnknownColor(name.to_string())),
+                color => color_bits |= color.to_bit(),
+            }
+        }
+
+        let features = split_list(&params.features)
+            .map(|name| {
+                FEATURE_LABELS
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| CardSearchError::UnknownFeature(name.to_string()))
+            })
+            .collect::<Result<HashSet<_>, _>>()?;
+        let (feature_bits1, feature_bits2) = if features.is_empty() {
+            (0, 0)
+        } else {
+            features.to_bits()
+        };
+
+        let cursor = params
+            .cursor
+            .as_deref()
+            .map(SearchCursor::decode)
+            .transpose()?;
+        if let Some(cursor) = &cursor {
+            let expects_text = matches!(params.sort, CardSortKey::Code | CardSortKey::Name);
+            if expects_text != matches!(cursor.key, CursorKey::Text(_)) {
+                return Err(CardSearchError::CursorSortMismatch);
+            }
+        }
+
+        Ok(Self {
+            color_bits,
+            feature_bits1,
+            feature_bits2,
+            card_types: parse_numbers(&params.card_types, "card_types")?,
+            products: parse_numbers(&params.products, "products")?,
+            levels: parse_numbers(&params.levels, "levels")?,
+            power_min: params.power_min,
+            power_max: params.power_max,
+            klass_ids: parse_numbers(&params.klass, "klass")?,
+            keywords: params
+                .text
+                .as_deref()
+                .unwrap_or_default()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            sort: params.sort,
+            order: params.order,
+            limit: params
+                .limit
+                .unwrap_or(DEFAULT_SEARCH_LIMIT)
+                .clamp(1, MAX_SEARCH_LIMIT),
+            cursor,
+        })
+    }
+}
+
+impl CardSearch {
+    /// 条件に一致する件数を数えるクエリ（カーソルは無視する）
+    pub fn count_query(&self) -> QueryBuilder<'_, Postgres> {
+        let mut query = QueryBuilder::new("SELECT COUNT(*) FROM wix_card c WHERE TRUE");
+        self.push_filters(&mut query);
+        query
+    }
+
+    /// 1ページ分を取得するクエリ
+    ///
+    /// 次ページの有無を判定するため `limit + 1` 件を取得する
+    pub fn page_query(&self) -> QueryBuilder<'_, Postgres> {
+        let mut query = QueryBuilder::new("SELECT c.* FROM wix_card c WHERE TRUE");
+        self.push_filters(&mut query);
+
+        let sort_expr = self.sort.expr();
+        let (comparison, direction) = match self.order {
+            SortOrder::Asc => (">", "ASC"),
+            SortOrder::Desc => ("<", "DESC"),
+        };
+
+        if let Some(cursor) = &self.cursor {
+            query.push(format!(" AND ({}, c.id) {} (", sort_expr, comparison));
+            match &cursor.key {
+                CursorKey::Int(key) => query.push_bind(*key),
+                CursorKey::Text(key) => query.push_bind(key.as_str()),
+            };
+            query.push(", ").push_bind(cursor.id).push(")");
+        }
+
+        query.push(format!(
+            " ORDER BY {} {}, c.id {} LIMIT ",
+            sort_expr, direction, direction
+        ));
+        query.push_bind(self.limit + 1);
+        query
+    }
+
+    /// 取得した行の位置を次ページのカーソルにする
+    pub fn cursor_after(&self, card: &CardDb) -> SearchCursor {
+        SearchCursor {
+            key: self.sort.key_of(card),
+            id: card.id,
+        }
+    }
+
+    fn push_filters<'a>(&'a self, query: &mut QueryBuilder<'a, Postgres>) {
+        if self.color_bits != 0 {
+            query
+                .push(" AND (c.color & ")
+                .push_bind(self.color_bits)
+                .push(") = ")
+                .push_bind(self.color_bits);
+        }
+
+        if self.feature_bits1 != 0 {
+            query
+                .push(" AND (c.feature_bits1 & ")
+                .push_bind(self.feature_bits1)
+                .push(") = ")
+                .push_bind(self.feature_bits1);
+        }
+        if self.feature_bits2 != 0 {
+            query
+                .push(" AND (c.feature_bits2 & ")
+                .push_bind(self.feature_bits2)
+                .push(") = ")
+                .push_bind(self.feature_bits2);
+        }
+
+        if !self.card_types.is_empty() {
+            query
+                .push(" AND c.card_type = ANY(")
+                .push_bind(&self.card_types)
+                .push(")");
+        }
+        if !self.products.is_empty() {
+            query
+                .push(" AND c.product = ANY(")
+                .push_bind(&self.products)
+                .push(")");
+        }
+        if !self.levels.is_empty() {
+            query
+                .push(" AND c.level = ANY(")
+                .push_bind(&self.levels)
+                .push(")");
+        }
+
+        if let Some(power_min) = self.power_min {
+            query
+                .push(format!(" AND {} >= ", POWER_EXPR))
+                .push_bind(power_min);
+        }
+        if let Some(power_max) = self.power_max {
+            query
+                .push(format!(" AND {} <= ", POWER_EXPR))
+                .push_bind(power_max);
+        }
+
+        if !self.klass_ids.is_empty() {
+            query
+                .push(
+                    " AND EXISTS (SELECT 1 FROM wix_card_klass ck \
+                     WHERE ck.card_id = c.id AND ck.klass_id = ANY(",
+                )
+                .push_bind(&self.klass_ids)
+                .push("))");
+        }
+
+        for keyword in &self.keywords {
+            let pattern = format!("%{}%", escape_like(keyword));
+            query.push(" AND (");
+            for (i, column) in ["c.name", "c.pronunciation", "c.code", "c.skill_text"]
+                .iter()
+                .enumerate()
+            {
+                if i > 0 {
+                    query.push(" OR ");
+                }
+                query
+                    .push(format!("{} ILIKE ", column))
+                    .push_bind(pattern.clone());
+            }
+            query.push(")");
+        }
+    }
+}
+
+fn split_list(value: &Option<String>) -> impl Iterator<Item = &str> {
+    value
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+fn parse_numbers<T: std::str::FromStr>(
+    value: &Option<String>,
+    field: &'static str,
+) -> Result<Vec<T>, CardSearchError> {
+    split_list(value)
+        .map(|item| {
+            item.parse().map_err(|_| CardSearchError::InvalidNumber {
+                field,
+                value: item.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// LIKEのワイルドカードをエスケープする
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(params: CardSearchParams) -> CardSearch {
+        CardSearch::try_from(params).unwrap()
+    }
+
+    #[test]
+    fn test_params_are_parsed_like_the_ui_filters() {
+        let search = search(CardSearchParams {
+            colors: Some("白, 青".to_string()),
+            card_types: Some("5,6".to_string()),
+            levels: Some("1,2".to_string()),
+            text: Some("ドロー　エナ".to_string()),
+            limit: Some(1000),
+            ..Default::default()
+        });
+
+        assert_eq!(search.color_bits, 2 | 4);
+        assert_eq!(search.card_types, vec![5, 6]);
+        assert_eq!(search.levels, vec![1, 2]);
+        assert_eq!(search.keywords, vec!["ドロー", "エナ"]);
+        assert_eq!(search.limit, MAX_SEARCH_LIMIT);
+    }
+
+    #[test]
+    fn test_invalid_params_are_rejected() {
+        let unknown_color = CardSearch::try_from(CardSearchParams {
+            colors: Some("紫".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            unknown_color,
+            Err(CardSearchError::UnknownColor("紫".to_string()))
+        );
+
+        let bad_level = CardSearch::try_from(CardSearchParams {
+            levels: Some("1,x".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(
+            bad_level,
+            Err(CardSearchError::InvalidNumber {
+                field: "levels",
+                value: "x".to_string()
+            })
+        );
+
+        let bad_cursor = CardSearch::try_from(CardSearchParams {
+            cursor: Some("!!!".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(bad_cursor, Err(CardSearchError::InvalidCursor));
+    }
+
+    #[test]
+    fn test_cursor_round_trip_and_sort_mismatch() {
+        let cursor = SearchCursor {
+            key: CursorKey::Text("WXDi-P01-001".to_string()),
+            id: 42,
+        };
+        assert_eq!(SearchCursor::decode(&cursor.encode()), Ok(cursor.clone()));
+
+        let mismatch = CardSearch::try_from(CardSearchParams {
+            sort: CardSortKey::Power,
+            cursor: Some(cursor.encode()),
+            ..Default::default()
+        });
+        assert_eq!(mismatch, Err(CardSearchError::CursorSortMismatch));
+    }
+
+    #[test]
+    fn test_page_query_applies_filters_cursor_and_order() {
+        let search = search(CardSearchParams {
+            features: Some(FEATURE_LABELS.keys().next().unwrap().to_string()),
+            klass: Some("3".to_string()),
+            power_min: Some(5000),
+            sort: CardSortKey::Power,
+            order: SortOrder::Desc,
+            cursor: Some(
+                SearchCursor {
+                    key: CursorKey::Int(8000),
+                    id: 10,
+                }
+                .encode(),
+            ),
+            ..Default::default()
+        });
+
+        let page = search.page_query();
+        let sql = page.sql();
+        assert!(sql.contains("ck.klass_id = ANY("));
+        assert!(sql.contains(&format!("{} >= ", POWER_EXPR)));
+        assert!(sql.contains(") < ("));
+        assert!(sql.contains("DESC, c.id DESC LIMIT"));
+        assert!(!search.count_query().sql().contains("LIMIT"));
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100%_\\"), "100\\%\\_\\\\");
+    }
+}
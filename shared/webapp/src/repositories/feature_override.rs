@@ -0,0 +1,251 @@
+//! カードフィーチャーの手動修正（`wix_card_feature_override`）の適用
+//!
+//! 修正には2種類ある。
+//! - 置き換え（`replace`）: ルールベースの検出結果を捨て、修正のフィーチャーだけにする
+//! - 部分修正（`partial`）: ルールベースの検出結果に追加・削除だけを行う。
+//!   パターンが改善されると、修正で触れていないフィーチャーにはその改善がそのまま反映される
+
+use feature::feature::{BurstHashSetToBits, HashSetToBits};
+use feature::{BurstFeature, CardFeature};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+/// 修正の種類（`wix_card_feature_override.mode`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideMode {
+    /// ルールベースの結果を修正のフィーチャーで置き換える
+    #[default]
+    Replace,
+    /// ルールベースの結果にフィーチャーを追加・削除する
+    Partial,
+}
+
+impl OverrideMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverrideMode::Replace => "replace",
+            OverrideMode::Partial => "partial",
+        }
+    }
+}
+
+impl TryFrom<String> for OverrideMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "replace" => Ok(OverrideMode::Replace),
+            "partial" => Ok(OverrideMode::Partial),
+            _ => Err(format!("不明な修正の種類です: {}", value)),
+        }
+    }
+}
+
+/// 1件の修正（フィーチャーは列挙子名で持つ）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureOverride {
+    pub mode: OverrideMode,
+    /// 置き換えなら修正後のすべて、部分修正なら追加するフィーチャー
+    pub features: Vec<String>,
+    pub burst_features: Vec<String>,
+    /// 部分修正で削除するフィーチャー（置き換えでは使わない）
+    pub removed_features: Vec<String>,
+    pub removed_burst_features: Vec<String>,
+}
+
+impl FeatureOverride {
+    /// ルールベースの特徴ビットに修正を適用する。定義にない名前は無視する
+    pub fn apply(&self, bits1: i64, bits2: i64, burst_bits: i64) -> (i64, i64, i64) {
+        let (features, burst_features) = self.apply_to_features(bits1, bits2, burst_bits);
+        let (bits1, bits2) = features.to_bits();
+        (bits1, bits2, burst_features.to_burst_bits())
+    }
+
+    /// 修正を適用した後のフィーチャー
+    pub fn apply_to_features(
+        &self,
+        bits1: i64,
+        bits2: i64,
+        burst_bits: i64,
+    ) -> (HashSet<CardFeature>, HashSet<BurstFeature>) {
+        let added = card_features(&self.features);
+        let added_burst = burst_features(&self.burst_features);
+        match self.mode {
+            OverrideMode::Replace => (added, added_burst),
+            OverrideMode::Partial => {
+                let removed = card_features(&self.removed_features);
+                let removed_burst = burst_features(&self.removed_burst_features);
+                let features = CardFeature::from_bits(bits1, bits2)
+                    .into_iter()
+                    .chain(added)
+                    .filter(|feature| !removed.contains(feature))
+                    .collect();
+                let burst = BurstFeature::from_bits(burst_bits)
+                    .into_iter()
+                    .chain(added_burst)
+                    .filter(|feature| !removed_burst.contains(feature))
+                    .collect();
+                (features, burst)
+            }
+        }
+    }
+
+    /// ルールベースの結果だけで修正後と同じになる（修正が不要になった）か
+    pub fn is_redundant(&self, bits1: i64, bits2: i64, burst_bits: i64) -> bool {
+        let rule_based: HashSet<CardFeature> =
+            CardFeature::from_bits(bits1, bits2).into_iter().collect();
+        let rule_based_burst: HashSet<BurstFeature> =
+            BurstFeature::from_bits(burst_bits).into_iter().collect();
+        self.apply_to_features(bits1, bits2, burst_bits) == (rule_based, rule_based_burst)
+    }
+
+    /// 部分修正のうち、ルールベースの結果がすでに一致していて効果のない追加・削除の列挙子名
+    /// （CardFeature、BurstFeatureの順。どちらも定義順）
+    ///
+    /// 置き換えでは常に空
+    pub fn redundant_deltas(
+        &self,
+        bits1: i64,
+        bits2: i64,
+        burst_bits: i64,
+    ) -> (Vec<String>, Vec<String>) {
+        if self.mode == OverrideMode::Replace {
+            return (Vec::new(), Vec::new());
+        }
+        let rule_based = CardFeature::from_bits(bits1, bits2);
+        let rule_based_burst = BurstFeature::from_bits(burst_bits);
+        let (added, removed) = (
+            card_features(&self.features),
+            card_features(&self.removed_features),
+        );
+        let (added_burst, removed_burst) = (
+            burst_features(&self.burst_features),
+            burst_features(&self.removed_burst_features),
+        );
+
+        let mut redundant = Vec::new();
+        for feature in CardFeature::create_vec() {
+            let detected = rule_based.contains(&feature);
+            if (added.contains(&feature) && detected) || (removed.contains(&feature) && !detected) {
+                redundant.push(feature.name().to_string());
+            }
+        }
+        let mut redundant_burst = Vec::new();
+        for feature in BurstFeature::create_vec() {
+            let detected = rule_based_burst.contains(&feature);
+            if (added_burst.contains(&feature) && detected)
+                || (removed_burst.contains(&feature) && !detected)
+            {
+                redundant_burst.push(feature.name().to_string());
+            }
+        }
+        (redundant, redundant_burst)
+    }
+
+    /// 定義にない列挙子名（フィーチャーの削除・改名で残ったもの）
+    pub fn unknown_names(&self) -> Vec<&str> {
+        self.features
+            .iter()
+            .chain(&self.removed_features)
+            .filter(|name| CardFeature::from_name(name).is_none())
+            .chain(
+                self.burst_features
+                    .iter()
+                    .chain(&self.removed_burst_features)
+                    .filter(|name| BurstFeature::from_name(name).is_none()),
+            )
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+fn card_features(names: &[String]) -> HashSet<CardFeature> {
+    names
+        .iter()
+        .filter_map(|name| CardFeature::from_name(name))
+        .collect()
+}
+
+fn burst_features(names: &[String]) -> HashSet<BurstFeature> {
+    names
+        .iter()
+        .filter_map(|name| BurstFeature::from_name(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn bits_of(features: &[CardFeature]) -> (i64, i64) {
+        features.iter().cloned().collect::<HashSet<_>>().to_bits()
+    }
+
+    #[test]
+    fn test_replace_ignores_rule_based_features() {
+        let (bits1, bits2) = bits_of(&[CardFeature::Charge]);
+        let o = FeatureOverride {
+            mode: OverrideMode::Replace,
+            features: names(&["Draw", "Removed"]),
+            burst_features: names(&["Search"]),
+            ..Default::default()
+        };
+
+        let (bits1, bits2, burst_bits) = o.apply(bits1, bits2, 0);
+        assert_eq!(
+            CardFeature::from_bits(bits1, bits2),
+            vec![CardFeature::Draw]
+        );
+        assert_eq!(
+            BurstFeature::from_bits(burst_bits),
+            vec![BurstFeature::Search]
+        );
+        assert_eq!(o.unknown_names(), ["Removed"]);
+    }
+
+    /// 部分修正は触れていないフィーチャーにルールベースの結果を残す
+    #[test]
+    fn test_partial_keeps_untouched_rule_based_features() {
+        let (bits1, bits2) = bits_of(&[CardFeature::Charge, CardFeature::Banish]);
+        let o = FeatureOverride {
+            mode: OverrideMode::Partial,
+            features: names(&["Draw"]),
+            removed_features: names(&["Banish"]),
+            ..Default::default()
+        };
+
+        let (bits1, bits2, _) = o.apply(bits1, bits2, 0);
+        assert_eq!(
+            CardFeature::from_bits(bits1, bits2),
+            vec![CardFeature::Draw, CardFeature::Charge]
+        );
+    }
+
+    /// ルールが追いついた部分修正は不要として検出される
+    #[test]
+    fn test_partial_becomes_redundant_when_rules_agree() {
+        let o = FeatureOverride {
+            mode: OverrideMode::Partial,
+            features: names(&["Draw"]),
+            removed_features: names(&["Banish"]),
+            ..Default::default()
+        };
+
+        let (bits1, bits2) = bits_of(&[CardFeature::Charge, CardFeature::Banish]);
+        assert!(!o.is_redundant(bits1, bits2, 0));
+        assert_eq!(o.redundant_deltas(bits1, bits2, 0), (vec![], vec![]));
+
+        let (bits1, bits2) = bits_of(&[CardFeature::Charge, CardFeature::Draw]);
+        assert!(o.is_redundant(bits1, bits2, 0));
+        assert_eq!(
+            o.redundant_deltas(bits1, bits2, 0),
+            (names(&["Draw", "Banish"]), vec![])
+        );
+    }
+}
@@ -1,11 +1,15 @@
 mod card;
+mod card_search;
 mod cardtype;
 mod color;
+mod feature_override;
 mod klass;
 mod product;
 
 pub use card::*;
+pub use card_search::*;
 pub use cardtype::*;
 pub use color::*;
+pub use feature_override::*;
 pub use klass::*;
 pub use product::*;
@@ -38,6 +38,11 @@ impl ProductRepository {
         self.cache.get(product_code).cloned()
     }
 
+    /// wix_productのデータバージョン（一覧キャッシュの無効化判定に使う）
+    pub async fn data_version(&self) -> Result<i64, sqlx::Error> {
+        crate::snapshot::data_version(&self.db_connector, &["wix_product"]).await
+    }
+
     pub fn get_all<'a>(
         &'a self,
     ) -> Pin<Box<dyn Future<Output = Result<Vec<Product>, sqlx::Error>> + Send + 'a>> {
@@ -31,6 +31,41 @@ fn random_string() -> String {
         .join("")
 }
 
+/// Django管理サーバーの子プロセス
+///
+/// `/admin_operation` の起動・停止と `/metrics` の監視で共有する
+#[derive(Clone, Default)]
+pub struct DjangoProcess {
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+/// Django管理サーバーの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DjangoProcessState {
+    /// 起動していない（停止操作済みを含む）
+    Stopped,
+    /// 起動中
+    Running,
+    /// 起動後、停止操作なしに終了した
+    Exited,
+}
+
+impl DjangoProcess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn state(&self) -> DjangoProcessState {
+        match self.child.lock().await.as_mut() {
+            None => DjangoProcessState::Stopped,
+            Some(child) => match child.try_wait() {
+                Ok(None) => DjangoProcessState::Running,
+                Ok(Some(_)) | Err(_) => DjangoProcessState::Exited,
+            },
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct DjangoStartResult {
     success: bool,
@@ -62,7 +97,7 @@ fn find_working_python() -> Option<String> {
 
     for candidate in candidates.into_iter().flatten() {
         if test_python_with_django(&candidate) {
-            println!("Found working Python: {}", candidate);
+            tracing::info!("Found working Python: {}", candidate);
             return Some(candidate);
         }
     }
@@ -189,7 +224,7 @@ static PYTHON_PATH_CACHE: OnceLock<Option<String>> = OnceLock::new();
 fn get_cached_python_path() -> Option<String> {
     PYTHON_PATH_CACHE
         .get_or_init(|| {
-            println!("Detecting working Python installation...");
+            tracing::info!("Detecting working Python installation...");
             find_working_python()
         })
         .clone()
@@ -197,10 +232,10 @@ fn get_cached_python_path() -> Option<String> {
 
 fn create_python_command() -> Command {
     if let Some(python_path) = get_cached_python_path() {
-        println!("Using verified Python: {}", python_path);
+        tracing::info!("Using verified Python: {}", python_path);
         Command::new(python_path)
     } else {
-        println!("Warning: No working Python found, using fallback");
+        tracing::warn!("No working Python found, using fallback");
         Command::new("python3")
     }
 }
@@ -241,14 +276,14 @@ fn create_gunicorn_command(
 }
 
 async fn start_django_server(State(router_state): State<Arc<RouterState>>) -> impl IntoResponse {
-    let process_handle = Arc::clone(&router_state.django_process_handle); // クローンして取り出す
+    let process_handle = Arc::clone(&router_state.django_process.child); // クローンして取り出す
     let mut handle = process_handle.lock().await;
 
     let rand_string = random_string();
     let admin_root = format!("admin_proxy/{}/", rand_string);
 
     if handle.is_some() {
-        println!("Django server is already running!");
+        tracing::info!("Django server is already running!");
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(DjangoStartResult {
@@ -262,10 +297,10 @@ async fn start_django_server(State(router_state): State<Arc<RouterState>>) -> im
     let axum_web_port = router_state.axum_web_port;
 
     let mut command = if should_use_gunicorn() {
-        println!("Using Gunicorn for Django server");
+        tracing::info!("Using Gunicorn for Django server");
         create_gunicorn_command(&admin_root, router_state.django_admin_port, axum_web_port)
     } else {
-        println!("Using Django development server");
+        tracing::info!("Using Django development server");
         let mut cmd = create_python_command();
         cmd.args([
             "../table_definition/manage.py",
@@ -293,7 +328,7 @@ async fn start_django_server(State(router_state): State<Arc<RouterState>>) -> im
 
     match command.spawn() {
         Ok(child) => {
-            println!("Django server started successfully with PID {}", child.id());
+            tracing::info!("Django server started successfully with PID {}", child.id());
             *handle = Some(child);
             (
                 StatusCode::OK,
@@ -304,7 +339,7 @@ async fn start_django_server(State(router_state): State<Arc<RouterState>>) -> im
             )
         }
         Err(error) => {
-            println!("Failed to start Django server: {}", error);
+            tracing::error!("Failed to start Django server: {}", error);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(DjangoStartResult {
@@ -317,11 +352,11 @@ async fn start_django_server(State(router_state): State<Arc<RouterState>>) -> im
 }
 
 async fn stop_django_server(State(state): State<Arc<RouterState>>) -> impl IntoResponse {
-    let process_handle = Arc::clone(&state.django_process_handle); // クローンして取り出す
+    let process_handle = Arc::clone(&state.django_process.child); // クローンして取り出す
     let mut handle = process_handle.lock().await;
 
     if let Some(mut child) = handle.take() {
-        println!("Stopping Django server with PID {}", child.id());
+        tracing::info!("Stopping Django server with PID {}", child.id());
 
         // WindowsではCtrl+Cに相当するCTRL_BREAKを送信
         #[cfg(windows)]
@@ -329,9 +364,9 @@ async fn stop_django_server(State(state): State<Arc<RouterState>>) -> impl IntoR
             use winapi::um::wincon::GenerateConsoleCtrlEvent;
             unsafe {
                 if GenerateConsoleCtrlEvent(winapi::um::wincon::CTRL_BREAK_EVENT, child.id()) == 0 {
-                    println!("Failed to send CTRL_BREAK_EVENT to Django server");
+                    tracing::error!("Failed to send CTRL_BREAK_EVENT to Django server");
                 } else {
-                    println!("CTRL_BREAK_EVENT sent to Django server");
+                    tracing::info!("CTRL_BREAK_EVENT sent to Django server");
                 }
             }
         }
@@ -343,21 +378,21 @@ async fn stop_django_server(State(state): State<Arc<RouterState>>) -> impl IntoR
             use nix::unistd::Pid;
 
             if let Err(err) = killpg(Pid::from_raw(child.id() as i32), Signal::SIGINT) {
-                println!("Failed to send SIGINT to Django server: {}", err);
+                tracing::error!("Failed to send SIGINT to Django server: {}", err);
             } else {
-                println!("SIGINT sent to Django server");
+                tracing::info!("SIGINT sent to Django server");
             }
         }
 
         // プロセスが自動停止せずにまだ動作している場合にkillを試みる
         if let Err(err) = child.kill() {
-            println!("Error stopping Django server: {}", err);
+            tracing::error!("Error stopping Django server: {}", err);
             Json(DjangoStartResult {
                 success: false,
                 entry: None,
             })
         } else {
-            println!("Django server stopped successfully.");
+            tracing::info!("Django server stopped successfully.");
 
             Json(DjangoStartResult {
                 success: true,
@@ -365,7 +400,7 @@ async fn stop_django_server(State(state): State<Arc<RouterState>>) -> impl IntoR
             })
         }
     } else {
-        println!("No server is running.");
+        tracing::info!("No server is running.");
         Json(DjangoStartResult {
             success: false,
             entry: None,
@@ -374,7 +409,7 @@ async fn stop_django_server(State(state): State<Arc<RouterState>>) -> impl IntoR
 }
 
 async fn get_django_status(State(state): State<Arc<RouterState>>) -> impl IntoResponse {
-    let process_handle = Arc::clone(&state.django_process_handle);
+    let process_handle = Arc::clone(&state.django_process.child);
     let handle = process_handle.lock().await;
 
     // プロセスが起動しているかチェック
@@ -453,7 +488,7 @@ async fn get_django_status(State(state): State<Arc<RouterState>>) -> impl IntoRe
 
 #[derive(Clone)]
 struct RouterState {
-    django_process_handle: Arc<Mutex<Option<Child>>>, // プロセス管理用のデモ的な型
+    django_process: DjangoProcess,
     django_admin_port: u16,
     axum_web_port: u16,
 }
@@ -461,9 +496,10 @@ struct RouterState {
 pub fn create_admin_portal_router(
     django_admin_port: u16,
     axum_web_port: u16,
+    django_process: DjangoProcess,
 ) -> (Router<AppState>, Router<AppState>, Router<AppState>) {
     let state = Arc::new(RouterState {
-        django_process_handle: Arc::new(Mutex::new(None)),
+        django_process,
         django_admin_port,
         axum_web_port,
     });
@@ -497,7 +533,7 @@ async fn proxy_handler(
     let url = match Uri::try_from(target_uri) {
         Ok(uri) => uri,
         Err(err) => {
-            eprintln!("Invalid URI: {}", err);
+            tracing::error!("Invalid URI: {}", err);
             return Response::builder()
                 .status(500)
                 .body(Body::from("Invalid URI"))
@@ -507,7 +543,7 @@ async fn proxy_handler(
 
     *req.uri_mut() = uri.clone();
 
-    println!("[proxy to2] {:?}", url);
+    tracing::debug!(%url, "admin proxy");
 
     match tokio::net::TcpStream::connect(&proxy_host_port).await {
         Ok(stream) => {
@@ -516,7 +552,7 @@ async fn proxy_handler(
             let (mut sender, connection) = match http1::handshake(io).await {
                 Ok(conn) => conn,
                 Err(err) => {
-                    eprintln!("Handshake failed: {:?}", err);
+                    tracing::error!("Handshake failed: {:?}", err);
                     return Response::builder()
                         .status(500)
                         .body(Body::from("Internal Server Error"))
@@ -526,7 +562,7 @@ async fn proxy_handler(
 
             tokio::spawn(async move {
                 if let Err(err) = connection.await {
-                    eprintln!("Connection closed with error: {:?}", err);
+                    tracing::error!("Connection closed with error: {:?}", err);
                 }
             });
 
@@ -550,7 +586,7 @@ async fn proxy_handler(
             let proxied_res = match sender.send_request(proxied_req).await {
                 Ok(res) => res,
                 Err(err) => {
-                    eprintln!("Request failed: {:?}", err);
+                    tracing::error!("Request failed: {:?}", err);
                     return Response::builder()
                         .status(502)
                         .body(Body::from("Bad Gateway"))
@@ -576,7 +612,7 @@ async fn proxy_handler(
                     }
                     Ok(_) => {}
                     Err(err) => {
-                        eprintln!("Error reading response body: {:?}", err);
+                        tracing::error!("Error reading response body: {:?}", err);
                         break;
                     }
                 };
@@ -585,7 +621,7 @@ async fn proxy_handler(
             response_builder.body(Body::from(response_bytes)).unwrap()
         }
         Err(err) => {
-            eprintln!("Failed to connect to target: {:?}", err);
+            tracing::error!("Failed to connect to target: {:?}", err);
             Response::builder()
                 .status(500)
                 .body(Body::from("Internal Server Error"))
@@ -1,52 +1,61 @@
 //! カードルーター
 //!
-//! カード一覧の取得APIを提供
+//! カード一覧の取得・検索・詳細APIを提供
 
-use crate::repositories::CardRepository;
+use crate::error::{ApiError, ApiErrorBody};
+use crate::repositories::{CardDetail, CardRepository, CardSearch, CardSearchParams};
+use crate::snapshot::SnapshotCache;
 use crate::state::AppState;
-use axum::extract::State;
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::routing::get;
-use axum::{Json, Router};
+use api_routes::ApiRoute;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum::Json;
 use models::card::Card;
 use serde::Serialize;
 use sqlx::{Pool, Postgres};
 use std::sync::Arc;
-use thiserror::Error;
-
-/// カードルーターのエラー型
-#[derive(Debug, Error)]
-pub enum CardRouterError {
-    #[error("データベースエラー: {0}")]
-    Database(#[from] sqlx::Error),
-    #[error("シリアライゼーションエラー: {0}")]
-    Serialization(#[from] serde_json::Error),
-}
+use utoipa::{OpenApi, ToSchema};
+
+/// カードAPIのOpenAPI定義（`/api/card` からの相対パス）
+#[derive(OpenApi)]
+#[openapi(paths(card_list, card_list_json, card_search, card_detail))]
+pub struct CardApiDoc;
 
 /// ルーター状態
 #[derive(Clone)]
 struct RouterState {
     card_repo: CardRepository,
+    snapshots: SnapshotCache,
 }
 
-/// カードルーターを作成
+/// カードAPIのルート表を作成
 ///
 /// # 引数
 ///
 /// * `pool` - データベース接続プール
+/// * `snapshots` - 一覧レスポンスのキャッシュ
 ///
 /// # 戻り値
 ///
-/// 設定されたAxumルーター
-pub fn create_card_router(pool: Arc<Pool<Postgres>>) -> Router<AppState> {
+/// `/api/card` からの相対パスのルート表
+pub fn card_routes(pool: Arc<Pool<Postgres>>, snapshots: SnapshotCache) -> Vec<ApiRoute<AppState>> {
     let card_repo = CardRepository::new(pool);
-    let state = RouterState { card_repo };
+    let state = RouterState {
+        card_repo,
+        snapshots,
+    };
 
-    Router::new()
-        .route("/", get(card_list))
-        .route("/list.json", get(card_list_json))
-        .with_state(state)
+    vec![
+        ApiRoute::get("/", card_list),
+        ApiRoute::get("/list.json", card_list_json),
+        ApiRoute::get("/search", card_search),
+        ApiRoute::get("/{code}", card_detail),
+    ]
+    .into_iter()
+    .map(|route| route.with_state(state.clone()))
+    .collect()
 }
 
 /// カード一覧をテキスト形式で取得
@@ -54,37 +63,56 @@ pub fn create_card_router(pool: Arc<Pool<Postgres>>) -> Router<AppState> {
 /// # 引数
 ///
 /// * `state` - ルーター状態
+/// * `headers` - リクエストヘッダー（`If-None-Match`・`Accept-Encoding`）
 ///
 /// # 戻り値
 ///
-/// カード名を改行区切りで連結した文字列
-async fn card_list(State(state): State<RouterState>) -> impl IntoResponse {
-    println!("カード一覧テキスト形式の取得を開始");
+/// カード名を改行区切りで連結した文字列、ETagが一致すれば304
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "card",
+    responses(
+        (status = 200, description = "カード名の改行区切り", body = String, content_type = "text/plain"),
+        (status = 304, description = "ETagが一致した"),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody),
+        (status = 503, description = "データベースの応答タイムアウト", body = ApiErrorBody)
+    )
+)]
+async fn card_list(
+    State(state): State<RouterState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let version = state.card_repo.data_version().await?;
+    let snapshot = state
+        .snapshots
+        .get_or_load("card_list", version, "text/plain; charset=utf-8", || async {
+            tracing::info!("カード一覧テキスト形式の取得を開始");
 
-    match state.card_repo.get_all().await {
-        Ok(card_list) => {
-            let result = card_list
+            let result = state
+                .card_repo
+                .get_all()
+                .await?
                 .into_iter()
                 .map(|card| card.name)
                 .collect::<Vec<String>>()
                 .join("\n");
-            println!(
+            tracing::info!(
                 "カード一覧テキスト形式の取得が成功: {}件",
                 result.lines().count()
             );
-            result
-        }
-        Err(e) => {
-            eprintln!("カード一覧取得エラー: {:?}", e);
-            "カード一覧の取得に失敗しました".to_string()
-        }
-    }
+            Ok(result.into_bytes())
+        })
+        .await?;
+
+    Ok(snapshot.respond(&headers))
 }
 
 /// JSON形式のカード一覧レスポンス
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct CardListJson {
     /// カード配列
+    #[schema(value_type = Vec<Object>)]
     cards: Vec<Card>,
     /// 総件数
     total: usize,
@@ -98,8 +126,8 @@ impl CardListJson {
     }
 
     /// JSON文字列に変換（エラーハンドリング付き）
-    pub fn to_json(&self) -> Result<String, CardRouterError> {
-        serde_json::to_string(&self).map_err(CardRouterError::from)
+    pub fn to_json(&self) -> Result<String, ApiError> {
+        serde_json::to_string(&self).map_err(ApiError::from)
     }
 }
 /// カード一覧をJSON形式で取得
@@ -107,25 +135,126 @@ impl CardListJson {
 /// # 引数
 ///
 /// * `state` - ルーター状態
+/// * `headers` - リクエストヘッダー（`If-None-Match`・`Accept-Encoding`）
 ///
 /// # 戻り値
 ///
-/// JSON形式のカード一覧またはエラーステータス
+/// JSON形式のカード一覧（ETagが一致すれば304）またはエラー
+#[utoipa::path(
+    get,
+    path = "/list.json",
+    tag = "card",
+    responses(
+        (status = 200, description = "全カード", body = CardListJson),
+        (status = 304, description = "ETagが一致した"),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody),
+        (status = 503, description = "データベースの応答タイムアウト", body = ApiErrorBody)
+    )
+)]
 async fn card_list_json(
     State(state): State<RouterState>,
-) -> Result<Json<CardListJson>, StatusCode> {
-    println!("カード一覧JSON形式の取得を開始");
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let version = state.card_repo.data_version().await?;
+    let snapshot = state
+        .snapshots
+        .get_or_load("card_list_json", version, "application/json", || async {
+            tracing::info!("カード一覧JSON形式の取得を開始");
+
+            let cards = state.card_repo.get_all().await?;
+
+            let cards: Vec<Card> = cards.into_iter().map(|card| card.into()).collect();
+
+            let total = cards.len();
+            tracing::info!("カード一覧JSON形式の取得が成功: {}件", total);
 
-    let cards = state.card_repo.get_all().await.map_err(|e| {
-        eprintln!("カード一覧取得エラー: {:?}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+            let res = CardListJson::new(cards);
+            Ok(res.to_json()?.into_bytes())
+        })
+        .await?;
 
-    let cards: Vec<Card> = cards.into_iter().map(|card| card.into()).collect();
+    Ok(snapshot.respond(&headers))
+}
+
+/// カード検索のレスポンス
+#[derive(Serialize, ToSchema)]
+pub struct CardSearchResponse {
+    /// このページのカード
+    #[schema(value_type = Vec<Object>)]
+    cards: Vec<Card>,
+    /// 条件に一致する総件数
+    total: i64,
+    /// 次ページ取得用のカーソル（最終ページでは `null`）
+    next_cursor: Option<String>,
+}
+
+/// 条件で絞り込んだカードをページ単位で取得
+///
+/// # 引数
+///
+/// * `state` - ルーター状態
+/// * `params` - 絞り込み・並び順・ページングの条件
+///
+/// # 戻り値
+///
+/// JSON形式の検索結果、条件が不正な場合は400（`validation_error`）
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "card",
+    params(CardSearchParams),
+    responses(
+        (status = 200, description = "検索結果の1ページ", body = CardSearchResponse),
+        (status = 400, description = "検索条件が不正", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody),
+        (status = 503, description = "データベースの応答タイムアウト", body = ApiErrorBody)
+    )
+)]
+async fn card_search(
+    State(state): State<RouterState>,
+    params: Result<Query<CardSearchParams>, QueryRejection>,
+) -> Result<Json<CardSearchResponse>, ApiError> {
+    let Query(params) = params?;
+    let search = CardSearch::try_from(params)?;
+    let page = state.card_repo.search(&search).await?;
 
-    let total = cards.len();
-    println!("カード一覧JSON形式の取得が成功: {}件", total);
+    Ok(Json(CardSearchResponse {
+        cards: page.cards.into_iter().map(Card::from).collect(),
+        total: page.total,
+        next_cursor: page.next_cursor,
+    }))
+}
 
-    let res = CardListJson::new(cards);
-    Ok(Json(res))
+/// 1枚のカードを関連付きで取得
+///
+/// # 引数
+///
+/// * `state` - ルーター状態
+/// * `code` - カード番号（IDまたは読みでも可）
+///
+/// # 戻り値
+///
+/// JSON形式のカード詳細、見つからない場合は404
+#[utoipa::path(
+    get,
+    path = "/{code}",
+    tag = "card",
+    params(("code" = String, Path, description = "カード番号（IDまたは読みでも可）")),
+    responses(
+        (status = 200, description = "カード詳細", body = CardDetail),
+        (status = 404, description = "カードが見つからない", body = ApiErrorBody),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody),
+        (status = 503, description = "データベースの応答タイムアウト", body = ApiErrorBody)
+    )
+)]
+async fn card_detail(
+    State(state): State<RouterState>,
+    Path(code): Path<String>,
+) -> Result<Json<CardDetail>, ApiError> {
+    state
+        .card_repo
+        .get_detail(&code)
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found(format!("カードが見つかりません: {}", code)))
 }
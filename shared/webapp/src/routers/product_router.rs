@@ -1,57 +1,109 @@
+use crate::error::{ApiError, ApiErrorBody};
 use crate::repositories::ProductRepository;
+use crate::snapshot::SnapshotCache;
 use crate::state::AppState;
+use api_routes::ApiRoute;
 use axum::extract::State;
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::routing::get;
-use axum::Json;
-use axum::Router;
+use axum::http::HeaderMap;
+use axum::response::Response;
 use models::product::Product;
 use serde::Serialize;
-use sqlx::{Error, Pool, Postgres};
+use sqlx::{Pool, Postgres};
 use std::sync::Arc;
+use utoipa::{OpenApi, ToSchema};
+
+/// 商品APIのOpenAPI定義（`/api/product` からの相対パス）
+#[derive(OpenApi)]
+#[openapi(paths(product_list, product_list_json))]
+pub struct ProductApiDoc;
 
 #[derive(Clone)]
 struct RouterState {
     repo: ProductRepository,
+    snapshots: SnapshotCache,
 }
 
-pub fn create_product_router(pool: Arc<Pool<Postgres>>) -> Router<AppState> {
+/// 商品APIのルート表（`/api/product` からの相対パス）
+pub fn product_routes(
+    pool: Arc<Pool<Postgres>>,
+    snapshots: SnapshotCache,
+) -> Vec<ApiRoute<AppState>> {
     let repo = ProductRepository::new(pool);
-    let state = RouterState { repo };
+    let state = RouterState { repo, snapshots };
 
-    Router::new()
-        .route("/", get(product_list))
-        .route("/list.json", get(product_list_json))
-        .with_state(state)
+    vec![
+        ApiRoute::get("/", product_list),
+        ApiRoute::get("/list.json", product_list_json),
+    ]
+    .into_iter()
+    .map(|route| route.with_state(state.clone()))
+    .collect()
 }
 
-async fn product_list(State(state): State<RouterState>) -> impl IntoResponse {
-    let products: Result<Vec<Product>, Error> = state.repo.get_all().await;
-
-    match products {
-        Ok(product_list) => product_list
-            .into_iter()
-            .map(|product| product.name.clone())
-            .collect::<Vec<String>>()
-            .join("\n"),
-        Err(_) => "エラーが発生しました".to_string(),
-    }
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "product",
+    responses(
+        (status = 200, description = "商品名の改行区切り", body = String, content_type = "text/plain"),
+        (status = 304, description = "ETagが一致した"),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody),
+        (status = 503, description = "データベースの応答タイムアウト", body = ApiErrorBody)
+    )
+)]
+async fn product_list(
+    State(state): State<RouterState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let version = state.repo.data_version().await?;
+    let snapshot = state
+        .snapshots
+        .get_or_load("product_list", version, "text/plain; charset=utf-8", || async {
+            let products: Vec<Product> = state.repo.get_all().await?;
+
+            Ok(products
+                .into_iter()
+                .map(|product| product.name.clone())
+                .collect::<Vec<String>>()
+                .join("\n")
+                .into_bytes())
+        })
+        .await?;
+
+    Ok(snapshot.respond(&headers))
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[schema(as = ProductListJson)]
 struct CardListJson {
+    #[schema(value_type = Vec<Object>)]
     products: Vec<Product>,
 }
+#[utoipa::path(
+    get,
+    path = "/list.json",
+    tag = "product",
+    responses(
+        (status = 200, description = "全商品", body = CardListJson),
+        (status = 304, description = "ETagが一致した"),
+        (status = 500, description = "データベースエラー", body = ApiErrorBody),
+        (status = 503, description = "データベースの応答タイムアウト", body = ApiErrorBody)
+    )
+)]
 async fn product_list_json(
     State(state): State<RouterState>,
-) -> Result<Json<CardListJson>, StatusCode> {
-    let cards = state
-        .repo
-        .get_all()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let res: CardListJson = CardListJson { products: cards };
-    Ok(Json(res))
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let version = state.repo.data_version().await?;
+    let snapshot = state
+        .snapshots
+        .get_or_load("product_list_json", version, "application/json", || async {
+            let cards = state.repo.get_all().await?;
+
+            let res: CardListJson = CardListJson { products: cards };
+            Ok(serde_json::to_vec(&res)?)
+        })
+        .await?;
+
+    Ok(snapshot.respond(&headers))
 }
@@ -0,0 +1,326 @@
+//! APIレスポンスのスナップショットキャッシュ
+//!
+//! カード・商品一覧はスクレイピングや解析を実行したときにしか変わらないため、
+//! シリアライズ済みの本文をgzip・brotli圧縮版と合わせてメモリに保持し、
+//! データバージョンが変わるまで使い回す。
+//! 本文のハッシュから強いETagを作り、`If-None-Match` が一致すれば304を返す。
+
+use crate::error::ApiError;
+use axum::body::Bytes;
+use axum::http::header::{
+    ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE, ETAG, IF_NONE_MATCH, VARY,
+};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+
+/// 本文の圧縮方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+impl Encoding {
+    /// `Accept-Encoding` から使う圧縮方式を選ぶ（br > gzip > 無圧縮）
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers
+            .get(ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Encoding::Identity;
+        };
+
+        let mut br = false;
+        let mut gzip = false;
+        for item in accept.split(',') {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let accepted = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .all(|q| q.trim().parse::<f32>().map(|q| q > 0.0).unwrap_or(false));
+            match name.as_str() {
+                "br" => br = accepted,
+                "gzip" => gzip = accepted,
+                "*" if accepted => {
+                    br = true;
+                    gzip = true;
+                }
+                _ => {}
+            }
+        }
+
+        if br {
+            Encoding::Brotli
+        } else if gzip {
+            Encoding::Gzip
+        } else {
+            Encoding::Identity
+        }
+    }
+
+    fn header_value(&self) -> Option<&'static str> {
+        match self {
+            Encoding::Identity => None,
+            Encoding::Gzip => Some("gzip"),
+            Encoding::Brotli => Some("br"),
+        }
+    }
+}
+
+/// シリアライズ済みのレスポンス本文
+#[derive(Debug)]
+pub struct Snapshot {
+    /// 作成時のデータバージョン
+    pub version: i64,
+    content_type: &'static str,
+    hash: String,
+    identity: Bytes,
+    gzip: Bytes,
+    brotli: Bytes,
+}
+
+impl Snapshot {
+    pub fn new(version: i64, content_type: &'static str, body: Vec<u8>) -> Self {
+        let digest = Sha256::digest(&body);
+        let hash = digest[..16]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&body)
+            .expect("メモリへのgzip圧縮は失敗しない");
+        let gzip = gzip.finish().expect("メモリへのgzip圧縮は失敗しない");
+
+        let mut brotli = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut brotli, 4096, 9, 22);
+            writer
+                .write_all(&body)
+                .expect("メモリへのbrotli圧縮は失敗しない");
+        }
+
+        Self {
+            version,
+            content_type,
+            hash,
+            identity: Bytes::from(body),
+            gzip: Bytes::from(gzip),
+            brotli: Bytes::from(brotli),
+        }
+    }
+
+    /// 圧縮方式ごとの強いETag（表現ごとに異なる値になる）
+    pub fn etag(&self, encoding: Encoding) -> String {
+        match encoding.header_value() {
+            Some(suffix) => format!("\"{}-{}\"", self.hash, suffix),
+            None => format!("\"{}\"", self.hash),
+        }
+    }
+
+    /// リクエストヘッダーに応じて200（圧縮済み本文）または304を返す
+    pub fn respond(&self, request_headers: &HeaderMap) -> Response {
+        let encoding = Encoding::negotiate(request_headers);
+        let etag = self.etag(encoding);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_str(&etag).expect("ETagはASCIIのみ"));
+        headers.insert(VARY, HeaderValue::from_static("accept-encoding"));
+        headers.insert(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+        if if_none_match(request_headers, &etag) {
+            return (StatusCode::NOT_MODIFIED, headers).into_response();
+        }
+
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(self.content_type));
+        let body = match encoding {
+            Encoding::Identity => self.identity.clone(),
+            Encoding::Gzip => self.gzip.clone(),
+            Encoding::Brotli => self.brotli.clone(),
+        };
+        if let Some(value) = encoding.header_value() {
+            headers.insert(CONTENT_ENCODING, HeaderValue::from_static(value));
+        }
+
+        (StatusCode::OK, headers, body).into_response()
+    }
+}
+
+/// `If-None-Match` のいずれかが `etag` と一致するか（弱い比較）
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// スナップショットの保持先（`AppState` で共有する）
+#[derive(Clone, Default)]
+pub struct SnapshotCache {
+    entries: Arc<RwLock<HashMap<&'static str, Arc<Snapshot>>>>,
+    /// 同じキーを同時に作り直さないためのロック
+    loading: Arc<Mutex<()>>,
+}
+
+impl SnapshotCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `version` のスナップショットを返す。なければ `load` の結果から作る
+    pub async fn get_or_load<F, Fut>(
+        &self,
+        key: &'static str,
+        version: i64,
+        content_type: &'static str,
+        load: F,
+    ) -> Result<Arc<Snapshot>, ApiError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<u8>, ApiError>>,
+    {
+        if let Some(snapshot) = self.current(key, version).await {
+            return Ok(snapshot);
+        }
+
+        let _loading = self.loading.lock().await;
+        if let Some(snapshot) = self.current(key, version).await {
+            return Ok(snapshot);
+        }
+
+        let snapshot = Arc::new(Snapshot::new(version, content_type, load().await?));
+        self.entries.write().await.insert(key, snapshot.clone());
+        Ok(snapshot)
+    }
+
+    /// 全スナップショットを破棄する（次のリクエストで作り直す）
+    pub async fn invalidate(&self) {
+        self.entries.write().await.clear();
+    }
+
+    async fn current(&self, key: &'static str, version: i64) -> Option<Arc<Snapshot>> {
+        self.entries
+            .read()
+            .await
+            .get(key)
+            .filter(|snapshot| snapshot.version == version)
+            .cloned()
+    }
+}
+
+/// テーブル群のデータバージョン
+///
+/// `wix_data_version` の値の合計を使う。値はテーブルへの書き込みのたびにトリガーで増え、
+/// 書き込みと同じトランザクションでコミットされるため、統計情報のような遅れやリセットがない。
+pub async fn data_version(pool: &Pool<Postgres>, tables: &[&str]) -> Result<i64, sqlx::Error> {
+    let query = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(version), 0)::bigint \
+         FROM wix_data_version WHERE table_name = ANY($1)",
+    )
+    .bind(tables)
+    .fetch_one(pool);
+
+    tokio::time::timeout(Duration::from_secs(5), query)
+        .await
+        .map_err(|_| sqlx::Error::PoolTimedOut)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use std::io::Read;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_negotiate_prefers_brotli_and_respects_q_zero() {
+        let negotiate = |value| Encoding::negotiate(&headers(&[("accept-encoding", value)]));
+
+        assert_eq!(negotiate("gzip, deflate, br"), Encoding::Brotli);
+        assert_eq!(negotiate("gzip, br;q=0"), Encoding::Gzip);
+        assert_eq!(negotiate("deflate"), Encoding::Identity);
+        assert_eq!(negotiate("*"), Encoding::Brotli);
+        assert_eq!(Encoding::negotiate(&HeaderMap::new()), Encoding::Identity);
+    }
+
+    #[tokio::test]
+    async fn test_gzip_response_and_not_modified() {
+        let body = br#"{"cards":[],"total":0}"#.to_vec();
+        let snapshot = Snapshot::new(1, "application/json", body.clone());
+
+        let response = snapshot.respond(&headers(&[("accept-encoding", "gzip")]));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+        let etag = response.headers()[ETAG].to_str().unwrap().to_string();
+        assert_eq!(etag, snapshot.etag(Encoding::Gzip));
+
+        let compressed = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(&compressed[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+
+        let response = snapshot.respond(&headers(&[
+            ("accept-encoding", "gzip"),
+            ("if-none-match", &format!("\"other\", {}", etag)),
+        ]));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+
+        // 表現が違えばETagも違うので304にはならない
+        let response = snapshot.respond(&headers(&[("if-none-match", &etag)]));
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_cache_reloads_only_when_version_changes() {
+        let cache = SnapshotCache::new();
+        let load = |body: &'static str| move || async move { Ok(body.as_bytes().to_vec()) };
+
+        let first = cache
+            .get_or_load("cards", 1, "text/plain", load("a"))
+            .await
+            .unwrap();
+        let same = cache
+            .get_or_load("cards", 1, "text/plain", load("b"))
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &same));
+
+        let changed = cache
+            .get_or_load("cards", 2, "text/plain", load("b"))
+            .await
+            .unwrap();
+        assert_ne!(
+            first.etag(Encoding::Identity),
+            changed.etag(Encoding::Identity)
+        );
+
+        cache.invalidate().await;
+        let reloaded = cache
+            .get_or_load("cards", 2, "text/plain", load("c"))
+            .await
+            .unwrap();
+        assert!(!Arc::ptr_eq(&changed, &reloaded));
+    }
+}
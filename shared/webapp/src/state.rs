@@ -1,8 +1,20 @@
+use crate::auth::ApiKeyAuth;
+use crate::routers::admin_process::DjangoProcess;
+use crate::snapshot::SnapshotCache;
 use sqlx::{Pool, Postgres};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db_pool: Arc<Pool<Postgres>>,
     pub django_admin_port: u16,
+    /// カード・商品一覧のレスポンスキャッシュ
+    pub snapshots: SnapshotCache,
+    /// 管理系APIのAPIキー認証
+    pub auth: ApiKeyAuth,
+    /// Django管理サーバーの子プロセス
+    pub django_process: DjangoProcess,
+    /// `/readyz` で比較するdatapackのバージョンファイル
+    pub datapack_version_file: PathBuf,
 }
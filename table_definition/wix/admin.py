@@ -6,7 +6,7 @@ Django Bridgeプロジェクト用の管理画面カスタマイズ
 - Axumプロジェクトからアクセス可能な管理インターフェースを提供
 """
 from django.contrib import admin
-from .models import Card, CardType, Color, Lrig, Product, Klass, Feature, Timing, RawCard
+from .models import Card, CardType, Color, Lrig, Product, Klass, Feature, Timing, RawCard, ApiKey, CardFeatureOverrideHistory, CardReviewItem
 
 
 # 管理画面のサイト設定
@@ -164,4 +164,40 @@ class RawCardAdmin(admin.ModelAdmin):
             last_analyzed_at=timezone.now()
         )
         self.message_user(request, f'{updated}件のカードの再解析をリクエストしました。')
-    reanalyze.short_description = '再解析をリクエスト'
\ No newline at end of file
+    reanalyze.short_description = '再解析をリクエスト'
+
+
+@admin.register(ApiKey)
+class ApiKeyAdmin(admin.ModelAdmin):
+    list_display = ('name', 'role', 'is_active', 'created_at', 'last_used_at')
+    list_filter = ('role', 'is_active')
+    readonly_fields = ('key_hash', 'created_at', 'last_used_at')
+
+
+@admin.register(CardFeatureOverrideHistory)
+class CardFeatureOverrideHistoryAdmin(admin.ModelAdmin):
+    """追記のみの履歴なので閲覧専用"""
+    list_display = ('pronunciation', 'action', 'changed_by', 'changed_at')
+    list_filter = ('action', 'changed_by')
+    search_fields = ('pronunciation',)
+
+    def has_add_permission(self, request):
+        return False
+
+    def has_change_permission(self, request, obj=None):
+        return False
+
+    def has_delete_permission(self, request, obj=None):
+        return False
+
+
+@admin.register(CardReviewItem)
+class CardReviewItemAdmin(admin.ModelAdmin):
+    """キューへの追加はfixed_data_serverが行う"""
+    list_display = ('pronunciation', 'reason', 'status', 'priority', 'claimed_by', 'resolved_by', 'updated_at')
+    list_filter = ('reason', 'status')
+    search_fields = ('pronunciation', 'detail')
+    readonly_fields = ('created_at', 'updated_at')
+
+    def has_add_permission(self, request):
+        return False
@@ -0,0 +1,25 @@
+import hashlib
+import secrets
+
+from django.core.management.base import BaseCommand, CommandError
+from wix.models import ApiKey
+
+
+class Command(BaseCommand):
+    help = "APIキーを発行する（キーは表示時の一度しか確認できない）"
+
+    def add_arguments(self, parser):
+        parser.add_argument('name', help="利用者や用途")
+        parser.add_argument('--role', choices=['reader', 'editor'], default='reader')
+
+    def handle(self, *args, **options):
+        if ApiKey.objects.filter(name=options['name']).exists():
+            raise CommandError(f"{options['name']} は既に登録されています")
+
+        key = secrets.token_urlsafe(32)
+        ApiKey.objects.create(
+            name=options['name'],
+            key_hash=hashlib.sha256(key.encode()).hexdigest(),
+            role=options['role'],
+        )
+        self.stdout.write(f"{options['name']} ({options['role']}): {key}")
@@ -0,0 +1,29 @@
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('wix', '0022_cardfeatureoverride'),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name='ApiKey',
+            fields=[
+                ('id', models.BigAutoField(auto_created=True, primary_key=True, serialize=False, verbose_name='ID')),
+                ('name', models.CharField(help_text='利用者や用途', max_length=100, unique=True, verbose_name='名前')),
+                ('key_hash', models.CharField(max_length=64, unique=True, verbose_name='キーのハッシュ')),
+                ('role', models.CharField(choices=[('reader', '閲覧のみ'), ('editor', '編集可')], default='reader', max_length=16, verbose_name='権限')),
+                ('is_active', models.BooleanField(default=True, verbose_name='有効')),
+                ('created_at', models.DateTimeField(auto_now_add=True, verbose_name='作成日時')),
+                ('last_used_at', models.DateTimeField(blank=True, null=True, verbose_name='最終使用日時')),
+            ],
+            options={
+                'verbose_name': 'APIキー',
+                'verbose_name_plural': 'APIキー',
+                'db_table': 'wix_api_key',
+                'ordering': ['name'],
+            },
+        ),
+    ]
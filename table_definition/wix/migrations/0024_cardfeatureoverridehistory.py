@@ -0,0 +1,37 @@
+import django.db.models.deletion
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('wix', '0023_apikey'),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name='CardFeatureOverrideHistory',
+            fields=[
+                ('id', models.BigAutoField(auto_created=True, primary_key=True, serialize=False, verbose_name='ID')),
+                ('pronunciation', models.CharField(db_index=True, max_length=200, verbose_name='読み方')),
+                ('action', models.CharField(choices=[('create', '作成'), ('update', '更新'), ('delete', '削除'), ('revert', '差し戻し')], max_length=16, verbose_name='操作')),
+                ('old_bits1', models.BigIntegerField(blank=True, null=True, verbose_name='変更前の効果1群')),
+                ('old_bits2', models.BigIntegerField(blank=True, null=True, verbose_name='変更前の効果2群')),
+                ('old_burst_bits', models.BigIntegerField(blank=True, null=True, verbose_name='変更前のライフバースト効果')),
+                ('old_note', models.TextField(blank=True, null=True, verbose_name='変更前のメモ')),
+                ('new_bits1', models.BigIntegerField(blank=True, null=True, verbose_name='変更後の効果1群')),
+                ('new_bits2', models.BigIntegerField(blank=True, null=True, verbose_name='変更後の効果2群')),
+                ('new_burst_bits', models.BigIntegerField(blank=True, null=True, verbose_name='変更後のライフバースト効果')),
+                ('new_note', models.TextField(blank=True, null=True, verbose_name='変更後のメモ')),
+                ('changed_by', models.CharField(help_text='APIキーの名前（認証無効時は anonymous）', max_length=100, verbose_name='変更者')),
+                ('changed_at', models.DateTimeField(auto_now_add=True, verbose_name='変更日時')),
+                ('reverted_from', models.ForeignKey(blank=True, help_text='差し戻しの場合、取り消した履歴', null=True, on_delete=django.db.models.deletion.PROTECT, related_name='reverts', to='wix.cardfeatureoverridehistory', verbose_name='差し戻し元')),
+            ],
+            options={
+                'verbose_name': 'カードフィーチャー修正履歴',
+                'verbose_name_plural': 'カードフィーチャー修正履歴',
+                'db_table': 'wix_card_feature_override_history',
+                'ordering': ['-changed_at', '-id'],
+            },
+        ),
+    ]
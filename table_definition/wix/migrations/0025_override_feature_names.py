@@ -0,0 +1,248 @@
+"""CardFeatureOverrideをビット値からフィーチャーの列挙子名で保存するように変更する
+
+ビット位置（define_features! の bit_shift）を付け替えると保存済みのビット値の意味が変わってしまうため、
+列挙子名で保存し、ビット値は読み込み時に求める。
+既存の行はこのマイグレーション作成時点の shared/feature/src/feature.rs の定義で変換する。
+"""
+
+from django.db import migrations, models
+
+# (列挙子名, bit_shift1, bit_shift2)  shared/feature/src/feature.rs の define_features! から転記
+CARD_FEATURES = [
+    ('DoubleCrush', 1, 0),
+    ('DiscardOpponent', 3, 0),
+    ('RandomDiscard', 4, 0),
+    ('Draw', 5, 0),
+    ('Assassin', 6, 0),
+    ('Freeze', 7, 0),
+    ('Drop', 8, 0),
+    ('OnDrop', 9, 0),
+    ('OnRefresh', 10, 0),
+    ('Lancer', 11, 0),
+    ('SLancer', 12, 0),
+    ('RemoveSigni', 13, 0),
+    ('NonAttackable', 14, 0),
+    ('Down', 15, 0),
+    ('Up', 16, 0),
+    ('Charge', 17, 0),
+    ('EnerAttack', 18, 0),
+    ('Trash', 19, 0),
+    ('EnerOffensive', 20, 0),
+    ('PowerUp', 21, 0),
+    ('PowerDown', 22, 0),
+    ('Bounce', 23, 0),
+    ('DeckBounce', 24, 0),
+    ('Salvage', 25, 0),
+    ('LifeBurst', 26, 0),
+    ('Shadow', 27, 0),
+    ('Invulnerable', 28, 0),
+    ('OnSpell', 29, 0),
+    ('OnArts', 31, 0),
+    ('OnBanish', 32, 0),
+    ('Banish', 33, 0),
+    ('Guard', 34, 0),
+    ('OnGuard', 35, 0),
+    ('AttackNoEffect', 36, 0),
+    ('OnTouch', 37, 0),
+    ('Awake', 38, 0),
+    ('Exceed', 39, 0),
+    ('OnExceed', 40, 0),
+    ('AddLife', 41, 0),
+    ('OnBurst', 42, 0),
+    ('LifeTrash', 43, 0),
+    ('LifeCrush', 44, 0),
+    ('Damage', 45, 0),
+    ('OnLifeCrush', 46, 0),
+    ('Position', 47, 0),
+    ('Vanilla', 48, 0),
+    ('TopSet', 50, 0),
+    ('BottomCheck', 51, 0),
+    ('Barrier', 52, 0),
+    ('LrigTrash', 54, 0),
+    ('Charm', 55, 0),
+    ('Craft', 56, 0),
+    ('Acce', 57, 0),
+    ('Rise', 58, 0),
+    ('Recollect', 59, 0),
+    ('SeekTop', 60, 0),
+    ('EraseSkill', 61, 0),
+    ('CancelDamage', 0, 1),
+    ('Reanimate', 0, 2),
+    ('AdditionalAttack', 0, 3),
+    ('UnGuardable', 0, 4),
+    ('SalvageSpell', 0, 5),
+    ('BanishOnAttack', 0, 6),
+    ('ShootLike', 0, 7),
+    ('LimitSigni', 0, 8),
+    ('FreeSpell', 0, 9),
+    ('DualColorEner', 0, 10),
+    ('GainCoin', 0, 11),
+    ('BetCoin', 0, 12),
+    ('HandCost', 0, 13),
+    ('RligDownCost', 0, 14),
+    ('Inherit', 0, 15),
+    ('PreventGrowCost', 0, 16),
+    ('PutSigniDefense', 0, 17),
+    ('PutSigniOffense', 0, 18),
+    ('Harmony', 0, 19),
+    ('MagicBox', 0, 20),
+    ('Virus', 0, 21),
+    ('FreeArts', 0, 22),
+    ('EnhanceLimit', 0, 23),
+    ('Soul', 0, 24),
+    ('Pripara', 0, 25),
+    ('Nijisanji', 0, 26),
+    ('Dissona', 0, 27),
+    ('Denonbu', 0, 28),
+    ('BlueArchive', 0, 29),
+    ('Tax', 0, 30),
+]
+
+# (列挙子名, bit_shift)  define_burst_features! から転記
+BURST_FEATURES = [
+    ('Defend1', 0),
+    ('Defend2', 1),
+    ('BlockLrig', 2),
+    ('BlockSigni', 3),
+    ('OffenciveDefend', 4),
+    ('Draw', 5),
+    ('Guard', 6),
+    ('Salvage', 7),
+    ('Charge', 8),
+    ('Search', 9),
+    ('Freeze', 10),
+    ('Discard', 11),
+    ('Heal', 12),
+    ('EraseSkill', 13),
+    ('Others', 14),
+]
+
+
+def bits_to_names(bits1, bits2, burst_bits):
+    """shift2が0のフィーチャーは1群、それ以外は2群のビットで判定する（CardFeature::from_bits と同じ）"""
+    features = [
+        name for name, shift1, shift2 in CARD_FEATURES
+        if (bits2 >> shift2 & 1 if shift2 else bits1 >> shift1 & 1)
+    ]
+    burst_features = [name for name, shift in BURST_FEATURES if burst_bits >> shift & 1]
+    return features, burst_features
+
+
+def names_to_bits(features, burst_features):
+    bits1 = bits2 = burst_bits = 0
+    for name, shift1, shift2 in CARD_FEATURES:
+        if name in features:
+            bits1 |= 1 << shift1
+            bits2 |= 1 << shift2
+    for name, shift in BURST_FEATURES:
+        if name in burst_features:
+            burst_bits |= 1 << shift
+    return bits1, bits2, burst_bits
+
+
+def forwards(apps, schema_editor):
+    CardFeatureOverride = apps.get_model('wix', 'CardFeatureOverride')
+    for override in CardFeatureOverride.objects.all():
+        override.features, override.burst_features = bits_to_names(
+            override.fixed_bits1, override.fixed_bits2, override.fixed_burst_bits
+        )
+        override.save(update_fields=['features', 'burst_features'])
+
+    History = apps.get_model('wix', 'CardFeatureOverrideHistory')
+    for entry in History.objects.all():
+        if entry.old_bits1 is not None:
+            entry.old_features, entry.old_burst_features = bits_to_names(
+                entry.old_bits1, entry.old_bits2, entry.old_burst_bits
+            )
+        if entry.new_bits1 is not None:
+            entry.new_features, entry.new_burst_features = bits_to_names(
+                entry.new_bits1, entry.new_bits2, entry.new_burst_bits
+            )
+        entry.save(update_fields=['old_features', 'old_burst_features', 'new_features', 'new_burst_features'])
+
+
+def backwards(apps, schema_editor):
+    CardFeatureOverride = apps.get_model('wix', 'CardFeatureOverride')
+    for override in CardFeatureOverride.objects.all():
+        override.fixed_bits1, override.fixed_bits2, override.fixed_burst_bits = names_to_bits(
+            override.features, override.burst_features
+        )
+        override.save(update_fields=['fixed_bits1', 'fixed_bits2', 'fixed_burst_bits'])
+
+    History = apps.get_model('wix', 'CardFeatureOverrideHistory')
+    for entry in History.objects.all():
+        if entry.old_features is not None:
+            entry.old_bits1, entry.old_bits2, entry.old_burst_bits = names_to_bits(
+                entry.old_features, entry.old_burst_features or []
+            )
+        if entry.new_features is not None:
+            entry.new_bits1, entry.new_bits2, entry.new_burst_bits = names_to_bits(
+                entry.new_features, entry.new_burst_features or []
+            )
+        entry.save(update_fields=['old_bits1', 'old_bits2', 'old_burst_bits', 'new_bits1', 'new_bits2', 'new_burst_bits'])
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('wix', '0024_cardfeatureoverridehistory'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='cardfeatureoverride',
+            name='features',
+            field=models.JSONField(default=list, help_text='手動で修正されたCardFeatureの列挙子名のリスト（ビット値は読み込み時に求める）', verbose_name='修正済み効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverride',
+            name='burst_features',
+            field=models.JSONField(default=list, help_text='手動で修正されたBurstFeatureの列挙子名のリスト', verbose_name='修正済みライフバースト効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='old_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更前の効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='old_burst_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更前のライフバースト効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='new_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更後の効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='new_burst_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更後のライフバースト効果'),
+        ),
+        # 逆方向で列を戻すときに既存行があっても追加できるよう、削除前に既定値を付けておく
+        migrations.AlterField(
+            model_name='cardfeatureoverride',
+            name='fixed_bits1',
+            field=models.BigIntegerField(default=0, verbose_name='修正済み効果1群'),
+        ),
+        migrations.AlterField(
+            model_name='cardfeatureoverride',
+            name='fixed_bits2',
+            field=models.BigIntegerField(default=0, verbose_name='修正済み効果2群'),
+        ),
+        migrations.AlterField(
+            model_name='cardfeatureoverride',
+            name='fixed_burst_bits',
+            field=models.BigIntegerField(default=0, verbose_name='修正済みライフバースト効果'),
+        ),
+        migrations.RunPython(forwards, backwards),
+        migrations.RemoveField(model_name='cardfeatureoverride', name='fixed_bits1'),
+        migrations.RemoveField(model_name='cardfeatureoverride', name='fixed_bits2'),
+        migrations.RemoveField(model_name='cardfeatureoverride', name='fixed_burst_bits'),
+        migrations.RemoveField(model_name='cardfeatureoverridehistory', name='old_bits1'),
+        migrations.RemoveField(model_name='cardfeatureoverridehistory', name='old_bits2'),
+        migrations.RemoveField(model_name='cardfeatureoverridehistory', name='old_burst_bits'),
+        migrations.RemoveField(model_name='cardfeatureoverridehistory', name='new_bits1'),
+        migrations.RemoveField(model_name='cardfeatureoverridehistory', name='new_bits2'),
+        migrations.RemoveField(model_name='cardfeatureoverridehistory', name='new_burst_bits'),
+    ]
@@ -0,0 +1,71 @@
+"""CardFeatureOverrideに部分修正（ルールベースの結果への追加・削除）を追加する
+
+既存の修正はこれまでどおり置き換え（replace）として扱う。
+"""
+
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('wix', '0025_override_feature_names'),
+    ]
+
+    operations = [
+        migrations.AddField(
+            model_name='cardfeatureoverride',
+            name='mode',
+            field=models.CharField(choices=[('replace', '置き換え'), ('partial', '部分修正')], default='replace', help_text='置き換え: ルールベースの結果を修正済み効果で置き換える / 部分修正: ルールベースの結果に追加・削除する', max_length=16, verbose_name='修正の種類'),
+        ),
+        migrations.AlterField(
+            model_name='cardfeatureoverride',
+            name='features',
+            field=models.JSONField(default=list, help_text='CardFeatureの列挙子名のリスト（置き換えなら修正後のすべて、部分修正なら追加するもの。ビット値は読み込み時に求める）', verbose_name='修正済み効果'),
+        ),
+        migrations.AlterField(
+            model_name='cardfeatureoverride',
+            name='burst_features',
+            field=models.JSONField(default=list, help_text='BurstFeatureの列挙子名のリスト（置き換えなら修正後のすべて、部分修正なら追加するもの）', verbose_name='修正済みライフバースト効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverride',
+            name='removed_features',
+            field=models.JSONField(blank=True, default=list, help_text='部分修正でルールベースの結果から削除するCardFeatureの列挙子名のリスト', verbose_name='削除する効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverride',
+            name='removed_burst_features',
+            field=models.JSONField(blank=True, default=list, help_text='部分修正でルールベースの結果から削除するBurstFeatureの列挙子名のリスト', verbose_name='削除するライフバースト効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='old_mode',
+            field=models.CharField(blank=True, max_length=16, null=True, verbose_name='変更前の修正の種類'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='old_removed_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更前の削除する効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='old_removed_burst_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更前の削除するライフバースト効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='new_mode',
+            field=models.CharField(blank=True, max_length=16, null=True, verbose_name='変更後の修正の種類'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='new_removed_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更後の削除する効果'),
+        ),
+        migrations.AddField(
+            model_name='cardfeatureoverridehistory',
+            name='new_removed_burst_features',
+            field=models.JSONField(blank=True, null=True, verbose_name='変更後の削除するライフバースト効果'),
+        ),
+    ]
@@ -0,0 +1,36 @@
+from django.db import migrations, models
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('wix', '0026_override_partial_mode'),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name='CardReviewItem',
+            fields=[
+                ('id', models.BigAutoField(auto_created=True, primary_key=True, serialize=False, verbose_name='ID')),
+                ('pronunciation', models.CharField(db_index=True, max_length=200, verbose_name='読み方')),
+                ('reason', models.CharField(choices=[('no_features', 'フィーチャーなし'), ('type_conflict', 'カード種類との矛盾'), ('detection_changed', '検出結果の変化'), ('low_coverage', 'パターンカバレッジ不足')], max_length=32, verbose_name='理由')),
+                ('detail', models.TextField(blank=True, default='', verbose_name='詳細')),
+                ('priority', models.IntegerField(default=0, help_text='大きいほど先に確認する', verbose_name='優先度')),
+                ('status', models.CharField(choices=[('pending', '確認待ち'), ('claimed', '担当中'), ('approved', '承認'), ('rejected', '却下')], default='pending', max_length=16, verbose_name='状態')),
+                ('claimed_by', models.CharField(blank=True, max_length=100, null=True, verbose_name='担当者')),
+                ('claimed_at', models.DateTimeField(blank=True, null=True, verbose_name='担当日時')),
+                ('resolved_by', models.CharField(blank=True, max_length=100, null=True, verbose_name='対応者')),
+                ('resolved_at', models.DateTimeField(blank=True, null=True, verbose_name='対応日時')),
+                ('resolution_note', models.TextField(blank=True, null=True, verbose_name='対応メモ')),
+                ('created_at', models.DateTimeField(auto_now_add=True, verbose_name='作成日時')),
+                ('updated_at', models.DateTimeField(auto_now=True, verbose_name='更新日時')),
+            ],
+            options={
+                'verbose_name': 'フィーチャー確認キュー',
+                'verbose_name_plural': 'フィーチャー確認キュー',
+                'db_table': 'wix_card_review_item',
+                'ordering': ['-priority', 'id'],
+                'unique_together': {('pronunciation', 'reason')},
+            },
+        ),
+    ]
@@ -0,0 +1,56 @@
+from django.db import migrations, models
+
+VERSIONED_TABLES = ['wix_card', 'wix_product']
+
+BUMP_FUNCTION = """
+CREATE OR REPLACE FUNCTION wix_bump_data_version() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO wix_data_version (table_name, version) VALUES (TG_TABLE_NAME, 1)
+    ON CONFLICT (table_name) DO UPDATE SET version = wix_data_version.version + 1;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+"""
+
+
+def trigger_sql(table):
+    return (
+        f"INSERT INTO wix_data_version (table_name, version) VALUES ('{table}', 0) "
+        f"ON CONFLICT (table_name) DO NOTHING; "
+        f"CREATE TRIGGER {table}_data_version "
+        f"AFTER INSERT OR UPDATE OR DELETE OR TRUNCATE ON {table} "
+        f"FOR EACH STATEMENT EXECUTE FUNCTION wix_bump_data_version();"
+    )
+
+
+class Migration(migrations.Migration):
+
+    dependencies = [
+        ('wix', '0027_cardreviewitem'),
+    ]
+
+    operations = [
+        migrations.CreateModel(
+            name='DataVersion',
+            fields=[
+                ('table_name', models.CharField(max_length=64, primary_key=True, serialize=False, verbose_name='テーブル名')),
+                ('version', models.BigIntegerField(default=0, verbose_name='バージョン')),
+            ],
+            options={
+                'verbose_name': 'データバージョン',
+                'verbose_name_plural': 'データバージョン',
+                'db_table': 'wix_data_version',
+            },
+        ),
+        migrations.RunSQL(
+            BUMP_FUNCTION,
+            reverse_sql="DROP FUNCTION IF EXISTS wix_bump_data_version();"
+        ),
+    ] + [
+        # 書き込みのあった文ごとにバージョンを上げる（トランザクションと一緒にコミットされる）
+        migrations.RunSQL(
+            trigger_sql(table),
+            reverse_sql=f"DROP TRIGGER IF EXISTS {table}_data_version ON {table};"
+        )
+        for table in VERSIONED_TABLES
+    ]
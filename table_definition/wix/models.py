@@ -225,17 +225,39 @@ class CardFeatureOverride(models.Model):
         db_index=True,
         help_text="同じ読み方のカードは全て同じフィーチャーが適用されます"
     )
-    fixed_bits1 = models.BigIntegerField(
-        verbose_name="修正済み効果1群",
-        help_text="手動で修正されたCardFeatureのビット値（1群）"
+    MODE_CHOICES = [
+        ('replace', '置き換え'),
+        ('partial', '部分修正'),
+    ]
+
+    mode = models.CharField(
+        verbose_name="修正の種類",
+        max_length=16,
+        choices=MODE_CHOICES,
+        default='replace',
+        help_text="置き換え: ルールベースの結果を修正済み効果で置き換える / 部分修正: ルールベースの結果に追加・削除する"
     )
-    fixed_bits2 = models.BigIntegerField(
-        verbose_name="修正済み効果2群",
-        help_text="手動で修正されたCardFeatureのビット値（2群）"
+    features = models.JSONField(
+        verbose_name="修正済み効果",
+        default=list,
+        help_text="CardFeatureの列挙子名のリスト（置き換えなら修正後のすべて、部分修正なら追加するもの。ビット値は読み込み時に求める）"
     )
-    fixed_burst_bits = models.BigIntegerField(
+    burst_features = models.JSONField(
         verbose_name="修正済みライフバースト効果",
-        help_text="手動で修正されたBurstFeatureのビット値"
+        default=list,
+        help_text="BurstFeatureの列挙子名のリスト（置き換えなら修正後のすべて、部分修正なら追加するもの）"
+    )
+    removed_features = models.JSONField(
+        verbose_name="削除する効果",
+        default=list,
+        blank=True,
+        help_text="部分修正でルールベースの結果から削除するCardFeatureの列挙子名のリスト"
+    )
+    removed_burst_features = models.JSONField(
+        verbose_name="削除するライフバースト効果",
+        default=list,
+        blank=True,
+        help_text="部分修正でルールベースの結果から削除するBurstFeatureの列挙子名のリスト"
     )
     created_at = models.DateTimeField(verbose_name="作成日時", auto_now_add=True)
     updated_at = models.DateTimeField(verbose_name="更新日時", auto_now=True)
@@ -252,4 +274,141 @@ class CardFeatureOverride(models.Model):
         ordering = ['pronunciation']
     
     def __str__(self):
-        return f"{self.pronunciation} - 修正済み"
+        return f"{self.pronunciation} - {self.get_mode_display()}"
+
+
+class CardFeatureOverrideHistory(models.Model):
+    """CardFeatureOverrideの変更履歴（追記のみ）
+
+    fixed_data_serverでの作成・更新・削除・差し戻しのたびに変更前後の値を1行記録する。
+    作成時は変更前、削除時は変更後の値が空になる。
+    """
+
+    ACTION_CHOICES = [
+        ('create', '作成'),
+        ('update', '更新'),
+        ('delete', '削除'),
+        ('revert', '差し戻し'),
+    ]
+
+    pronunciation = models.CharField(verbose_name="読み方", max_length=200, db_index=True)
+    action = models.CharField(verbose_name="操作", max_length=16, choices=ACTION_CHOICES)
+    old_mode = models.CharField(verbose_name="変更前の修正の種類", max_length=16, null=True, blank=True)
+    old_features = models.JSONField(verbose_name="変更前の効果", null=True, blank=True)
+    old_burst_features = models.JSONField(verbose_name="変更前のライフバースト効果", null=True, blank=True)
+    old_removed_features = models.JSONField(verbose_name="変更前の削除する効果", null=True, blank=True)
+    old_removed_burst_features = models.JSONField(verbose_name="変更前の削除するライフバースト効果", null=True, blank=True)
+    old_note = models.TextField(verbose_name="変更前のメモ", null=True, blank=True)
+    new_mode = models.CharField(verbose_name="変更後の修正の種類", max_length=16, null=True, blank=True)
+    new_features = models.JSONField(verbose_name="変更後の効果", null=True, blank=True)
+    new_burst_features = models.JSONField(verbose_name="変更後のライフバースト効果", null=True, blank=True)
+    new_removed_features = models.JSONField(verbose_name="変更後の削除する効果", null=True, blank=True)
+    new_removed_burst_features = models.JSONField(verbose_name="変更後の削除するライフバースト効果", null=True, blank=True)
+    new_note = models.TextField(verbose_name="変更後のメモ", null=True, blank=True)
+    changed_by = models.CharField(verbose_name="変更者", max_length=100, help_text="APIキーの名前（認証無効時は anonymous）")
+    changed_at = models.DateTimeField(verbose_name="変更日時", auto_now_add=True)
+    reverted_from = models.ForeignKey(
+        'self', verbose_name="差し戻し元", null=True, blank=True, on_delete=models.PROTECT,
+        related_name='reverts', help_text="差し戻しの場合、取り消した履歴"
+    )
+
+    class Meta:
+        verbose_name = "カードフィーチャー修正履歴"
+        verbose_name_plural = "カードフィーチャー修正履歴"
+        db_table = 'wix_card_feature_override_history'
+        ordering = ['-changed_at', '-id']
+
+    def __str__(self):
+        return f"{self.pronunciation} - {self.get_action_display()} ({self.changed_by})"
+
+
+class CardReviewItem(models.Model):
+    """フィーチャーの目視確認待ちキュー
+
+    fixed_data_serverがヒューリスティクス（フィーチャーなし・カード種類との矛盾・ルール変更による
+    検出結果の変化・パターンカバレッジ不足）で疑わしいカードを積み、メンテナンス画面で担当・承認・却下する。
+    同じ読みと理由の組は1件にまとめ、内容が変われば対応済みのものも確認待ちに戻す。
+    """
+
+    REASON_CHOICES = [
+        ('no_features', 'フィーチャーなし'),
+        ('type_conflict', 'カード種類との矛盾'),
+        ('detection_changed', '検出結果の変化'),
+        ('low_coverage', 'パターンカバレッジ不足'),
+    ]
+
+    STATUS_CHOICES = [
+        ('pending', '確認待ち'),
+        ('claimed', '担当中'),
+        ('approved', '承認'),
+        ('rejected', '却下'),
+    ]
+
+    pronunciation = models.CharField(verbose_name="読み方", max_length=200, db_index=True)
+    reason = models.CharField(verbose_name="理由", max_length=32, choices=REASON_CHOICES)
+    detail = models.TextField(verbose_name="詳細", blank=True, default='')
+    priority = models.IntegerField(verbose_name="優先度", default=0, help_text="大きいほど先に確認する")
+    status = models.CharField(verbose_name="状態", max_length=16, choices=STATUS_CHOICES, default='pending')
+    claimed_by = models.CharField(verbose_name="担当者", max_length=100, null=True, blank=True)
+    claimed_at = models.DateTimeField(verbose_name="担当日時", null=True, blank=True)
+    resolved_by = models.CharField(verbose_name="対応者", max_length=100, null=True, blank=True)
+    resolved_at = models.DateTimeField(verbose_name="対応日時", null=True, blank=True)
+    resolution_note = models.TextField(verbose_name="対応メモ", null=True, blank=True)
+    created_at = models.DateTimeField(verbose_name="作成日時", auto_now_add=True)
+    updated_at = models.DateTimeField(verbose_name="更新日時", auto_now=True)
+
+    class Meta:
+        verbose_name = "フィーチャー確認キュー"
+        verbose_name_plural = "フィーチャー確認キュー"
+        db_table = 'wix_card_review_item'
+        unique_together = [('pronunciation', 'reason')]
+        ordering = ['-priority', 'id']
+
+    def __str__(self):
+        return f"{self.pronunciation} - {self.get_reason_display()} ({self.get_status_display()})"
+
+
+class ApiKey(models.Model):
+    """server・fixed_data_serverの管理系APIを呼ぶためのAPIキー
+
+    キー本体は保存せず、SHA-256ハッシュのみを持つ。発行は `manage.py create_api_key` で行う。
+    """
+
+    ROLE_CHOICES = [
+        ('reader', '閲覧のみ'),
+        ('editor', '編集可'),
+    ]
+
+    name = models.CharField(verbose_name="名前", max_length=100, unique=True, help_text="利用者や用途")
+    key_hash = models.CharField(verbose_name="キーのハッシュ", max_length=64, unique=True)
+    role = models.CharField(verbose_name="権限", max_length=16, choices=ROLE_CHOICES, default='reader')
+    is_active = models.BooleanField(verbose_name="有効", default=True)
+    created_at = models.DateTimeField(verbose_name="作成日時", auto_now_add=True)
+    last_used_at = models.DateTimeField(verbose_name="最終使用日時", null=True, blank=True)
+
+    class Meta:
+        verbose_name = "APIキー"
+        verbose_name_plural = "APIキー"
+        db_table = 'wix_api_key'
+        ordering = ['name']
+
+    def __str__(self):
+        return f"{self.name} ({self.role})"
+
+
+class DataVersion(models.Model):
+    """テーブルごとのデータバージョン
+
+    wix_card・wix_productへの書き込みのたびにトリガーで1増える。
+    APIサーバーは一覧キャッシュの無効化判定に使う
+    """
+    table_name = models.CharField(verbose_name="テーブル名", max_length=64, primary_key=True)
+    version = models.BigIntegerField(verbose_name="バージョン", default=0)
+
+    def __str__(self):
+        return f"{self.table_name}: {self.version}"
+
+    class Meta:
+        verbose_name = "データバージョン"
+        verbose_name_plural = "データバージョン"
+        db_table = 'wix_data_version'
@@ -3,7 +3,7 @@ use crate::components::svg_definition::{
 };
 use crate::components::{BurstTextRenderer, SkillTextRenderer};
 use crate::contexts::FilterContext;
-use crate::utils::maintenance::is_maintenance_mode;
+use crate::utils::maintenance::{encode_url_component, is_maintenance_mode};
 use datapack::CardExport;
 use leptos::prelude::*;
 
@@ -250,7 +250,7 @@ pub fn CardItem(
                                         if !pronunciation.is_empty() {
                                             view! {
                                                 <a
-                                                    href=format!("/edit/{}", pronunciation)
+                                                    href=format!("/edit/{}", encode_url_component(&pronunciation))
                                                     class="bg-yellow-100 hover:bg-yellow-200 px-1 py-0.5 rounded text-yellow-800 hover:text-yellow-900 transition-colors cursor-pointer text-xs"
                                                     title="フィーチャーを編集"
                                                 >
@@ -430,7 +430,7 @@ pub fn CardItem(
                                                 if !pronunciation.is_empty() {
                                                     view! {
                                                         <a
-                                                            href=format!("/edit/{}", pronunciation)
+                                                            href=format!("/edit/{}", encode_url_component(&pronunciation))
                                                             class="bg-yellow-100 hover:bg-yellow-200 px-2 py-1 rounded text-yellow-800 hover:text-yellow-900 transition-colors cursor-pointer text-xs"
                                                             title="フィーチャーを編集"
                                                         >
@@ -9,7 +9,7 @@ mod types;
 mod utils;
 
 use components::SvgDefinition;
-use pages::{CardDetailPage, CardPage, CreditsPage, HomePage, FeatureEditPage};
+use pages::{CardDetailPage, CardPage, CreditsPage, HomePage, FeatureEditPage, ReviewQueuePage};
 
 #[component]
 fn App() -> impl IntoView {
@@ -24,6 +24,7 @@ fn App() -> impl IntoView {
                     <Route path=path!("/card/:param") view=CardDetailPage/>
                     <Route path=path!("/credits") view=CreditsPage/>
                     <Route path=path!("/edit/:pronunciation") view=FeatureEditPage/>
+                    <Route path=path!("/review") view=ReviewQueuePage/>
                 </Routes>
             </div>
         </Router>
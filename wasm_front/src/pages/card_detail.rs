@@ -1,5 +1,5 @@
 use crate::components::card_item::{CardItem, ViewMode};
-use crate::utils::maintenance::is_maintenance_mode;
+use crate::utils::maintenance::{encode_url_component, is_maintenance_mode};
 use datapack::CardExport;
 use leptos::prelude::*;
 use leptos_router::hooks::use_params_map;
@@ -146,7 +146,7 @@ pub fn CardDetailPage() -> impl IntoView {
                                         if !pronunciation.is_empty() {
                                             view! {
                                                 <a
-                                                    href=format!("/edit/{}", pronunciation)
+                                                    href=format!("/edit/{}", encode_url_component(&pronunciation))
                                                     class="inline-flex items-center px-3 py-1 bg-yellow-500 text-white text-sm rounded hover:bg-yellow-600 transition-colors"
                                                 >
                                                     "🛠️ Edit Features"
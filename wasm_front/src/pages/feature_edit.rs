@@ -5,7 +5,10 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
 use web_sys::{RequestInit, RequestMode, Response};
 
-use crate::utils::maintenance::{get_fixed_data_server_url, is_maintenance_mode};
+use crate::utils::maintenance::{
+    encode_url_component, get_fixed_data_server_url, is_maintenance_mode,
+    set_fixed_data_auth_header,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct FeatureOverride {
@@ -23,6 +26,33 @@ struct OverrideResponse {
     note: Option<String>,
 }
 
+/// fixed_data_serverの確認キューの項目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct ReviewItem {
+    pub(super) id: i64,
+    pub(super) pronunciation: String,
+    pub(super) reason: String,
+    pub(super) detail: String,
+    pub(super) priority: i32,
+    pub(super) status: String,
+    pub(super) claimed_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResolveReviewRequest {
+    note: Option<String>,
+}
+
+pub(super) fn review_reason_label(reason: &str) -> &'static str {
+    match reason {
+        "no_features" => "フィーチャーなし",
+        "type_conflict" => "カード種類との矛盾",
+        "detection_changed" => "検出結果の変化",
+        "low_coverage" => "パターンカバレッジ不足",
+        _ => "その他",
+    }
+}
+
 // 実際のCardFeatureとBurstFeatureをdatapackから取得する関数
 fn get_card_features_by_category() -> Vec<(String, Vec<String>)> {
     datapack::extract_card_features_grouped()
@@ -58,7 +88,8 @@ pub fn FeatureEditPage() -> impl IntoView {
     let (loading, set_loading) = signal(false);
     let (error_message, set_error_message) = signal(None::<String>);
     let (success_message, set_success_message) = signal(None::<String>);
-
+    let (review_items, set_review_items) = signal(Vec::<ReviewItem>::new());
+    let (review_note, set_review_note) = signal(String::new());
 
     // Load on mount
     Effect::new(move |_| {
@@ -69,7 +100,7 @@ pub fn FeatureEditPage() -> impl IntoView {
         
         spawn_local(async move {
             let server_url = get_fixed_data_server_url();
-            let url = format!("{}/api/overrides/{}", server_url, pronunciation);
+            let url = format!("{}/api/overrides/{}", server_url, encode_url_component(&pronunciation));
             
             match load_override_from_api(&url).await {
                 Ok(Some(override_data)) => {
@@ -91,6 +122,13 @@ pub fn FeatureEditPage() -> impl IntoView {
                     set_error_message.set(Some(format!("読み込みエラー: {}", e)));
                 }
             }
+
+            // このカードの未対応の確認キュー
+            let review_url = format!("{}/api/review?pronunciation={}", server_url, encode_url_component(&pronunciation));
+            match load_review_items_from_api(&review_url).await {
+                Ok(items) => set_review_items.set(items),
+                Err(e) => set_error_message.set(Some(format!("確認キューの読み込みエラー: {}", e))),
+            }
         });
     });
 
@@ -140,7 +178,7 @@ pub fn FeatureEditPage() -> impl IntoView {
                 Ok(_) => {
                     set_success_message.set(Some("保存しました".to_string()));
                     // Reload to get updated data
-                    let reload_url = format!("{}/api/overrides/{}", get_fixed_data_server_url(), pronunciation);
+                    let reload_url = format!("{}/api/overrides/{}", get_fixed_data_server_url(), encode_url_component(&pronunciation));
                     match load_override_from_api(&reload_url).await {
                         Ok(Some(updated_data)) => {
                             set_current_override.set(Some(updated_data));
@@ -156,11 +194,42 @@ pub fn FeatureEditPage() -> impl IntoView {
         });
     };
 
+    // 担当・承認・却下（承認・却下は確認メモを添える）。終わったら一覧を読み直す
+    let review_action = move |id: i64, action: &'static str| {
+        set_error_message.set(None);
+        set_success_message.set(None);
+        let pronunciation = pronunciation();
+        let note = review_note.get();
+        let body = ResolveReviewRequest {
+            note: if note.trim().is_empty() { None } else { Some(note) },
+        };
+
+        spawn_local(async move {
+            let server_url = get_fixed_data_server_url();
+            let url = format!("{}/api/review/{}/{}", server_url, id, action);
+            match post_review_action_to_api(&url, &body).await {
+                Ok(_) => {
+                    if action != "claim" {
+                        set_review_note.set(String::new());
+                    }
+                    let review_url = format!("{}/api/review?pronunciation={}", server_url, encode_url_component(&pronunciation));
+                    if let Ok(items) = load_review_items_from_api(&review_url).await {
+                        set_review_items.set(items);
+                    }
+                }
+                Err(e) => {
+                    set_error_message.set(Some(format!("確認キューの更新エラー: {}", e)));
+                }
+            }
+        });
+    };
+
     view! {
         <div class="container mx-auto px-4 py-8">
             <div class="bg-blue-100 border border-blue-400 text-blue-700 px-4 py-3 rounded mb-6">
                 <h1 class="text-2xl font-bold">"フィーチャー編集モード"</h1>
                 <p>"読み方: " <span class="font-mono">{pronunciation}</span></p>
+                <a href="/review" class="text-sm underline">"確認キューの一覧へ"</a>
             </div>
 
             {move || error_message.get().map(|msg| view! {
@@ -175,6 +244,59 @@ pub fn FeatureEditPage() -> impl IntoView {
                 </div>
             })}
 
+            {move || {
+                let items = review_items.get();
+                (!items.is_empty()).then(|| view! {
+                    <div class="bg-yellow-50 border border-yellow-400 rounded-lg p-4 mb-6">
+                        <h2 class="text-lg font-bold mb-2">"確認キュー"</h2>
+                        {items.into_iter().map(|item| {
+                            let id = item.id;
+                            let status = match item.claimed_by.as_ref().filter(|_| item.status == "claimed") {
+                                Some(claimed_by) => format!("{} が担当中", claimed_by),
+                                None => "確認待ち".to_string(),
+                            };
+                            view! {
+                                <div class="border-t border-yellow-200 py-2">
+                                    <div class="flex items-center justify-between">
+                                        <div>
+                                            <span class="font-semibold">{review_reason_label(&item.reason)}</span>
+                                            <span class="ml-2 text-sm text-gray-600">{status}</span>
+                                        </div>
+                                        <div class="flex space-x-2">
+                                            <button
+                                                class="px-3 py-1 text-sm rounded border border-gray-300 bg-white hover:bg-gray-50"
+                                                on:click=move |_| review_action(id, "claim")
+                                            >
+                                                "担当"
+                                            </button>
+                                            <button
+                                                class="px-3 py-1 text-sm rounded bg-green-500 text-white hover:bg-green-600"
+                                                on:click=move |_| review_action(id, "approve")
+                                            >
+                                                "承認"
+                                            </button>
+                                            <button
+                                                class="px-3 py-1 text-sm rounded bg-red-500 text-white hover:bg-red-600"
+                                                on:click=move |_| review_action(id, "reject")
+                                            >
+                                                "却下"
+                                            </button>
+                                        </div>
+                                    </div>
+                                    <p class="text-sm text-gray-700 whitespace-pre-wrap">{item.detail}</p>
+                                </div>
+                            }
+                        }).collect::<Vec<_>>()}
+                        <input
+                            class="mt-2 w-full px-3 py-1 text-sm border border-gray-300 rounded-md"
+                            placeholder="確認メモ（承認・却下の理由など）"
+                            prop:value=move || review_note.get()
+                            on:input=move |ev| set_review_note.set(event_target_value(&ev))
+                        />
+                    </div>
+                })
+            }}
+
             <div class="grid grid-cols-1 lg:grid-cols-2 gap-6">
                 // CardFeatures
                 <div class="bg-white rounded-lg shadow p-6">
@@ -294,6 +416,7 @@ async fn load_override_from_api(url: &str) -> Result<Option<OverrideResponse>, S
 
     let request = web_sys::Request::new_with_str_and_init(url, &opts)
         .map_err(|_| "リクエスト作成エラー")?;
+    set_fixed_data_auth_header(&request)?;
 
     let window = web_sys::window().unwrap();
     let resp_value = wasm_bindgen_futures::JsFuture::from(window.fetch_with_request(&request))
@@ -334,6 +457,7 @@ async fn save_override_to_api(url: &str, data: &FeatureOverride) -> Result<(), S
 
     request.headers().set("Content-Type", "application/json")
         .map_err(|_| "ヘッダー設定エラー")?;
+    set_fixed_data_auth_header(&request)?;
 
     let window = web_sys::window().unwrap();
     let resp_value = wasm_bindgen_futures::JsFuture::from(window.fetch_with_request(&request))
@@ -347,4 +471,73 @@ async fn save_override_to_api(url: &str, data: &FeatureOverride) -> Result<(), S
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+pub(super) async fn load_review_items_from_api(url: &str) -> Result<Vec<ReviewItem>, String> {
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
+
+    let request = web_sys::Request::new_with_str_and_init(url, &opts)
+        .map_err(|_| "リクエスト作成エラー")?;
+    set_fixed_data_auth_header(&request)?;
+
+    let window = web_sys::window().unwrap();
+    let resp_value = wasm_bindgen_futures::JsFuture::from(window.fetch_with_request(&request))
+        .await
+        .map_err(|_| "ネットワークエラー")?;
+
+    let resp: Response = resp_value.dyn_into().unwrap();
+
+    if !resp.ok() {
+        return Err(format!("HTTPエラー: {}", resp.status()));
+    }
+
+    let text = wasm_bindgen_futures::JsFuture::from(resp.text().unwrap())
+        .await
+        .map_err(|_| "レスポンス読み取りエラー")?;
+
+    serde_json::from_str(&text.as_string().unwrap())
+        .map_err(|e| format!("JSONパースエラー: {}", e))
+}
+
+async fn post_review_action_to_api(url: &str, data: &ResolveReviewRequest) -> Result<(), String> {
+    let json = serde_json::to_string(data)
+        .map_err(|e| format!("JSONシリアライズエラー: {}", e))?;
+
+    let opts = RequestInit::new();
+    opts.set_method("POST");
+    opts.set_mode(RequestMode::Cors);
+    opts.set_body(&wasm_bindgen::JsValue::from_str(&json));
+
+    let request = web_sys::Request::new_with_str_and_init(url, &opts)
+        .map_err(|_| "リクエスト作成エラー")?;
+
+    request.headers().set("Content-Type", "application/json")
+        .map_err(|_| "ヘッダー設定エラー")?;
+    set_fixed_data_auth_header(&request)?;
+
+    let window = web_sys::window().unwrap();
+    let resp_value = wasm_bindgen_futures::JsFuture::from(window.fetch_with_request(&request))
+        .await
+        .map_err(|_| "ネットワークエラー")?;
+
+    let resp: Response = resp_value.dyn_into().unwrap();
+
+    // 他の人が担当中・対応済みなどはサーバーのメッセージをそのまま見せる
+    if !resp.ok() {
+        let status = resp.status();
+        let message = match resp.text() {
+            Ok(promise) => wasm_bindgen_futures::JsFuture::from(promise)
+                .await
+                .ok()
+                .and_then(|text| text.as_string())
+                .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+                .and_then(|body| body.get("message").and_then(|m| m.as_str()).map(str::to_string)),
+            Err(_) => None,
+        };
+        return Err(message.unwrap_or_else(|| format!("HTTPエラー: {}", status)));
+    }
+
+    Ok(())
+}
@@ -3,9 +3,11 @@ mod card_detail;
 mod credits;
 mod home;
 mod feature_edit;
+mod review_queue;
 
 pub use card::CardPage;
 pub use card_detail::CardDetailPage;
 pub use credits::CreditsPage;
 pub use home::HomePage;
 pub use feature_edit::FeatureEditPage;
+pub use review_queue::ReviewQueuePage;
@@ -0,0 +1,108 @@
+use leptos::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+use super::feature_edit::{load_review_items_from_api, review_reason_label, ReviewItem};
+use crate::utils::maintenance::{
+    encode_url_component, get_fixed_data_server_url, is_maintenance_mode,
+};
+
+/// 確認キューの未対応の項目の一覧（優先度順）。各項目からフィーチャー編集へ移る
+#[component]
+pub fn ReviewQueuePage() -> impl IntoView {
+    if !is_maintenance_mode() {
+        return view! {
+            <div class="container mx-auto px-4 py-8">
+                <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded">
+                    <h2 class="text-xl font-bold">"確認キューは無効です"</h2>
+                    <p>"この機能はローカル環境でのみ利用可能です。"</p>
+                </div>
+            </div>
+        }.into_any();
+    }
+
+    let (reason, set_reason) = signal(String::new());
+    let (items, set_items) = signal(Vec::<ReviewItem>::new());
+    let (loading, set_loading) = signal(false);
+    let (error_message, set_error_message) = signal(None::<String>);
+
+    // 理由を変えるたびに読み直す
+    Effect::new(move |_| {
+        let reason = reason.get();
+        set_loading.set(true);
+        set_error_message.set(None);
+
+        spawn_local(async move {
+            let mut url = format!("{}/api/review", get_fixed_data_server_url());
+            if !reason.is_empty() {
+                url.push_str(&format!("?reason={}", encode_url_component(&reason)));
+            }
+            match load_review_items_from_api(&url).await {
+                Ok(loaded) => set_items.set(loaded),
+                Err(e) => set_error_message.set(Some(format!("確認キューの読み込みエラー: {}", e))),
+            }
+            set_loading.set(false);
+        });
+    });
+
+    view! {
+        <div class="container mx-auto px-4 py-8">
+            <div class="bg-yellow-50 border border-yellow-400 text-yellow-800 px-4 py-3 rounded mb-6 flex items-center justify-between">
+                <h1 class="text-2xl font-bold">"確認キュー"</h1>
+                <select
+                    class="px-3 py-1 text-sm border border-gray-300 rounded-md bg-white"
+                    on:change=move |ev| set_reason.set(event_target_value(&ev))
+                >
+                    <option value="">"すべての理由"</option>
+                    {["no_features", "type_conflict", "detection_changed", "low_coverage"]
+                        .into_iter()
+                        .map(|reason| view! {
+                            <option value=reason>{review_reason_label(reason)}</option>
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+            </div>
+
+            {move || error_message.get().map(|msg| view! {
+                <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded mb-4">
+                    {msg}
+                </div>
+            })}
+
+            {move || {
+                if loading.get() {
+                    return view! { <p class="text-gray-600">"読み込み中..."</p> }.into_any();
+                }
+                let items = items.get();
+                if items.is_empty() {
+                    return view! { <p class="text-gray-600">"未対応の項目はありません"</p> }.into_any();
+                }
+                view! {
+                    <div class="bg-white rounded-lg shadow divide-y">
+                        {items.into_iter().map(|item| {
+                            let status = match item.claimed_by.as_ref().filter(|_| item.status == "claimed") {
+                                Some(claimed_by) => format!("{} が担当中", claimed_by),
+                                None => "確認待ち".to_string(),
+                            };
+                            view! {
+                                <a
+                                    href=format!("/edit/{}", encode_url_component(&item.pronunciation))
+                                    class="block px-4 py-3 hover:bg-gray-50"
+                                >
+                                    <div class="flex items-center justify-between">
+                                        <div>
+                                            <span class="font-mono">{item.pronunciation.clone()}</span>
+                                            <span class="ml-2 font-semibold">{review_reason_label(&item.reason)}</span>
+                                            <span class="ml-2 text-sm text-gray-600">{status}</span>
+                                        </div>
+                                        <span class="text-sm text-gray-500">{format!("優先度 {}", item.priority)}</span>
+                                    </div>
+                                    <p class="text-sm text-gray-700 whitespace-pre-wrap">{item.detail}</p>
+                                </a>
+                            }
+                        }).collect::<Vec<_>>()}
+                    </div>
+                }.into_any()
+            }}
+        </div>
+    }.into_any()
+}
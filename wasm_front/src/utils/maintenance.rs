@@ -20,4 +20,31 @@ pub fn get_fixed_data_server_url() -> String {
         // Production環境では無効
         "".to_string()
     }
-}
\ No newline at end of file
+}
+/// fixed_data_serverのAPIキー（認証有効時に必要）
+///
+/// ブラウザの開発者ツールで `localStorage.setItem("fixed_data_api_key", "<key>")` して設定する
+pub fn get_fixed_data_api_key() -> Option<String> {
+    window()?
+        .local_storage()
+        .ok()??
+        .get_item("fixed_data_api_key")
+        .ok()?
+        .filter(|key| !key.is_empty())
+}
+
+/// APIキーが設定されていればAuthorizationヘッダーを付ける
+pub fn set_fixed_data_auth_header(request: &web_sys::Request) -> Result<(), String> {
+    if let Some(key) = get_fixed_data_api_key() {
+        request
+            .headers()
+            .set("Authorization", &format!("Bearer {}", key))
+            .map_err(|_| "ヘッダー設定エラー".to_string())?;
+    }
+    Ok(())
+}
+
+/// URLのパス・クエリに入れる値をエンコードする（読みには `&` `#` `+` や空白が入ることがある）
+pub fn encode_url_component(value: &str) -> String {
+    js_sys::encode_uri_component(value).into()
+}